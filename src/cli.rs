@@ -1,6 +1,7 @@
 //! # The Command-Line Arguments
 
-use crate::constants::{DEFAULT_MAX_CONNECTIONS, DEFAULT_PORT};
+use crate::constants::{DEFAULT_DATABASES, DEFAULT_MAX_CONNECTIONS, DEFAULT_PORT};
+use clap::builder::TypedValueParser;
 use clap::Parser;
 
 #[derive(Debug, Parser)]
@@ -14,4 +15,12 @@ pub struct Args {
     /// Maximum number of allowed parallel connections from clients
     #[arg(long, default_value_t = DEFAULT_MAX_CONNECTIONS)]
     pub max_conn: usize,
+
+    /// Number of logical databases, numbered from 0
+    #[arg(
+        long,
+        default_value_t = DEFAULT_DATABASES,
+        value_parser = clap::value_parser!(u16).range(1..).map(usize::from)
+    )]
+    pub databases: usize,
 }
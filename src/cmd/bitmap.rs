@@ -0,0 +1,960 @@
+//! # Bitmap Commands
+//!
+//! Handlers for the commands of the [bitmap](https://redis.io/docs/latest/commands/?group=bitmap) group
+//!
+//! - [Redis bitmaps](https://redis.io/docs/latest/develop/data-types/bitmaps/)
+//!
+//! Bitmaps are not an actual data type, but a set of bit-oriented operations defined on the string type,
+//! which is binary-safe. Bits are numbered from the most significant bit of the first byte,
+//! so bit 0 is the highest bit of byte 0, and bit 8 is the highest bit of byte 1.
+//!
+//! Strings are limited to 512 MB, so the maximum bit offset is 2^32-1.
+
+use crate::cmd::string::{check_string_length, string_range};
+use crate::cmd::{get_live, get_string, parse_integer, read_live, write_lock};
+use crate::conn::Client;
+use crate::constants::PROTO_MAX_BULK_LEN;
+use crate::errors::CmdError;
+use crate::expiry::now_ms;
+use crate::resp::Value;
+use crate::storage::generic::Crud;
+use crate::storage::object::Object;
+use crate::types::{ConcurrentStorageType, ExpirationTimeType};
+use bytes::{Bytes, BytesMut};
+use std::ops::Range;
+
+/// Handler for the [SETBIT](https://redis.io/docs/latest/commands/setbit/) command
+///
+/// `SETBIT key offset value` => `original bit`
+///
+/// Sets or clears the bit at `offset` in the string value stored at `key`, depending on `value`, which can be
+/// either 0 or 1. When `key` does not exist, a new string value is created. The string is grown to make sure
+/// it can hold a bit at `offset`, and the added bytes are set to 0. The time to live of the key is retained.
+///
+/// Returns the original bit value stored at `offset` as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers).
+///
+/// Example:
+/// - `"*4\r\n$6\r\nSETBIT\r\n$5\r\nmykey\r\n$1\r\n7\r\n$1\r\n1\r\n"` => `:0\r\n`
+pub(crate) fn handle_setbit<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let key = &args[1];
+    let offset = parse_bit_offset(&args[2], None)?;
+    let bit = match args[3].as_ref() {
+        b"0" => false,
+        b"1" => true,
+        _ => return Err(CmdError::BitOutOfRange),
+    };
+
+    let now = now_ms()?;
+    let mut s = write_lock(storage);
+    let byte = offset / 8;
+    let mask = 0x80 >> (offset % 8);
+    let original = modify_bits(&mut *s, key, now, byte + 1, |value| {
+        let original = value[byte] & mask != 0;
+        if bit {
+            value[byte] |= mask;
+        } else {
+            value[byte] &= !mask;
+        }
+        original
+    })?;
+
+    Ok(Value::Integer(original as i64))
+}
+
+/// Handler for the [GETBIT](https://redis.io/docs/latest/commands/getbit/) command
+///
+/// `GETBIT key offset` => `bit`
+///
+/// Returns the bit value at `offset` in the string value stored at `key` as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers).
+///
+/// When `offset` is beyond the string length, the string is assumed to be a contiguous space with 0 bits.
+/// When `key` does not exist it is assumed to be an empty string, so `offset` is always out of range
+/// and the value is also assumed to be a contiguous space with 0 bits.
+///
+/// Example:
+/// - `"*3\r\n$6\r\nGETBIT\r\n$5\r\nmykey\r\n$1\r\n7\r\n"` => `:1\r\n`
+pub(crate) fn handle_getbit<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let offset = parse_bit_offset(&args[2], None)?;
+    let value = read_string(storage, &args[1])?;
+    Ok(Value::Integer(get_bit(&value, offset) as i64))
+}
+
+/// Handler for the [BITCOUNT](https://redis.io/docs/latest/commands/bitcount/) command
+///
+/// `BITCOUNT key [start end [BYTE | BIT]]` => `count`
+///
+/// Counts the number of set bits (population counting) in a string.
+///
+/// By default all the bytes contained in the string are examined. It is possible to specify the counting
+/// operation only in an interval passing the additional arguments `start` and `end`, which can be negative,
+/// the same as with `GETRANGE`. By default, they are byte indexes, which the `BIT` option changes to bit indexes.
+///
+/// Non-existent keys are treated as empty strings, so the command will return zero.
+///
+/// Returns the number of bits set to 1 as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers).
+///
+/// Example:
+/// - `"*4\r\n$8\r\nBITCOUNT\r\n$5\r\nmykey\r\n$1\r\n1\r\n$1\r\n1\r\n"` => `:6\r\n` - if key `mykey` held `foobar`
+pub(crate) fn handle_bitcount<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let range = match args.len() {
+        2 => None,
+        4 | 5 => Some((
+            parse_integer(&args[2])?,
+            parse_integer(&args[3])?,
+            parse_bit_unit(args.get(4))?,
+        )),
+        _ => return Err(CmdError::SyntaxError),
+    };
+
+    let value = read_string(storage, &args[1])?;
+    let bits = match range {
+        None => Some(0..value.len() * 8),
+        Some((start, end, is_bit)) => bit_range(value.len(), start, end, is_bit),
+    };
+
+    Ok(Value::Integer(
+        bits.map_or(0, |bits| count_bits(&value, bits)) as i64,
+    ))
+}
+
+/// Handler for the [BITPOS](https://redis.io/docs/latest/commands/bitpos/) command
+///
+/// `BITPOS key bit [start [end [BYTE | BIT]]]` => `position`
+///
+/// Returns the position of the first bit set to 1 or 0 in a string as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers).
+///
+/// The position is returned, thinking of the string as an array of bits from left to right, where the first byte's
+/// most significant bit is at position 0, even when a range is specified. The range is given the same way as
+/// with `BITCOUNT`.
+///
+/// Non-existent keys are treated as empty strings. If we look for set bits and the string is empty
+/// or composed of just zero bytes, -1 is returned. If we look for clear bits and the string only contains
+/// bits set to 1, the function returns the first bit not part of the string on the right, unless an explicit
+/// `end` was given, in which case -1 is returned.
+///
+/// Example:
+/// - `"*3\r\n$6\r\nBITPOS\r\n$5\r\nmykey\r\n$1\r\n0\r\n"` => `:12\r\n` - if key `mykey` held `\xff\xf0\x00`
+pub(crate) fn handle_bitpos<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let bit = match parse_integer(&args[2])? {
+        0 => 0,
+        1 => 1,
+        _ => return Err(CmdError::BitArgument),
+    };
+    if args.len() > 6 {
+        return Err(CmdError::SyntaxError);
+    }
+    let start = args.get(3).map(|arg| parse_integer(arg)).transpose()?;
+    let end = args.get(4).map(|arg| parse_integer(arg)).transpose()?;
+    let is_bit = parse_bit_unit(args.get(5))?;
+
+    let Some(value) = get_string(storage, &args[1])? else {
+        return Ok(Value::Integer(if bit == 1 { -1 } else { 0 }));
+    };
+
+    let Some(bits) = bit_range(value.len(), start.unwrap_or(0), end.unwrap_or(-1), is_bit) else {
+        return Ok(Value::Integer(-1));
+    };
+    let pos = match find_bit(&value, bits, bit) {
+        Some(pos) => pos as i64,
+        // The string is thought of as padded with zeros on the right, unless its end was given explicitly.
+        None if bit == 0 && end.is_none() => value.len() as i64 * 8,
+        None => -1,
+    };
+
+    Ok(Value::Integer(pos))
+}
+
+/// Handler for the [BITOP](https://redis.io/docs/latest/commands/bitop/) command
+///
+/// `BITOP <AND | OR | XOR | NOT | DIFF> destkey key [key ...]` => `length`
+///
+/// Performs a bitwise operation between multiple keys, containing string values, and stores the result
+/// in the destination key, without a time to live:
+/// - `AND`, `OR`, `XOR`: the bitwise operation between all the keys
+/// - `NOT`: the bitwise inversion of a single key
+/// - `DIFF`: the bits of the first key that aren't set in any of the other keys, which requires at least two keys
+///
+/// When the strings have different lengths, shorter strings are treated as if they were zero-padded up to
+/// the length of the longest string, and non-existent keys are treated as empty strings.
+/// An empty result deletes the destination key.
+///
+/// Returns the size of the string stored in the destination key, which is equal to the size of the longest
+/// input string, as an [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers).
+///
+/// Example:
+/// - `"*5\r\n$5\r\nBITOP\r\n$3\r\nAND\r\n$4\r\ndest\r\n$4\r\nkey1\r\n$4\r\nkey2\r\n"` => `:6\r\n` - if keys
+///   `key1` and `key2` held `foobar` and `abcdef`
+pub(crate) fn handle_bitop<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let op = args[1].to_ascii_uppercase();
+    let dest = &args[2];
+    let sources = &args[3..];
+    match op.as_slice() {
+        b"AND" | b"OR" | b"XOR" => {}
+        b"NOT" if sources.len() != 1 => return Err(CmdError::BitopNotSingleKey),
+        b"NOT" => {}
+        b"DIFF" if sources.len() < 2 => return Err(CmdError::BitopTwoKeys("DIFF".to_string())),
+        b"DIFF" => {}
+        _ => return Err(CmdError::SyntaxError),
+    }
+
+    let now = now_ms()?;
+    let mut s = write_lock(storage);
+    let values = sources
+        .iter()
+        .map(|key| match read_live(&*s, key, now) {
+            None => Ok(Bytes::new()),
+            Some((value, _)) => value.as_string().cloned().ok_or(CmdError::WrongType),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let len = values.iter().map(Bytes::len).max().unwrap_or_default();
+    let byte_at = |value: &Bytes, i: usize| value.get(i).copied().unwrap_or_default();
+    let result = (0..len)
+        .map(|i| {
+            let mut bytes = values.iter().map(|value| byte_at(value, i));
+            let first = bytes.next().unwrap_or_default();
+            match op.as_slice() {
+                b"AND" => bytes.fold(first, |acc, byte| acc & byte),
+                b"OR" => bytes.fold(first, |acc, byte| acc | byte),
+                b"XOR" => bytes.fold(first, |acc, byte| acc ^ byte),
+                b"NOT" => !first,
+                b"DIFF" => first & !bytes.fold(0, |acc, byte| acc | byte),
+                _ => unreachable!("Unknown operations have been rejected above"),
+            }
+        })
+        .collect::<Vec<u8>>();
+
+    if result.is_empty() {
+        s.delete(dest);
+    } else {
+        s.create(dest, Object::String(Bytes::from(result)), None);
+    }
+
+    Ok(Value::Integer(len as i64))
+}
+
+/// Handler for the [BITFIELD](https://redis.io/docs/latest/commands/bitfield/) command
+///
+/// `BITFIELD key [GET encoding offset | [OVERFLOW <WRAP | SAT | FAIL>] <SET encoding offset value |
+/// INCRBY encoding offset increment> [GET encoding offset | [OVERFLOW <WRAP | SAT | FAIL>] <SET encoding offset
+/// value | INCRBY encoding offset increment> ...]]`
+///
+/// Treats a string as an array of bits, and is capable of addressing specific integer fields of varying bit widths
+/// and arbitrary non (necessary) aligned offset:
+/// - `GET encoding offset`: returns the specified bit field
+/// - `SET encoding offset value`: sets the specified bit field and returns its old value
+/// - `INCRBY encoding offset increment`: increments or decrements the specified bit field and returns the new value
+/// - `OVERFLOW <WRAP | SAT | FAIL>`: changes the overflow behavior of the following `SET` and `INCRBY` operations:
+///   wrap around, saturate at the minimum or maximum value, or don't perform the operation and return nil
+///
+/// The encoding is `i` for signed or `u` for unsigned integers, followed by the number of bits,
+/// up to 64 bits for signed and 63 bits for unsigned integers. The offset is in bits, or, if prefixed with `#`,
+/// in multiples of the encoding's width. The string is grown with zero bytes as needed by the writes,
+/// and the time to live of the key is retained.
+///
+/// Returns an [array](https://redis.io/docs/latest/develop/reference/protocol-spec/#arrays) with the result
+/// of each `GET`, `SET` and `INCRBY` operation, in order.
+///
+/// Example:
+/// - `"*6\r\n$8\r\nBITFIELD\r\n$5\r\nmykey\r\n$6\r\nINCRBY\r\n$2\r\ni5\r\n$3\r\n100\r\n$1\r\n1\r\n"` => `*1\r\n:1\r\n`
+pub(crate) fn handle_bitfield<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    bitfield(storage, args, false)
+}
+
+/// Handler for the [BITFIELD_RO](https://redis.io/docs/latest/commands/bitfield_ro/) command
+///
+/// `BITFIELD_RO key [GET encoding offset [GET encoding offset ...]]`
+///
+/// Read-only variant of [`BITFIELD`](handle_bitfield), which only accepts the `GET` subcommand.
+///
+/// Example:
+/// - `"*5\r\n$11\r\nBITFIELD_RO\r\n$5\r\nmykey\r\n$3\r\nGET\r\n$2\r\ni8\r\n$1\r\n0\r\n"` => `*1\r\n:0\r\n`
+pub(crate) fn handle_bitfield_ro<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    bitfield(storage, args, true)
+}
+
+/// An integer field of a bitmap, as addressed by `BITFIELD`
+#[derive(Clone, Copy, Debug)]
+struct Field {
+    signed: bool,
+    /// Width in bits: 1 to 64 for signed and 1 to 63 for unsigned integers
+    bits: usize,
+    /// Bit offset of the field's most significant bit
+    offset: usize,
+}
+
+/// A single operation of `BITFIELD`
+#[derive(Clone, Copy, Debug)]
+enum BitfieldOp {
+    Get,
+    Set(i64),
+    IncrBy(i64),
+}
+
+/// Overflow behavior of `BITFIELD`'s `SET` and `INCRBY` operations
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Overflow {
+    #[default]
+    Wrap,
+    Sat,
+    Fail,
+}
+
+/// Parses and performs the operations of `BITFIELD` and `BITFIELD_RO`.
+///
+/// All operations are parsed before any of them is performed, so a syntax error leaves the key untouched.
+/// Writes are performed under a single write lock.
+fn bitfield<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    args: &[Bytes],
+    read_only: bool,
+) -> Result<Value, CmdError> {
+    let key = &args[1];
+
+    let mut ops = vec![];
+    let mut overflow = Overflow::default();
+    let mut i = 2;
+    while i < args.len() {
+        let subcommand = args[i].to_ascii_uppercase();
+        let remaining = args.len() - i - 1;
+        match subcommand.as_slice() {
+            b"GET" if remaining >= 2 => {
+                let field = parse_field(&args[i + 1], &args[i + 2])?;
+                ops.push((field, BitfieldOp::Get, overflow));
+                i += 3;
+            }
+            b"SET" | b"INCRBY" if remaining >= 3 => {
+                if read_only {
+                    return Err(CmdError::BitfieldReadOnly);
+                }
+                let field = parse_field(&args[i + 1], &args[i + 2])?;
+                let value = parse_integer(&args[i + 3])?;
+                let op = if subcommand.as_slice() == b"SET" {
+                    BitfieldOp::Set(value)
+                } else {
+                    BitfieldOp::IncrBy(value)
+                };
+                ops.push((field, op, overflow));
+                i += 4;
+            }
+            b"OVERFLOW" if remaining >= 1 => {
+                overflow = match args[i + 1].to_ascii_uppercase().as_slice() {
+                    b"WRAP" => Overflow::Wrap,
+                    b"SAT" => Overflow::Sat,
+                    b"FAIL" => Overflow::Fail,
+                    _ => return Err(CmdError::InvalidOverflowType),
+                };
+                i += 2;
+            }
+            _ => return Err(CmdError::SyntaxError),
+        }
+    }
+
+    // The string has to be long enough for the field that ends the furthest, even if it isn't written to.
+    let highest_write = ops
+        .iter()
+        .filter(|(_, op, _)| !matches!(op, BitfieldOp::Get))
+        .map(|(field, _, _)| field.offset + field.bits - 1)
+        .max();
+    let Some(highest_write) = highest_write else {
+        let value = read_string(storage, key)?;
+        return Ok(Value::Array(
+            ops.iter()
+                .map(|(field, _, _)| Value::Integer(get_field(&value, field)))
+                .collect(),
+        ));
+    };
+
+    let len = highest_write / 8 + 1;
+    check_string_length(len)?;
+    let now = now_ms()?;
+    let mut s = write_lock(storage);
+    let replies = modify_bits(&mut *s, key, now, len, |value| perform(value, &ops))?;
+
+    Ok(Value::Array(replies))
+}
+
+/// Performs the operations of `BITFIELD` on a string that is long enough for all of their fields,
+/// and returns their replies.
+fn perform(value: &mut BytesMut, ops: &[(Field, BitfieldOp, Overflow)]) -> Vec<Value> {
+    let mut replies = Vec::with_capacity(ops.len());
+    for (field, op, overflow) in ops {
+        let old = get_field(value, field);
+        let (new, overflowed) = match op {
+            BitfieldOp::Get => {
+                replies.push(Value::Integer(old));
+                continue;
+            }
+            BitfieldOp::IncrBy(increment) => {
+                apply_overflow(old as i128 + *increment as i128, field, *overflow)
+            }
+            // Values of unsigned fields are taken as unsigned 64-bit integers, the same as Redis does it.
+            BitfieldOp::Set(new) if field.signed => apply_overflow(*new as i128, field, *overflow),
+            BitfieldOp::Set(new) => apply_overflow(*new as u64 as i128, field, *overflow),
+        };
+        if overflowed && *overflow == Overflow::Fail {
+            replies.push(Value::Null);
+            continue;
+        }
+        set_field(value, field, new);
+        replies.push(Value::Integer(match op {
+            BitfieldOp::IncrBy(_) => new,
+            _ => old,
+        }));
+    }
+    replies
+}
+
+/// Modifies the string stored at `key` in place, for the commands that write bits, after padding it with zeros
+/// to at least `len` bytes.
+///
+/// A nonexistent key is created, and the time to live of an existing key is retained.
+/// Returns an error if the key holds a value of another type.
+fn modify_bits<S: Crud, T>(
+    s: &mut S,
+    key: &Bytes,
+    now: ExpirationTimeType,
+    len: usize,
+    f: impl FnOnce(&mut BytesMut) -> T,
+) -> Result<T, CmdError> {
+    let Some((value, _)) = get_live(s, key, now) else {
+        let mut value = BytesMut::zeroed(len);
+        let result = f(&mut value);
+        s.update(key, Object::String(value.freeze()), None);
+        return Ok(result);
+    };
+    value.as_string().ok_or(CmdError::WrongType)?;
+
+    let result = s.modify(key, |value| {
+        value.modify_string(|value| {
+            if value.len() < len {
+                value.resize(len, 0);
+            }
+            f(value)
+        })
+    });
+    Ok(result.flatten().expect("The key holds a string"))
+}
+
+/// Parses the encoding and the offset of a `BITFIELD` field, such as `i8` and `#2`.
+fn parse_field(encoding: &[u8], offset: &[u8]) -> Result<Field, CmdError> {
+    let (signed, bits) = match encoding.split_first() {
+        Some((b'i' | b'I', bits)) => (true, bits),
+        Some((b'u' | b'U', bits)) => (false, bits),
+        _ => return Err(CmdError::InvalidBitfieldType),
+    };
+    let max_bits = if signed { 64 } else { 63 };
+    let bits = parse_integer(bits)
+        .ok()
+        .filter(|bits| (1..=max_bits).contains(bits))
+        .ok_or(CmdError::InvalidBitfieldType)? as usize;
+    let offset = parse_bit_offset(offset, Some(bits))?;
+
+    Ok(Field {
+        signed,
+        bits,
+        offset,
+    })
+}
+
+/// Parses a bit offset, which must be a non-negative integer that addresses a bit within the maximum string size.
+///
+/// With `width`, as for `BITFIELD`, an offset prefixed with `#` is multiplied by the width.
+fn parse_bit_offset(arg: &[u8], width: Option<usize>) -> Result<usize, CmdError> {
+    let (arg, multiplier) = match (arg.strip_prefix(b"#"), width) {
+        (Some(arg), Some(width)) => (arg, width as i64),
+        _ => (arg, 1),
+    };
+    parse_integer(arg)
+        .ok()
+        .and_then(|offset| offset.checked_mul(multiplier))
+        .filter(|offset| (0..PROTO_MAX_BULK_LEN as i64 * 8).contains(offset))
+        .map(|offset| offset as usize)
+        .ok_or(CmdError::BitOffsetOutOfRange)
+}
+
+/// Parses the optional `BYTE` or `BIT` unit of a range, and returns whether it is `BIT`.
+fn parse_bit_unit(arg: Option<&Bytes>) -> Result<bool, CmdError> {
+    match arg {
+        None => Ok(false),
+        Some(arg) if arg.eq_ignore_ascii_case(b"BYTE") => Ok(false),
+        Some(arg) if arg.eq_ignore_ascii_case(b"BIT") => Ok(true),
+        Some(_) => Err(CmdError::SyntaxError),
+    }
+}
+
+/// Reads the string stored at `key`, for commands that treat nonexistent keys as empty strings.
+fn read_string<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    key: &Bytes,
+) -> Result<Bytes, CmdError> {
+    Ok(get_string(storage, key)?.unwrap_or_default())
+}
+
+/// Converts the inclusive `start` and `end` indexes of `BITCOUNT` and `BITPOS`, which can be negative,
+/// into a range of bits of a string of length `len`.
+///
+/// The indexes are in bytes, or in bits if `is_bit` is set. Returns `None` if the range is empty.
+fn bit_range(len: usize, start: i64, end: i64, is_bit: bool) -> Option<Range<usize>> {
+    if is_bit {
+        string_range(len * 8, start, end)
+    } else {
+        string_range(len, start, end).map(|bytes| bytes.start * 8..bytes.end * 8)
+    }
+}
+
+/// Returns the bit at `offset`, where bits beyond the end of the string are 0.
+fn get_bit(value: &[u8], offset: usize) -> u8 {
+    value
+        .get(offset / 8)
+        .map_or(0, |byte| (byte >> (7 - offset % 8)) & 1)
+}
+
+/// Counts the bits set to 1 in a range of bits.
+fn count_bits(value: &[u8], bits: Range<usize>) -> usize {
+    let mut count = 0;
+    let mut pos = bits.start;
+    while pos < bits.end {
+        if pos.is_multiple_of(8) && pos + 8 <= bits.end {
+            count += value[pos / 8].count_ones() as usize;
+            pos += 8;
+        } else {
+            count += get_bit(value, pos) as usize;
+            pos += 1;
+        }
+    }
+    count
+}
+
+/// Finds the position of the first bit equal to `bit` in a range of bits.
+fn find_bit(value: &[u8], bits: Range<usize>, bit: u8) -> Option<usize> {
+    // Whole bytes with all bits different from the one we're looking for are skipped at once.
+    let skip = if bit == 1 { 0x00 } else { 0xff };
+    let mut pos = bits.start;
+    while pos < bits.end {
+        if pos.is_multiple_of(8) && pos + 8 <= bits.end && value[pos / 8] == skip {
+            pos += 8;
+        } else if get_bit(value, pos) == bit {
+            return Some(pos);
+        } else {
+            pos += 1;
+        }
+    }
+    None
+}
+
+/// Reads an integer field, which can extend beyond the end of the string, where bits are 0.
+fn get_field(value: &[u8], field: &Field) -> i64 {
+    let raw = (0..field.bits).fold(0u64, |acc, j| {
+        (acc << 1) | get_bit(value, field.offset + j) as u64
+    });
+    if field.signed && field.bits < 64 && raw >> (field.bits - 1) == 1 {
+        // Sign-extend negative values.
+        (raw | (u64::MAX << field.bits)) as i64
+    } else {
+        raw as i64
+    }
+}
+
+/// Writes an integer field, which must fit in the string.
+fn set_field(value: &mut [u8], field: &Field, new: i64) {
+    let new = new as u64;
+    for j in 0..field.bits {
+        let pos = field.offset + j;
+        let mask = 0x80 >> (pos % 8);
+        if (new >> (field.bits - 1 - j)) & 1 == 1 {
+            value[pos / 8] |= mask;
+        } else {
+            value[pos / 8] &= !mask;
+        }
+    }
+}
+
+/// Fits a value into the range of a field, according to the overflow behavior.
+///
+/// Returns the value to store, and whether the value overflowed.
+fn apply_overflow(value: i128, field: &Field, overflow: Overflow) -> (i64, bool) {
+    let modulus = 1i128 << field.bits;
+    let (min, max) = if field.signed {
+        (-(modulus / 2), modulus / 2 - 1)
+    } else {
+        (0, modulus - 1)
+    };
+    if (min..=max).contains(&value) {
+        return (value as i64, false);
+    }
+    let fitted = match overflow {
+        Overflow::Sat => value.clamp(min, max),
+        Overflow::Wrap | Overflow::Fail => {
+            let wrapped = value.rem_euclid(modulus);
+            if wrapped > max {
+                wrapped - modulus
+            } else {
+                wrapped
+            }
+        }
+    };
+    (fitted as i64, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::tests::{run, storage};
+
+    fn set(key: &str, value: &[u8]) {
+        storage().write().unwrap().create(
+            &Bytes::copy_from_slice(key.as_bytes()),
+            Object::String(Bytes::copy_from_slice(value)),
+            None,
+        );
+    }
+
+    fn get(key: &str) -> Value {
+        run(&["GET", key]).unwrap()
+    }
+
+    fn int(value: i64) -> Value {
+        Value::Integer(value)
+    }
+
+    #[test]
+    fn handle_setbit_getbit() {
+        assert_eq!(int(0), run(&["SETBIT", "setbit", "7", "1"]).unwrap());
+        assert_eq!(int(1), run(&["SETBIT", "setbit", "7", "0"]).unwrap());
+        assert_eq!(int(0), run(&["SETBIT", "setbit", "17", "1"]).unwrap());
+        assert_eq!(
+            Value::BulkString(Bytes::from_static(b"\x00\x00\x40")),
+            get("setbit")
+        );
+        assert_eq!(int(1), run(&["GETBIT", "setbit", "17"]).unwrap());
+        assert_eq!(int(0), run(&["GETBIT", "setbit", "100"]).unwrap());
+        assert_eq!(int(0), run(&["GETBIT", "setbit_missing", "0"]).unwrap());
+
+        assert!(matches!(
+            run(&["SETBIT", "setbit", "-1", "1"]),
+            Err(CmdError::BitOffsetOutOfRange)
+        ));
+        assert!(matches!(
+            run(&["SETBIT", "setbit", "4294967296", "1"]),
+            Err(CmdError::BitOffsetOutOfRange)
+        ));
+        assert!(matches!(
+            run(&["SETBIT", "setbit", "0", "2"]),
+            Err(CmdError::BitOutOfRange)
+        ));
+    }
+
+    #[test]
+    fn handle_setbit_bitfield_in_place() {
+        set("setbit_in_place", &[0; 1024]);
+        let key = Bytes::from("setbit_in_place");
+        let ptr = || {
+            let s = storage().read().unwrap();
+            s.get(&key).unwrap().0.as_string().unwrap().as_ptr()
+        };
+        let before = ptr();
+        for offset in ["0", "100", "8191"] {
+            assert_eq!(
+                int(0),
+                run(&["SETBIT", "setbit_in_place", offset, "1"]).unwrap()
+            );
+        }
+        let reply = run(&["BITFIELD", "setbit_in_place", "INCRBY", "u8", "#10", "5"]).unwrap();
+        assert_eq!(Value::Array(vec![int(5)]), reply);
+        assert_eq!(before, ptr());
+        assert_eq!(int(5), run(&["BITCOUNT", "setbit_in_place"]).unwrap());
+    }
+
+    #[test]
+    fn handle_bitcount() {
+        set("bitcount", b"foobar");
+        assert_eq!(int(26), run(&["BITCOUNT", "bitcount"]).unwrap());
+        assert_eq!(int(4), run(&["BITCOUNT", "bitcount", "0", "0"]).unwrap());
+        assert_eq!(int(6), run(&["BITCOUNT", "bitcount", "1", "1"]).unwrap());
+        assert_eq!(
+            int(6),
+            run(&["BITCOUNT", "bitcount", "1", "1", "byte"]).unwrap()
+        );
+        assert_eq!(
+            int(17),
+            run(&["BITCOUNT", "bitcount", "5", "30", "BIT"]).unwrap()
+        );
+        assert_eq!(int(0), run(&["BITCOUNT", "bitcount", "2", "1"]).unwrap());
+        assert_eq!(int(0), run(&["BITCOUNT", "bitcount_missing"]).unwrap());
+        assert!(matches!(
+            run(&["BITCOUNT", "bitcount", "0"]),
+            Err(CmdError::SyntaxError)
+        ));
+        assert!(matches!(
+            run(&["BITCOUNT", "bitcount", "0", "1", "BITS"]),
+            Err(CmdError::SyntaxError)
+        ));
+    }
+
+    #[test]
+    fn handle_bitpos() {
+        set("bitpos", b"\xff\xf0\x00");
+        assert_eq!(int(12), run(&["BITPOS", "bitpos", "0"]).unwrap());
+        set("bitpos", b"\x00\xff\xf0");
+        assert_eq!(int(8), run(&["BITPOS", "bitpos", "1", "0"]).unwrap());
+        assert_eq!(int(16), run(&["BITPOS", "bitpos", "1", "2"]).unwrap());
+        assert_eq!(
+            int(16),
+            run(&["BITPOS", "bitpos", "1", "2", "-1", "BYTE"]).unwrap()
+        );
+        assert_eq!(
+            int(7),
+            run(&["BITPOS", "bitpos", "0", "7", "15", "BIT"]).unwrap()
+        );
+        assert_eq!(
+            int(-1),
+            run(&["BITPOS", "bitpos", "0", "8", "11", "BIT"]).unwrap()
+        );
+
+        set("bitpos_ones", b"\xff\xff");
+        assert_eq!(int(16), run(&["BITPOS", "bitpos_ones", "0"]).unwrap());
+        assert_eq!(
+            int(-1),
+            run(&["BITPOS", "bitpos_ones", "0", "0", "-1"]).unwrap()
+        );
+
+        assert_eq!(int(-1), run(&["BITPOS", "bitpos_missing", "1"]).unwrap());
+        assert_eq!(int(0), run(&["BITPOS", "bitpos_missing", "0"]).unwrap());
+        assert!(matches!(
+            run(&["BITPOS", "bitpos", "2"]),
+            Err(CmdError::BitArgument)
+        ));
+    }
+
+    #[test]
+    fn handle_bitop() {
+        set("bitop1", b"foobar");
+        set("bitop2", b"abcdef");
+        set("bitop3", b"\x0f");
+        for (op, expected) in [
+            ("AND", &b"`bc`ab"[..]),
+            ("or", b"goofev"),
+            ("XOR", b"\x07\x0d\x0c\x06\x04\x14"),
+        ] {
+            assert_eq!(
+                int(6),
+                run(&["BITOP", op, "bitop_dest", "bitop1", "bitop2"]).unwrap()
+            );
+            assert_eq!(
+                Value::BulkString(Bytes::copy_from_slice(expected)),
+                get("bitop_dest")
+            );
+        }
+        assert_eq!(
+            int(1),
+            run(&["BITOP", "NOT", "bitop_dest", "bitop3"]).unwrap()
+        );
+        assert_eq!(
+            Value::BulkString(Bytes::from_static(b"\xf0")),
+            get("bitop_dest")
+        );
+        assert_eq!(
+            int(6),
+            run(&["BITOP", "DIFF", "bitop_dest", "bitop1", "bitop3"]).unwrap()
+        );
+        assert_eq!(
+            Value::BulkString(Bytes::from_static(b"`oobar")),
+            get("bitop_dest")
+        );
+
+        assert_eq!(
+            int(0),
+            run(&["BITOP", "AND", "bitop_dest", "bitop_missing"]).unwrap()
+        );
+        assert_eq!(Value::Null, get("bitop_dest"));
+
+        assert!(matches!(
+            run(&["BITOP", "NOT", "bitop_dest", "bitop1", "bitop2"]),
+            Err(CmdError::BitopNotSingleKey)
+        ));
+        assert!(matches!(
+            run(&["BITOP", "DIFF", "bitop_dest", "bitop1"]),
+            Err(CmdError::BitopTwoKeys(op)) if op == "DIFF"
+        ));
+        assert!(matches!(
+            run(&["BITOP", "NAND", "bitop_dest", "bitop1"]),
+            Err(CmdError::SyntaxError)
+        ));
+    }
+
+    #[test]
+    fn handle_bitfield() {
+        assert_eq!(
+            Value::Array(vec![int(1), int(0)]),
+            run(&["BITFIELD", "bitfield", "INCRBY", "i5", "100", "1", "GET", "u4", "0"]).unwrap()
+        );
+        assert_eq!(
+            Value::Array(vec![int(0), int(100), int(-56)]),
+            run(&[
+                "BITFIELD", "bitfield", "SET", "i8", "#1", "100", "GET", "i8", "8", "INCRBY", "i8",
+                "#1", "100"
+            ])
+            .unwrap()
+        );
+        assert_eq!(
+            Value::Array(vec![int(0), int(-56)]),
+            run(&[
+                "BITFIELD_RO",
+                "bitfield",
+                "GET",
+                "u8",
+                "0",
+                "GET",
+                "i8",
+                "#1"
+            ])
+            .unwrap()
+        );
+        assert_eq!(
+            Value::Array(vec![]),
+            run(&["BITFIELD", "bitfield_missing"]).unwrap()
+        );
+        assert_eq!(Value::Null, get("bitfield_missing"));
+    }
+
+    #[test]
+    fn handle_bitfield_overflow() {
+        let incr = |overflow: &str| {
+            run(&[
+                "BITFIELD",
+                "bitfield_overflow",
+                "OVERFLOW",
+                overflow,
+                "INCRBY",
+                "u2",
+                "102",
+                "1",
+            ])
+            .unwrap()
+        };
+        let results = (0..4).map(|_| incr("WRAP")).collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                Value::Array(vec![int(1)]),
+                Value::Array(vec![int(2)]),
+                Value::Array(vec![int(3)]),
+                Value::Array(vec![int(0)])
+            ],
+            results
+        );
+        run(&["BITFIELD", "bitfield_overflow", "SET", "u2", "102", "3"]).unwrap();
+        assert_eq!(Value::Array(vec![int(3)]), incr("SAT"));
+        assert_eq!(Value::Array(vec![Value::Null]), incr("FAIL"));
+
+        assert_eq!(
+            Value::Array(vec![int(0), int(127)]),
+            run(&[
+                "BITFIELD",
+                "bitfield_sat",
+                "OVERFLOW",
+                "SAT",
+                "SET",
+                "i8",
+                "0",
+                "1000",
+                "GET",
+                "i8",
+                "0"
+            ])
+            .unwrap()
+        );
+        assert_eq!(
+            Value::Array(vec![int(0), int(255)]),
+            run(&[
+                "BITFIELD",
+                "bitfield_sat",
+                "OVERFLOW",
+                "SAT",
+                "SET",
+                "u8",
+                "8",
+                "-1",
+                "GET",
+                "u8",
+                "8"
+            ])
+            .unwrap()
+        );
+        assert_eq!(
+            Value::Array(vec![int(i64::MIN)]),
+            run(&[
+                "BITFIELD",
+                "bitfield_sat",
+                "OVERFLOW",
+                "SAT",
+                "INCRBY",
+                "i64",
+                "16",
+                "-9223372036854775808"
+            ])
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn handle_bitfield_errors() {
+        assert!(matches!(
+            run(&["BITFIELD", "bitfield_errors", "GET", "u64", "0"]),
+            Err(CmdError::InvalidBitfieldType)
+        ));
+        assert!(matches!(
+            run(&["BITFIELD", "bitfield_errors", "GET", "i0", "0"]),
+            Err(CmdError::InvalidBitfieldType)
+        ));
+        assert!(matches!(
+            run(&["BITFIELD", "bitfield_errors", "GET", "i8", "-1"]),
+            Err(CmdError::BitOffsetOutOfRange)
+        ));
+        assert!(matches!(
+            run(&["BITFIELD", "bitfield_errors", "OVERFLOW", "NONE"]),
+            Err(CmdError::InvalidOverflowType)
+        ));
+        assert!(matches!(
+            run(&["BITFIELD", "bitfield_errors", "SET", "i8", "0"]),
+            Err(CmdError::SyntaxError)
+        ));
+        assert!(matches!(
+            run(&["BITFIELD_RO", "bitfield_errors", "SET", "i8", "0", "1"]),
+            Err(CmdError::BitfieldReadOnly)
+        ));
+        assert_eq!(Value::Null, get("bitfield_errors"));
+    }
+}
@@ -0,0 +1,395 @@
+//! # Connection Commands
+//!
+//! Handlers for the commands of the [connection](https://redis.io/docs/latest/commands/?group=connection) group
+
+use crate::cmd::parse_db_index;
+use crate::conn::Client;
+use crate::constants::REDIS_VERSION;
+use crate::errors::CmdError;
+use crate::resp::{Protocol, Value};
+use crate::storage::generic::Crud;
+use crate::types::ConcurrentStorageType;
+use bytes::Bytes;
+
+/// Handler for the [CLIENT](https://redis.io/docs/latest/commands/client/) command
+///
+/// `CLIENT <subcommand> [<arg> ...]`
+///
+/// Inspects and configures the connection.
+///
+/// Subcommands:
+/// - `GETNAME` => the name of the connection, or a nil value if it has none
+/// - `HELP` => the list of subcommands
+/// - `ID` => the unique ID of the connection
+/// - `NO-TOUCH ON | OFF` => `OK` - whether the connection's commands leave the last access time and the LFU counter
+///   of the keys that they read alone; `TOUCH` always updates them
+/// - `SETNAME connection-name` => `OK` - sets the name of the connection, or removes it if it's empty
+///
+/// Example:
+/// - `"*3\r\n$6\r\nCLIENT\r\n$8\r\nNO-TOUCH\r\n$2\r\nON\r\n"` => `+OK\r\n`
+pub(crate) fn handle_client<KV: Crud, KE: Crud>(
+    _storage: &ConcurrentStorageType<KV, KE>,
+    client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let subcommand = args[1].to_ascii_lowercase();
+    let arity = match subcommand.as_slice() {
+        b"getname" | b"help" | b"id" => 2,
+        b"no-touch" | b"setname" => 3,
+        _ => {
+            return Err(CmdError::UnknownSubcommand(
+                "CLIENT".to_string(),
+                String::from_utf8_lossy(&args[1]).to_string(),
+            ))
+        }
+    };
+    if args.len() != arity {
+        return Err(CmdError::WrongNumberOfArgs(format!(
+            "client|{}",
+            String::from_utf8_lossy(&subcommand)
+        )));
+    }
+
+    match subcommand.as_slice() {
+        b"getname" => Ok(client.name.clone().map_or(Value::Null, Value::BulkString)),
+        b"help" => Ok(Value::Array(
+            [
+                "CLIENT <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+                "GETNAME",
+                "    Return the name of the current connection.",
+                "ID",
+                "    Return the ID of the current connection.",
+                "NO-TOUCH (ON|OFF)",
+                "    Will not touch LRU/LFU stats when this mode is on.",
+                "SETNAME <name>",
+                "    Assign the name <name> to the current connection.",
+                "HELP",
+                "    Print this help.",
+            ]
+            .into_iter()
+            .map(|line| Value::SimpleString(Bytes::from_static(line.as_bytes())))
+            .collect(),
+        )),
+        b"id" => Ok(Value::Integer(client.id as i64)),
+        b"no-touch" => {
+            client.no_touch = match args[2].to_ascii_uppercase().as_slice() {
+                b"ON" => true,
+                b"OFF" => false,
+                _ => return Err(CmdError::SyntaxError),
+            };
+            Ok(Value::OK)
+        }
+        b"setname" => {
+            if !is_valid_client_name(&args[2]) {
+                return Err(CmdError::InvalidClientName);
+            }
+            client.name = (!args[2].is_empty()).then(|| args[2].clone());
+            Ok(Value::OK)
+        }
+        _ => unreachable!("Unknown subcommands have been rejected above"),
+    }
+}
+
+/// Handler for the [ECHO](https://redis.io/docs/latest/commands/echo/) command
+///
+/// Handles a single `ECHO` request.
+///
+/// Returns a copy of the argument as a
+/// [bulk string](https://redis.io/docs/latest/develop/reference/protocol-spec/#bulk-strings).
+///
+/// - Example request from a client: `"*2\r\n$4\r\nECHO\r\n$3\r\nHey\r\n"`.
+///   That's `["ECHO", "Hey"]` encoded using the Redis protocol.
+///   - Expected response from the server: `$3\r\nHey\r\n` (a bulk string)
+pub(crate) fn handle_echo<KV: Crud, KE: Crud>(
+    _storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    Ok(Value::BulkString(args[1].clone()))
+}
+
+/// Handler for the [HELLO](https://redis.io/docs/latest/commands/hello/) command
+///
+/// Handles a single `HELLO` request.
+///
+/// `HELLO [protover [AUTH username password] [SETNAME clientname]]`
+///
+/// Switches the connection to the given protocol version, RESP2 or RESP3, and optionally sets its name.
+/// The connection keeps its current protocol version if `protover` is not provided.
+///
+/// There is no authentication in our server, so `AUTH` only succeeds for the `default` user, whatever the password.
+///
+/// Returns a map of server and connection properties, in the newly selected protocol version:
+/// a [map](https://redis.io/docs/latest/develop/reference/protocol-spec/#maps) in RESP3,
+/// and a flat array of keys and values in RESP2.
+///
+/// Example:
+/// - `"*2\r\n$5\r\nHELLO\r\n$1\r\n3\r\n"` => `%7\r\n$6\r\nserver\r\n$5\r\nredis\r\n...` - switches to RESP3
+pub(crate) fn handle_hello<KV: Crud, KE: Crud>(
+    _storage: &ConcurrentStorageType<KV, KE>,
+    client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let args = &args[1..];
+    let mut protocol = client.protocol;
+    let mut name = None;
+    if let Some(protover) = args.first() {
+        protocol = match std::str::from_utf8(protover)
+            .ok()
+            .and_then(|protover| protover.parse::<i64>().ok())
+        {
+            Some(2) => Protocol::RESP2,
+            Some(3) => Protocol::RESP3,
+            Some(_) => return Err(CmdError::NoProto),
+            None => return Err(CmdError::InvalidProtocolVersion),
+        };
+
+        let mut i = 1;
+        while i < args.len() {
+            let more_args = args.len() - i - 1;
+            match args[i].to_ascii_uppercase().as_slice() {
+                b"AUTH" if more_args >= 2 => {
+                    if args[i + 1].as_ref().ne(b"default") {
+                        return Err(CmdError::WrongPass);
+                    }
+                    i += 3;
+                }
+                b"SETNAME" if more_args >= 1 => {
+                    if !is_valid_client_name(&args[i + 1]) {
+                        return Err(CmdError::InvalidClientName);
+                    }
+                    name = Some(args[i + 1].clone());
+                    i += 2;
+                }
+                _ => {
+                    return Err(CmdError::HelloSyntaxError(
+                        String::from_utf8_lossy(&args[i]).to_string(),
+                    ))
+                }
+            }
+        }
+    }
+
+    client.protocol = protocol;
+    if let Some(name) = name {
+        client.name = (!name.is_empty()).then_some(name);
+    }
+
+    Ok(Value::Map(vec![
+        (
+            Value::BulkString(Bytes::from_static(b"server")),
+            Value::BulkString(Bytes::from_static(b"redis")),
+        ),
+        (
+            Value::BulkString(Bytes::from_static(b"version")),
+            Value::BulkString(Bytes::from_static(REDIS_VERSION.as_bytes())),
+        ),
+        (
+            Value::BulkString(Bytes::from_static(b"proto")),
+            Value::Integer(protocol as i64),
+        ),
+        (
+            Value::BulkString(Bytes::from_static(b"id")),
+            Value::Integer(client.id as i64),
+        ),
+        (
+            Value::BulkString(Bytes::from_static(b"mode")),
+            Value::BulkString(Bytes::from_static(b"standalone")),
+        ),
+        (
+            Value::BulkString(Bytes::from_static(b"role")),
+            Value::BulkString(Bytes::from_static(b"master")),
+        ),
+        (
+            Value::BulkString(Bytes::from_static(b"modules")),
+            Value::Array(vec![]),
+        ),
+    ]))
+}
+
+/// Handler for the [PING](https://redis.io/docs/latest/commands/ping/) command
+///
+/// Handles a single `PING` request.
+///
+/// Returns `PONG` as a [simple string](https://redis.io/docs/latest/develop/reference/protocol-spec/#simple-strings)
+/// if no argument is provided, otherwise returns a copy of the argument as a
+/// [bulk string](https://redis.io/docs/latest/develop/reference/protocol-spec/#bulk-strings).
+///
+/// - Example request from a client: `"*1\r\n$4\r\nPING\r\n"`
+///   That's `["PING"]` encoded using the Redis protocol.
+///    - Expected response from the server: `+PONG\r\n` (a simple string)
+/// - Example request from a client: `"*2\r\n$4\r\PING\r\n$8\r\nTest a B\r\n"`
+///   That's `["PING", "Test a B"]` encoded using the Redis protocol.
+///    - Expected response from the server: `$8\r\nTest a B\r\n` (a bulk string)
+pub(crate) fn handle_ping<KV: Crud, KE: Crud>(
+    _storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    match args {
+        [_] => Ok(Value::SimpleString(Bytes::from_static(b"PONG"))),
+        [_, argument] => Ok(Value::BulkString(argument.clone())),
+        _ => Err(CmdError::WrongNumberOfArgs("ping".to_string())),
+    }
+}
+
+/// Handler for the [SELECT](https://redis.io/docs/latest/commands/select/) command
+///
+/// `SELECT index` => `OK`
+///
+/// Selects the database that the connection's subsequent commands work with. Databases are numbered from 0,
+/// and a new connection starts in database 0. The number of databases is configurable, and is 16 by default.
+///
+/// Example:
+/// - `"*2\r\n$6\r\nSELECT\r\n$1\r\n1\r\n"` => `+OK\r\n`
+pub(crate) fn handle_select<KV: Crud, KE: Crud>(
+    databases: &[ConcurrentStorageType<KV, KE>],
+    client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    client.db = parse_db_index(&args[1], databases.len())?;
+    Ok(Value::OK)
+}
+
+/// Checks whether a connection name consists of printable characters other than space only.
+fn is_valid_client_name(name: &[u8]) -> bool {
+    name.iter().all(|c| (b'!'..=b'~').contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::error_reply;
+    use crate::cmd::tests::{run_as, storage};
+
+    #[test]
+    fn handle_echo_binary() {
+        let words = vec![Bytes::from("ECHO"), Bytes::from_static(b"\x00\xff\r\n")];
+        let result = handle_echo(storage(), &mut Client::new(), &words).unwrap();
+
+        let expected = Value::BulkString(Bytes::from_static(b"\x00\xff\r\n"));
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn handle_hello_unsupported_protocol() {
+        let mut client = Client::new();
+        let words = vec![Bytes::from("HELLO"), Bytes::from("4")];
+        let err = handle_hello(storage(), &mut client, &words).unwrap_err();
+        let expected = Value::Error(Bytes::from("NOPROTO unsupported protocol version"));
+        assert_eq!(expected, error_reply(&err));
+        assert_eq!(Protocol::RESP2, client.protocol);
+    }
+
+    #[test]
+    fn handle_hello_wrong_user() {
+        let mut client = Client::new();
+        let words = ["HELLO", "3", "AUTH", "admin", "secret"]
+            .into_iter()
+            .map(Bytes::from)
+            .collect::<Vec<_>>();
+        let err = handle_hello(storage(), &mut client, &words).unwrap_err();
+        let expected = Value::Error(Bytes::from(
+            "WRONGPASS invalid username-password pair or user is disabled.",
+        ));
+        assert_eq!(expected, error_reply(&err));
+        assert_eq!(Protocol::RESP2, client.protocol);
+    }
+
+    #[test]
+    fn handle_ping_ping_pong() {
+        let input = vec![Bytes::from("PING")];
+        let result = handle_ping(storage(), &mut Client::new(), &input).unwrap();
+
+        let expected = Value::SimpleString(Bytes::from("PONG"));
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn handle_ping_ping_with_arg() {
+        let words = vec![Bytes::from("PING"), Bytes::from("Hello, world!")];
+        let result = handle_ping(storage(), &mut Client::new(), &words).unwrap();
+
+        let expected = Value::BulkString(Bytes::from("Hello, world!"));
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn handle_select() {
+        let mut client = Client::new();
+        run_as(&mut client, &["SET", "select_key", "zero"]).unwrap();
+        assert_eq!(Value::OK, run_as(&mut client, &["SELECT", "15"]).unwrap());
+        assert_eq!(15, client.db);
+        assert_eq!(
+            Value::Null,
+            run_as(&mut client, &["GET", "select_key"]).unwrap()
+        );
+        run_as(&mut client, &["SET", "select_key", "fifteen"]).unwrap();
+
+        assert_eq!(Value::OK, run_as(&mut client, &["SELECT", "0"]).unwrap());
+        assert_eq!(
+            Value::BulkString(Bytes::from("zero")),
+            run_as(&mut client, &["GET", "select_key"]).unwrap()
+        );
+
+        for index in ["16", "-1"] {
+            assert!(matches!(
+                run_as(&mut client, &["SELECT", index]),
+                Err(CmdError::DbIndexOutOfRange)
+            ));
+        }
+        for index in ["one", "4294967296"] {
+            assert!(matches!(
+                run_as(&mut client, &["SELECT", index]),
+                Err(CmdError::NotAnInteger)
+            ));
+        }
+        assert_eq!(0, client.db);
+    }
+
+    #[test]
+    fn handle_client() {
+        let mut client = Client::new();
+        assert_eq!(
+            Value::Integer(client.id as i64),
+            run_as(&mut client, &["CLIENT", "ID"]).unwrap()
+        );
+        assert_eq!(
+            Value::Null,
+            run_as(&mut client, &["CLIENT", "GETNAME"]).unwrap()
+        );
+        assert_eq!(
+            Value::OK,
+            run_as(&mut client, &["CLIENT", "SETNAME", "worker-1"]).unwrap()
+        );
+        assert_eq!(
+            Value::BulkString(Bytes::from("worker-1")),
+            run_as(&mut client, &["CLIENT", "GETNAME"]).unwrap()
+        );
+        assert!(matches!(
+            run_as(&mut client, &["CLIENT", "SETNAME", "worker 1"]),
+            Err(CmdError::InvalidClientName)
+        ));
+
+        assert_eq!(
+            Value::OK,
+            run_as(&mut client, &["client", "no-touch", "on"]).unwrap()
+        );
+        assert!(client.no_touch);
+        assert!(matches!(
+            run_as(&mut client, &["CLIENT", "NO-TOUCH", "maybe"]),
+            Err(CmdError::SyntaxError)
+        ));
+        assert!(matches!(
+            run_as(&mut client, &["CLIENT", "NO-TOUCH"]),
+            Err(CmdError::WrongNumberOfArgs(name)) if name == "client|no-touch"
+        ));
+        assert!(matches!(
+            run_as(&mut client, &["CLIENT", "KILL", "1"]),
+            Err(CmdError::UnknownSubcommand(..))
+        ));
+    }
+}
@@ -0,0 +1,121 @@
+//! # Hash Commands
+//!
+//! Handlers for the commands of the [hash](https://redis.io/docs/latest/commands/?group=hash) group
+
+use crate::cmd::keyspace::{parse_cursor, scan_reply, ScanOptions};
+use crate::cmd::with_key;
+use crate::conn::Client;
+use crate::errors::CmdError;
+use crate::resp::Value;
+use crate::storage::generic::Crud;
+use crate::storage::object::Object;
+use crate::types::ConcurrentStorageType;
+use bytes::Bytes;
+
+/// Handler for the [HSCAN](https://redis.io/docs/latest/commands/hscan/) command
+///
+/// `HSCAN key cursor [MATCH pattern] [COUNT count] [NOVALUES]` => `[next cursor, array of fields and values]`
+///
+/// Incrementally iterates over the fields of the hash stored at `key`, the same way [SCAN](super::keyspace)
+/// iterates over the keys. `MATCH` is applied to the fields. With `NOVALUES`, only the fields are returned.
+///
+/// Returns an [array](https://redis.io/docs/latest/develop/reference/protocol-spec/#arrays) of two elements:
+/// the next cursor as a bulk string, and a flat array of fields and their values.
+///
+/// Example:
+/// - `"*3\r\n$5\r\nHSCAN\r\n$4\r\nhash\r\n$1\r\n0\r\n"` => `*2\r\n$1\r\n0\r\n*2\r\n$1\r\nf\r\n$1\r\nv\r\n`
+pub(crate) fn handle_hscan<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let cursor = parse_cursor(&args[2])?;
+    let options = ScanOptions::parse(&args[3..], false, true)?;
+
+    with_key(storage, &args[1], |value| {
+        let hash = match value {
+            None => return Ok(scan_reply(0, vec![])),
+            Some(Object::Hash(hash)) => hash,
+            Some(_) => return Err(CmdError::WrongType),
+        };
+        let (next, fields) = hash.scan(cursor, options.count);
+        let mut elements = vec![];
+        for (field, value) in fields
+            .into_iter()
+            .filter(|(field, _)| options.matches(field))
+        {
+            elements.push(Value::BulkString(field.clone()));
+            if !options.novalues {
+                elements.push(Value::BulkString(value.clone()));
+            }
+        }
+
+        Ok(scan_reply(next, elements))
+    })?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::tests::{run, storage};
+
+    #[test]
+    fn handle_hscan() {
+        storage().write().unwrap().create(
+            &Bytes::from("hscan"),
+            Object::Hash(
+                (0..50)
+                    .map(|i| (Bytes::from(format!("f{i}")), Bytes::from(format!("v{i}"))))
+                    .collect(),
+            ),
+            None,
+        );
+        let mut elements = vec![];
+        let mut cursor = "0".to_string();
+        loop {
+            let reply = run(&["HSCAN", "hscan", &cursor, "COUNT", "7", "MATCH", "f1*"]).unwrap();
+            let Value::Array(reply) = reply else {
+                panic!("Expected an array");
+            };
+            let Ok([Value::BulkString(next), Value::Array(batch)]) = <[Value; 2]>::try_from(reply)
+            else {
+                panic!("Expected a cursor and an array");
+            };
+            elements.extend(batch);
+            cursor = String::from_utf8(next.to_vec()).unwrap();
+            if cursor == "0" {
+                break;
+            }
+        }
+        // f1 and f10..f19, with their values
+        assert_eq!(22, elements.len());
+        for pair in elements.chunks(2) {
+            let [Value::BulkString(field), Value::BulkString(value)] = pair else {
+                panic!("Expected a field and a value");
+            };
+            assert!(field.starts_with(b"f1"));
+            assert_eq!(value[1..], field[1..]);
+        }
+        let reply = run(&["HSCAN", "hscan", "0", "COUNT", "100", "NOVALUES"]).unwrap();
+        let Value::Array(reply) = reply else {
+            panic!("Expected an array");
+        };
+        assert!(matches!(&reply[1], Value::Array(fields) if fields.len() == 50));
+        assert_eq!(
+            Value::Array(vec![
+                Value::BulkString(Bytes::from("0")),
+                Value::Array(vec![])
+            ]),
+            run(&["HSCAN", "hscan_missing", "0"]).unwrap()
+        );
+        run(&["SET", "hscan_string", "v"]).unwrap();
+        assert!(matches!(
+            run(&["HSCAN", "hscan_string", "0"]),
+            Err(CmdError::WrongType)
+        ));
+        assert!(matches!(
+            run(&["HSCAN", "hscan", "0", "TYPE", "string"]),
+            Err(CmdError::SyntaxError)
+        ));
+    }
+}
@@ -0,0 +1,461 @@
+//! # HyperLogLog Commands
+//!
+//! Handlers for the commands of the [hyperloglog](https://redis.io/docs/latest/commands/?group=hyperloglog) group
+//!
+//! - [Redis HyperLogLog](https://redis.io/docs/latest/develop/data-types/probabilistic/hyperloglogs/)
+//!
+//! HyperLogLogs are stored as string values, in the same format as Redis uses, which is described in
+//! [`crate::storage::hyperloglog`].
+
+use crate::cmd::{read_live, read_lock, write_lock};
+use crate::conn::Client;
+use crate::errors::CmdError;
+use crate::expiry::now_ms;
+use crate::resp::Value;
+use crate::storage::generic::Crud;
+use crate::storage::hyperloglog::{self, HyperLogLog, REGISTERS};
+use crate::storage::object::Object;
+use crate::types::ConcurrentStorageType;
+use bytes::Bytes;
+
+/// Handler for the [PFADD](https://redis.io/docs/latest/commands/pfadd/) command
+///
+/// `PFADD key [element [element ...]]` => `1 or 0`
+///
+/// Adds the elements to the HyperLogLog stored at `key`. An empty HyperLogLog is created if `key` does not exist.
+/// The time to live of the key is retained.
+///
+/// Returns 1 as an [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers)
+/// if at least one register was altered, or if the key was created, and 0 otherwise.
+///
+/// Example:
+/// - `"*4\r\n$5\r\nPFADD\r\n$3\r\nhll\r\n$1\r\na\r\n$1\r\nb\r\n"` => `:1\r\n`
+pub(crate) fn handle_pfadd<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let key = &args[1];
+
+    let now = now_ms()?;
+    let mut s = write_lock(storage);
+    let (mut hll, expiry, mut updated) = match read_live(&*s, key, now) {
+        None => (HyperLogLog::default(), None, true),
+        Some((value, expiry)) => (to_hll(&value)?, expiry, false),
+    };
+    for element in &args[2..] {
+        updated |= hll.add(element)?;
+    }
+    if updated {
+        hll.invalidate_cache();
+        s.update(key, to_object(hll), expiry);
+    }
+
+    Ok(Value::Integer(updated as i64))
+}
+
+/// Handler for the [PFCOUNT](https://redis.io/docs/latest/commands/pfcount/) command
+///
+/// `PFCOUNT key [key ...]` => `cardinality`
+///
+/// Returns the approximated cardinality of the HyperLogLog stored at `key` as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers), or 0 if `key` does not exist.
+///
+/// The cardinality is cached in the HyperLogLog, so counting a single key may modify its value.
+///
+/// With multiple keys, returns the cardinality of the union of the HyperLogLogs, which are merged on the fly
+/// into a temporary HyperLogLog. The keys are not modified.
+///
+/// Example:
+/// - `"*2\r\n$7\r\nPFCOUNT\r\n$3\r\nhll\r\n"` => `:2\r\n`
+pub(crate) fn handle_pfcount<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let now = now_ms()?;
+
+    if args.len() > 2 {
+        let s = read_lock(storage);
+        let mut max = vec![0; REGISTERS];
+        for key in &args[1..] {
+            if let Some((value, _)) = read_live(&*s, key, now) {
+                to_hll(&value)?.merge_into(&mut max)?;
+            }
+        }
+        return Ok(Value::Integer(hyperloglog::count_registers(&max) as i64));
+    }
+
+    let key = &args[1];
+    let mut s = write_lock(storage);
+    let Some((value, expiry)) = read_live(&*s, key, now) else {
+        return Ok(Value::Integer(0));
+    };
+    let mut hll = to_hll(&value)?;
+    let card = match hll.cached_count() {
+        Some(card) => card,
+        None => {
+            let card = hll.count()?;
+            s.update(key, to_object(hll), expiry);
+            card
+        }
+    };
+
+    Ok(Value::Integer(card as i64))
+}
+
+/// Handler for the [PFMERGE](https://redis.io/docs/latest/commands/pfmerge/) command
+///
+/// `PFMERGE destkey [sourcekey [sourcekey ...]]` => `OK`
+///
+/// Merges the HyperLogLogs stored at the source keys, and at `destkey` if it exists, into `destkey`.
+/// The registers of the result are the maximums of the registers of the inputs.
+/// Nonexistent keys are treated as empty HyperLogLogs.
+///
+/// The result uses the dense representation if any of the inputs does, and the sparse one otherwise,
+/// as long as it fits. The time to live of `destkey` is retained.
+///
+/// Example:
+/// - `"*4\r\n$7\r\nPFMERGE\r\n$4\r\nhll3\r\n$4\r\nhll1\r\n$4\r\nhll2\r\n"` => `+OK\r\n`
+pub(crate) fn handle_pfmerge<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let dest = &args[1];
+
+    let now = now_ms()?;
+    let mut s = write_lock(storage);
+    let mut max = vec![0; REGISTERS];
+    let mut dense = false;
+    for key in &args[1..] {
+        if let Some((value, _)) = read_live(&*s, key, now) {
+            let hll = to_hll(&value)?;
+            dense |= !hll.is_sparse();
+            hll.merge_into(&mut max)?;
+        }
+    }
+
+    let (mut hll, expiry) = match read_live(&*s, dest, now) {
+        None => (HyperLogLog::default(), None),
+        Some((value, expiry)) => (to_hll(&value)?, expiry),
+    };
+    hll.merge_from(&max, dense)?;
+    s.update(dest, to_object(hll), expiry);
+
+    Ok(Value::OK)
+}
+
+/// Handler for the [PFDEBUG](https://redis.io/docs/latest/commands/pfdebug/) command
+///
+/// `PFDEBUG subcommand key` => depends on the subcommand
+///
+/// An internal command for testing HyperLogLogs.
+///
+/// Subcommands:
+/// - `GETREG key` => an array of the values of all registers; converts the HyperLogLog to dense
+/// - `DECODE key` => a description of the opcodes of a sparse HyperLogLog, such as `Z:16384`
+/// - `ENCODING key` => `sparse` or `dense`
+/// - `TODENSE key` => 1 if the HyperLogLog was converted to dense, or 0 if it already was dense
+///
+/// Example:
+/// - `"*3\r\n$7\r\nPFDEBUG\r\n$8\r\nENCODING\r\n$3\r\nhll\r\n"` => `+sparse\r\n`
+pub(crate) fn handle_pfdebug<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let key = &args[2];
+
+    let now = now_ms()?;
+    let mut s = write_lock(storage);
+    let (value, expiry) = read_live(&*s, key, now).ok_or(CmdError::KeyDoesNotExist)?;
+    let mut hll = to_hll(&value)?;
+
+    match args[1].to_ascii_lowercase().as_slice() {
+        b"getreg" => {
+            if hll.to_dense()? {
+                s.update(key, to_object(hll.clone()), expiry);
+            }
+            Ok(Value::Array(
+                hll.registers()?
+                    .into_iter()
+                    .map(|register| Value::Integer(register as i64))
+                    .collect(),
+            ))
+        }
+        b"decode" => {
+            let decoded = hll.decode_sparse().ok_or(CmdError::HllNotSparse)?;
+            Ok(Value::BulkString(Bytes::from(decoded)))
+        }
+        b"encoding" => Ok(Value::SimpleString(Bytes::from_static(
+            if hll.is_sparse() { b"sparse" } else { b"dense" },
+        ))),
+        b"todense" => {
+            let converted = hll.to_dense()?;
+            if converted {
+                s.update(key, to_object(hll), expiry);
+            }
+            Ok(Value::Integer(converted as i64))
+        }
+        _ => Err(CmdError::UnknownPfdebugSubcommand(
+            String::from_utf8_lossy(&args[1]).to_string(),
+        )),
+    }
+}
+
+/// Handler for the [PFSELFTEST](https://redis.io/docs/latest/commands/pfselftest/) command
+///
+/// `PFSELFTEST` => `OK`
+///
+/// An internal command for testing HyperLogLogs. It checks the encoding of the dense registers,
+/// and that the sparse and dense representations agree, and estimate cardinalities within the expected error.
+///
+/// Returns a `TESTFAILED` error describing the first failure.
+///
+/// Example:
+/// - `"*1\r\n$10\r\nPFSELFTEST\r\n"` => `+OK\r\n`
+pub(crate) fn handle_pfselftest<KV: Crud, KE: Crud>(
+    _storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    _args: &[Bytes],
+) -> Result<Value, CmdError> {
+    hyperloglog::self_test(now_ms()? as u64).map_err(CmdError::SelfTestFailed)?;
+
+    Ok(Value::OK)
+}
+
+/// Checks that a value is a valid HyperLogLog.
+fn to_hll(value: &Object) -> Result<HyperLogLog, CmdError> {
+    let bytes = value.as_string().ok_or(CmdError::NotHyperLogLog)?;
+    HyperLogLog::from_bytes(bytes)
+}
+
+fn to_object(hll: HyperLogLog) -> Object {
+    Object::String(Bytes::from(hll.into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::tests::{run, storage};
+    use crate::storage::hyperloglog::murmur_hash64a;
+
+    fn get(key: &str) -> Bytes {
+        match run(&["GET", key]).unwrap() {
+            Value::BulkString(value) => value,
+            value => panic!("Expected a bulk string, got {value:?}"),
+        }
+    }
+
+    fn int(value: i64) -> Value {
+        Value::Integer(value)
+    }
+
+    #[test]
+    fn murmur_hash() {
+        assert_eq!(0, murmur_hash64a(b"", 0));
+        let hash = murmur_hash64a(b"hello", 0xadc83b19);
+        assert_ne!(hash, murmur_hash64a(b"hellp", 0xadc83b19));
+        assert_ne!(hash, murmur_hash64a(b"hello", 0));
+    }
+
+    #[test]
+    fn handle_pfadd_creates_sparse() {
+        assert_eq!(int(1), run(&["PFADD", "pfadd_empty"]).unwrap());
+        assert_eq!(
+            Bytes::from_static(b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x80\x7f\xff"),
+            get("pfadd_empty")
+        );
+        assert_eq!(int(0), run(&["PFADD", "pfadd_empty"]).unwrap());
+        assert_eq!(int(0), run(&["PFCOUNT", "pfadd_empty"]).unwrap());
+        // The cached cardinality is now valid.
+        assert_eq!(
+            Bytes::from_static(b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x7f\xff"),
+            get("pfadd_empty")
+        );
+        assert_eq!(
+            Value::BulkString(Bytes::from("Z:16384")),
+            run(&["PFDEBUG", "DECODE", "pfadd_empty"]).unwrap()
+        );
+    }
+
+    #[test]
+    fn handle_pfadd_pfcount() {
+        assert_eq!(
+            int(1),
+            run(&["PFADD", "pfcount", "a", "b", "c", "d", "e", "f", "g"]).unwrap()
+        );
+        assert_eq!(int(7), run(&["PFCOUNT", "pfcount"]).unwrap());
+        assert_eq!(int(0), run(&["PFADD", "pfcount", "a", "b"]).unwrap());
+        assert_eq!(int(7), run(&["PFCOUNT", "pfcount"]).unwrap());
+        assert_eq!(int(0), run(&["PFCOUNT", "pfcount_missing"]).unwrap());
+        assert_eq!(
+            Value::SimpleString(Bytes::from("sparse")),
+            run(&["PFDEBUG", "ENCODING", "pfcount"]).unwrap()
+        );
+
+        let registers = match run(&["PFDEBUG", "GETREG", "pfcount"]).unwrap() {
+            Value::Array(registers) => registers,
+            value => panic!("Expected an array, got {value:?}"),
+        };
+        assert_eq!(REGISTERS, registers.len());
+        assert!(registers.iter().filter(|r| **r != int(0)).count() <= 7);
+        assert_eq!(
+            Value::SimpleString(Bytes::from("dense")),
+            run(&["PFDEBUG", "ENCODING", "pfcount"]).unwrap()
+        );
+        assert_eq!(int(7), run(&["PFCOUNT", "pfcount"]).unwrap());
+    }
+
+    #[test]
+    fn handle_pfadd_sparse_dense_agree() {
+        let elements = (0..2000)
+            .map(|i| format!("element:{i}"))
+            .collect::<Vec<_>>();
+        let mut words = vec!["PFADD", "pfagree_sparse"];
+        words.extend(elements.iter().map(String::as_str));
+        run(&words).unwrap();
+        words[1] = "pfagree_dense";
+        run(&["PFADD", "pfagree_dense"]).unwrap();
+        assert_eq!(
+            int(1),
+            run(&["PFDEBUG", "TODENSE", "pfagree_dense"]).unwrap()
+        );
+        assert_eq!(
+            int(0),
+            run(&["PFDEBUG", "TODENSE", "pfagree_dense"]).unwrap()
+        );
+        run(&words).unwrap();
+
+        let sparse = run(&["PFCOUNT", "pfagree_sparse"]).unwrap();
+        assert_eq!(sparse, run(&["PFCOUNT", "pfagree_dense"]).unwrap());
+        let Value::Integer(card) = sparse else {
+            panic!("Expected an integer");
+        };
+        assert!((1950..=2050).contains(&card), "{card}");
+        assert_eq!(
+            run(&["PFDEBUG", "GETREG", "pfagree_sparse"]).unwrap(),
+            run(&["PFDEBUG", "GETREG", "pfagree_dense"]).unwrap()
+        );
+    }
+
+    #[test]
+    fn handle_pfadd_promotes_large_sparse() {
+        let elements = (0..20000).map(|i| i.to_string()).collect::<Vec<_>>();
+        let mut words = vec!["PFADD", "pfpromote"];
+        words.extend(elements.iter().map(String::as_str));
+        run(&words).unwrap();
+        assert_eq!(
+            Value::SimpleString(Bytes::from("dense")),
+            run(&["PFDEBUG", "ENCODING", "pfpromote"]).unwrap()
+        );
+        assert_eq!(12304, get("pfpromote").len());
+        let Value::Integer(card) = run(&["PFCOUNT", "pfpromote"]).unwrap() else {
+            panic!("Expected an integer");
+        };
+        assert!((19500..=20500).contains(&card), "{card}");
+    }
+
+    #[test]
+    fn handle_pfcount_pfmerge() {
+        run(&["PFADD", "pfmerge1", "foo", "bar", "zap", "a"]).unwrap();
+        run(&["PFADD", "pfmerge2", "a", "b", "c", "foo"]).unwrap();
+        assert_eq!(
+            int(6),
+            run(&["PFCOUNT", "pfmerge1", "pfmerge2", "pfmerge_missing"]).unwrap()
+        );
+        assert_eq!(
+            Value::OK,
+            run(&["PFMERGE", "pfmerge3", "pfmerge1", "pfmerge2"]).unwrap()
+        );
+        assert_eq!(int(6), run(&["PFCOUNT", "pfmerge3"]).unwrap());
+        assert_eq!(
+            Value::SimpleString(Bytes::from("sparse")),
+            run(&["PFDEBUG", "ENCODING", "pfmerge3"]).unwrap()
+        );
+
+        run(&["PFDEBUG", "TODENSE", "pfmerge2"]).unwrap();
+        assert_eq!(
+            Value::OK,
+            run(&["PFMERGE", "pfmerge1", "pfmerge2"]).unwrap()
+        );
+        assert_eq!(int(6), run(&["PFCOUNT", "pfmerge1"]).unwrap());
+        assert_eq!(
+            Value::SimpleString(Bytes::from("dense")),
+            run(&["PFDEBUG", "ENCODING", "pfmerge1"]).unwrap()
+        );
+
+        assert_eq!(Value::OK, run(&["PFMERGE", "pfmerge_empty"]).unwrap());
+        assert_eq!(int(0), run(&["PFCOUNT", "pfmerge_empty"]).unwrap());
+    }
+
+    #[test]
+    fn handle_pf_invalid_values() {
+        run(&["SET", "pfinvalid", "not a hll"]).unwrap();
+        for words in [
+            &["PFADD", "pfinvalid", "a"][..],
+            &["PFCOUNT", "pfinvalid"],
+            &["PFCOUNT", "pfinvalid", "pfinvalid_missing"],
+            &["PFMERGE", "pfinvalid_dest", "pfinvalid"],
+            &["PFDEBUG", "ENCODING", "pfinvalid"],
+        ] {
+            let result = run(words);
+            assert!(matches!(result, Err(CmdError::NotHyperLogLog)), "{words:?}");
+        }
+        assert_eq!(
+            "WRONGTYPE Key is not a valid HyperLogLog string value.",
+            format!(
+                "{} {}",
+                CmdError::NotHyperLogLog.code(),
+                CmdError::NotHyperLogLog
+            )
+        );
+
+        // A sparse HyperLogLog whose opcodes don't cover all registers
+        storage().write().unwrap().create(
+            &Bytes::from_static(b"pfcorrupted"),
+            Object::String(Bytes::from_static(
+                b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x80\x7f\xfe",
+            )),
+            None,
+        );
+        assert!(matches!(
+            run(&["PFCOUNT", "pfcorrupted"]),
+            Err(CmdError::CorruptedHll)
+        ));
+        assert_eq!("INVALIDOBJ", CmdError::CorruptedHll.code());
+
+        // A sparse HyperLogLog that ends in a truncated `XZERO` opcode
+        storage().write().unwrap().create(
+            &Bytes::from_static(b"pftruncated"),
+            Object::String(Bytes::from_static(
+                b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x7f",
+            )),
+            None,
+        );
+        for words in [
+            &["PFADD", "pftruncated", "a", "b", "c"][..],
+            &["PFCOUNT", "pftruncated"],
+            &["PFMERGE", "pftruncated_dest", "pftruncated"],
+        ] {
+            let result = run(words);
+            assert!(matches!(result, Err(CmdError::CorruptedHll)), "{words:?}");
+        }
+
+        assert!(matches!(
+            run(&["PFDEBUG", "ENCODING", "pfdebug_missing"]),
+            Err(CmdError::KeyDoesNotExist)
+        ));
+        run(&["PFADD", "pfdebug", "a"]).unwrap();
+        assert!(matches!(
+            run(&["PFDEBUG", "NOPE", "pfdebug"]),
+            Err(CmdError::UnknownPfdebugSubcommand(sub)) if sub == "NOPE"
+        ));
+        run(&["PFDEBUG", "TODENSE", "pfdebug"]).unwrap();
+        assert!(matches!(
+            run(&["PFDEBUG", "DECODE", "pfdebug"]),
+            Err(CmdError::HllNotSparse)
+        ));
+    }
+}
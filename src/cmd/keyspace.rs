@@ -0,0 +1,1833 @@
+//! # Keyspace Commands
+//!
+//! Handlers for the commands of the [generic](https://redis.io/docs/latest/commands/?group=generic) group,
+//! which work with keys of any type
+
+use crate::cmd::{
+    get_live, parse_db_index, parse_integer, read_live, read_lock, with_key, write_lock,
+    write_lock_pair,
+};
+use crate::conn::Client;
+use crate::errors::CmdError;
+use crate::expiry::{is_expired, now_ms};
+use crate::glob;
+use crate::lazyfree;
+use crate::random::random_u64;
+use crate::resp::Value;
+use crate::storage::generic::Crud;
+use crate::storage::metadata::{Encoding, Metadata};
+use crate::storage::rdb;
+use crate::types::{
+    ConcurrentStorageType, ExpirationTime, ExpirationTimeType, StorageKey, StorageType,
+    StorageValue,
+};
+use bytes::Bytes;
+
+/// Handler for the [DEL](https://redis.io/docs/latest/commands/del/) command
+///
+/// `DEL key [key ...]` => `number of keys that were removed`
+///
+/// Removes the specified keys. A key is ignored if it does not exist.
+///
+/// Returns the number of keys that were removed as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers).
+///
+/// Example:
+/// - `"*3\r\n$3\r\nDEL\r\n$4\r\nkey1\r\n$4\r\nkey2\r\n"` => `:2\r\n`
+pub(crate) fn handle_del<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let removed = remove_keys(storage, &args[1..])?;
+    Ok(Value::Integer(removed.len() as i64))
+}
+
+/// Handler for the [UNLINK](https://redis.io/docs/latest/commands/unlink/) command
+///
+/// `UNLINK key [key ...]` => `number of keys that were unlinked`
+///
+/// Removes the specified keys, like [DEL](handle_del), but large values are freed in a background thread,
+/// so the command doesn't block.
+///
+/// Returns the number of keys that were unlinked as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers).
+///
+/// Example:
+/// - `"*3\r\n$6\r\nUNLINK\r\n$4\r\nkey1\r\n$4\r\nkey2\r\n"` => `:2\r\n`
+pub(crate) fn handle_unlink<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let removed = remove_keys(storage, &args[1..])?;
+    let count = removed.len();
+    removed.into_iter().for_each(lazyfree::free);
+    Ok(Value::Integer(count as i64))
+}
+
+/// Handler for the [EXISTS](https://redis.io/docs/latest/commands/exists/) command
+///
+/// `EXISTS key [key ...]` => `number of keys that exist`
+///
+/// Returns the number of the specified keys that exist as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers).
+/// A key that is mentioned multiple times is counted multiple times.
+///
+/// Example:
+/// - `"*3\r\n$6\r\nEXISTS\r\n$4\r\nkey1\r\n$4\r\nkey1\r\n"` => `:2\r\n`
+pub(crate) fn handle_exists<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    Ok(Value::Integer(count_live(storage, &args[1..])? as i64))
+}
+
+/// Handler for the [KEYS](https://redis.io/docs/latest/commands/keys/) command
+///
+/// `KEYS pattern` => `array of keys`
+///
+/// Returns all keys matching the glob-style `pattern` as an
+/// [array](https://redis.io/docs/latest/develop/reference/protocol-spec/#arrays) of bulk strings,
+/// in no particular order. See [`glob`] for the supported patterns.
+///
+/// It is meant for debugging, as it blocks the server while it goes over the whole keyspace.
+///
+/// Example:
+/// - `"*2\r\n$4\r\nKEYS\r\n$3\r\na?c\r\n"` => `*1\r\n$3\r\nabc\r\n`
+pub(crate) fn handle_keys<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let pattern = &args[1];
+    let all_keys = pattern.as_ref() == b"*";
+
+    let now = now_ms()?;
+    let s = read_lock(storage);
+    let keys = s
+        .keys()
+        .into_iter()
+        .filter(|key| all_keys || glob::string_match(pattern, key, false))
+        .filter(|key| s.expiry(key).is_some_and(|expiry| !is_expired(expiry, now)))
+        .map(Value::BulkString)
+        .collect();
+
+    Ok(Value::Array(keys))
+}
+
+/// Handler for the [OBJECT](https://redis.io/docs/latest/commands/object/) command
+///
+/// `OBJECT <subcommand> [<arg> ...]`
+///
+/// Inspects the metadata of a key. Inspecting a key doesn't count as an access of it.
+///
+/// Subcommands:
+/// - `ENCODING key` => the internal representation that Redis would use for the value, such as `embstr` or `listpack`
+/// - `FREQ key` => the logarithmic access frequency counter
+/// - `HELP` => the list of subcommands
+/// - `IDLETIME key` => the number of seconds since the last access
+/// - `REFCOUNT key` => the number of references to the value
+///
+/// The subcommands that take a key return a nil value if the key does not exist.
+///
+/// There are no eviction policies in our server, so both the idle time and the access frequency are always tracked,
+/// unlike in Redis, which only tracks the one that its eviction policy goes by.
+///
+/// Example:
+/// - `"*3\r\n$6\r\nOBJECT\r\n$8\r\nENCODING\r\n$3\r\nkey\r\n"` => `$6\r\nembstr\r\n`
+pub(crate) fn handle_object<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let subcommand = args[1].to_ascii_lowercase();
+    let arity = match subcommand.as_slice() {
+        b"help" => 2,
+        b"encoding" | b"freq" | b"idletime" | b"refcount" => 3,
+        _ => {
+            return Err(CmdError::UnknownSubcommand(
+                "OBJECT".to_string(),
+                String::from_utf8_lossy(&args[1]).to_string(),
+            ))
+        }
+    };
+    if args.len() != arity {
+        return Err(CmdError::WrongNumberOfArgs(format!(
+            "object|{}",
+            String::from_utf8_lossy(&subcommand)
+        )));
+    }
+    if subcommand == b"help" {
+        return Ok(Value::Array(
+            [
+                "OBJECT <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+                "ENCODING <key>",
+                "    Return the kind of internal representation used in order to store the value",
+                "    associated with a <key>.",
+                "FREQ <key>",
+                "    Return the access frequency index of the <key>. The returned integer is",
+                "    proportional to the logarithm of the recent access frequency of the key.",
+                "IDLETIME <key>",
+                "    Return the idle time of the <key>, that is the approximated number of",
+                "    seconds elapsed since the last access to the key.",
+                "REFCOUNT <key>",
+                "    Return the number of references of the value associated with the specified",
+                "    <key>.",
+                "HELP",
+                "    Print this help.",
+            ]
+            .into_iter()
+            .map(|line| Value::SimpleString(Bytes::from_static(line.as_bytes())))
+            .collect(),
+        ));
+    }
+
+    let key = &args[2];
+    let now = now_ms()?;
+    let s = read_lock(storage);
+    let Some((value, _)) = get_live(&*s, key, now) else {
+        return Ok(Value::Null);
+    };
+    // Stores that don't keep metadata report the metadata of a new key.
+    let metadata = s
+        .metadata(key)
+        .cloned()
+        .unwrap_or_else(|| Metadata::new(Encoding::of(value, None)));
+
+    Ok(match subcommand.as_slice() {
+        b"encoding" => {
+            Value::BulkString(Bytes::from_static(metadata.encoding().as_str().as_bytes()))
+        }
+        b"freq" => Value::Integer(metadata.frequency() as i64),
+        b"idletime" => Value::Integer((metadata.idle_time_ms() / 1000) as i64),
+        b"refcount" => {
+            // Small integers are shared between all keys that hold them, and shared objects report
+            // the maximum reference count.
+            let shared = metadata.encoding() == Encoding::Int
+                && value
+                    .as_string()
+                    .and_then(|value| parse_integer(value).ok())
+                    .is_some_and(|n| (0..OBJ_SHARED_INTEGERS).contains(&n));
+            Value::Integer(if shared { i32::MAX as i64 } else { 1 })
+        }
+        _ => unreachable!("Unknown subcommands have been rejected above"),
+    })
+}
+
+/// Handler for the [DUMP](https://redis.io/docs/latest/commands/dump/) command
+///
+/// `DUMP key` => `serialized value`
+///
+/// Serializes the value stored at `key` in the [RDB format](crate::storage::rdb) of Redis, so that it can be
+/// restored with [RESTORE](handle_restore), here or in Redis. The time to live isn't part of the payload.
+///
+/// Returns the payload as a
+/// [bulk string](https://redis.io/docs/latest/develop/reference/protocol-spec/#bulk-strings),
+/// or a nil value if `key` does not exist.
+///
+/// Example:
+/// - `"*2\r\n$4\r\nDUMP\r\n$3\r\nkey\r\n"` => `$13\r\n\x00\xc0\n\x0c\x00...\r\n`
+pub(crate) fn handle_dump<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let key = &args[1];
+    let now = now_ms()?;
+    let s = read_lock(storage);
+    let Some((value, _)) = get_live(&*s, key, now) else {
+        return Ok(Value::Null);
+    };
+    let encoding = s
+        .metadata(key)
+        .map_or_else(|| Encoding::of(value, None), Metadata::encoding);
+
+    Ok(Value::BulkString(Bytes::from(rdb::dump(value, encoding)?)))
+}
+
+/// Handler for the [RESTORE](https://redis.io/docs/latest/commands/restore/) command
+///
+/// `RESTORE key ttl serialized-value [REPLACE] [ABSTTL] [IDLETIME seconds] [FREQ frequency]` => `OK`
+///
+/// Creates `key` with the value of a payload produced by [DUMP](handle_dump), here or in Redis.
+/// The key expires in `ttl` milliseconds, or never if `ttl` is 0.
+///
+/// Options:
+/// - `REPLACE` -- Overwrite `key` if it already exists.
+/// - `ABSTTL` -- `ttl` is an absolute Unix time in milliseconds; the key isn't created if it's in the past.
+/// - `IDLETIME seconds` -- The idle time of the key, as reported by `OBJECT IDLETIME`.
+/// - `FREQ frequency` -- The access frequency counter of the key, as reported by `OBJECT FREQ`;
+///   mutually exclusive with `IDLETIME`.
+///
+/// Fails with a `BUSYKEY` error if `key` already exists without `REPLACE`, and with an error if the payload
+/// comes from a newer RDB version, or if its checksum doesn't match.
+///
+/// Returns `OK` as a
+/// [simple string](https://redis.io/docs/latest/develop/reference/protocol-spec/#simple-strings).
+///
+/// Example:
+/// - `"*4\r\n$7\r\nRESTORE\r\n$3\r\nkey\r\n$1\r\n0\r\n$13\r\n\x00\xc0\n...\r\n"` => `+OK\r\n`
+pub(crate) fn handle_restore<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let key = &args[1];
+
+    let mut replace = false;
+    let mut absttl = false;
+    let mut idle_time = None;
+    let mut frequency = None;
+    let mut i = 4;
+    while i < args.len() {
+        match args[i].to_ascii_uppercase().as_slice() {
+            b"REPLACE" => replace = true,
+            b"ABSTTL" => absttl = true,
+            b"IDLETIME" if i + 1 < args.len() && frequency.is_none() => {
+                let seconds = parse_integer(&args[i + 1])?;
+                idle_time = Some(u64::try_from(seconds).map_err(|_| CmdError::InvalidIdleTime)?);
+                i += 1;
+            }
+            b"FREQ" if i + 1 < args.len() && idle_time.is_none() => {
+                let counter = parse_integer(&args[i + 1])?;
+                frequency = Some(u8::try_from(counter).map_err(|_| CmdError::InvalidFrequency)?);
+                i += 1;
+            }
+            _ => return Err(CmdError::SyntaxError),
+        }
+        i += 1;
+    }
+
+    // An existing key is reported before an invalid TTL or payload, the same as in Redis,
+    // so their errors are only returned once the key has been checked.
+    let ttl = parse_integer(&args[2])
+        .and_then(|ttl| ExpirationTimeType::try_from(ttl).map_err(|_| CmdError::NegativeTtl));
+    let value = rdb::restore(&args[3]);
+
+    let now = now_ms()?;
+    let mut s = write_lock(storage);
+    if !replace && get_live(&*s, key, now).is_some() {
+        return Err(CmdError::BusyKey);
+    }
+    let (ttl, value) = (ttl?, value?);
+
+    s.delete(key);
+    let expiry = match ttl {
+        0 => None,
+        ttl if absttl => Some(ttl),
+        ttl => Some(now + ttl),
+    };
+    if is_expired(expiry, now) {
+        return Ok(Value::OK);
+    }
+    s.create(key, value, expiry);
+    if let Some(metadata) = s.metadata(key) {
+        if let Some(seconds) = idle_time {
+            metadata.set_idle_time_ms(seconds.saturating_mul(1000));
+        }
+        if let Some(counter) = frequency {
+            metadata.set_frequency(counter);
+        }
+    }
+
+    Ok(Value::OK)
+}
+
+/// Handler for the [RANDOMKEY](https://redis.io/docs/latest/commands/randomkey/) command
+///
+/// `RANDOMKEY` => `a random key`
+///
+/// Returns a random key from the selected database as a
+/// [bulk string](https://redis.io/docs/latest/develop/reference/protocol-spec/#bulk-strings),
+/// or a nil value if the database is empty.
+///
+/// The key is picked at a random [cursor](crate::storage::cursor) position, which takes O(log N) time,
+/// under the read lock. Expired keys that are picked along the way are deleted under the write lock,
+/// and another key is picked instead.
+///
+/// Example:
+/// - `"*1\r\n$9\r\nRANDOMKEY\r\n"` => `$3\r\nkey\r\n`
+pub(crate) fn handle_randomkey<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    _args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let now = now_ms()?;
+    let expired = |s: &StorageType<KV, KE>, key: &StorageKey| {
+        s.expiry(key).is_some_and(|expiry| is_expired(expiry, now))
+    };
+    // Every pick either returns a key or deletes one, so the loop ends.
+    loop {
+        let key = {
+            let s = read_lock(storage);
+            let Some(key) = random_key(&*s) else {
+                return Ok(Value::Null);
+            };
+            if !expired(&s, &key) {
+                return Ok(Value::BulkString(key));
+            }
+            key
+        };
+        let mut s = write_lock(storage);
+        // The key could have been set again in the meantime, so it's checked once more under the write lock.
+        if expired(&s, &key) {
+            s.delete(&key);
+        }
+    }
+}
+
+/// Picks the key at a random [cursor](crate::storage::cursor) position, or returns `None` if there are no keys.
+fn random_key<S: Crud>(s: &S) -> Option<StorageKey> {
+    let keys = match s.scan(random_u64(), 1) {
+        (_, keys) if !keys.is_empty() => keys,
+        // There are no keys at or after the position, so the first key is picked, if there is one.
+        _ => s.scan(0, 1).1,
+    };
+    keys.into_iter().next()
+}
+
+/// Handler for the [RENAME](https://redis.io/docs/latest/commands/rename/) command
+///
+/// `RENAME key newkey` => `OK`
+///
+/// Renames `key` to `newkey`, together with its time to live. If `newkey` already exists, it is overwritten.
+/// The whole operation is atomic.
+///
+/// Returns an error if `key` does not exist.
+///
+/// Example:
+/// - `"*3\r\n$6\r\nRENAME\r\n$7\r\ntmp:key\r\n$3\r\nkey\r\n"` => `+OK\r\n`
+pub(crate) fn handle_rename<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    rename_generic(storage, &args[1], &args[2], false)?;
+    Ok(Value::OK)
+}
+
+/// Handler for the [RENAMENX](https://redis.io/docs/latest/commands/renamenx/) command
+///
+/// `RENAMENX key newkey` => `1 or 0`
+///
+/// Renames `key` to `newkey`, together with its time to live, only if `newkey` does not exist yet.
+///
+/// Returns 1 as an [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers)
+/// if `key` was renamed, and 0 if `newkey` already exists. Returns an error if `key` does not exist.
+///
+/// Example:
+/// - `"*3\r\n$8\r\nRENAMENX\r\n$7\r\ntmp:key\r\n$3\r\nkey\r\n"` => `:1\r\n`
+pub(crate) fn handle_renamenx<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let renamed = rename_generic(storage, &args[1], &args[2], true)?;
+    Ok(Value::Integer(renamed as i64))
+}
+
+/// Handler for the [COPY](https://redis.io/docs/latest/commands/copy/) command
+///
+/// `COPY source destination [DB destination-db] [REPLACE]` => `1 or 0`
+///
+/// Copies the value stored at `source`, together with its time to live, to `destination`.
+///
+/// Options:
+/// - `DB destination-db` -- The index of the database to copy to; the default is the current one.
+/// - `REPLACE` -- Overwrite `destination` if it already exists.
+///
+/// Returns 1 as an [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers)
+/// if `source` was copied, and 0 if it does not exist, or if `destination` already exists without `REPLACE`.
+///
+/// Example:
+/// - `"*3\r\n$4\r\nCOPY\r\n$7\r\ndolly\r\n$5\r\nclone\r\n"` => `:1\r\n`
+pub(crate) fn handle_copy<KV: Crud, KE: Crud>(
+    databases: &[ConcurrentStorageType<KV, KE>],
+    client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let (source, destination) = (&args[1], &args[2]);
+
+    let mut replace = false;
+    let mut db = client.db;
+    let mut i = 3;
+    while i < args.len() {
+        match args[i].to_ascii_uppercase().as_slice() {
+            b"REPLACE" => replace = true,
+            b"DB" if i + 1 < args.len() => {
+                db = parse_db_index(&args[i + 1], databases.len())?;
+                i += 1;
+            }
+            _ => return Err(CmdError::SyntaxError),
+        }
+        i += 1;
+    }
+    if db == client.db && source == destination {
+        return Err(CmdError::SameObject);
+    }
+
+    let now = now_ms()?;
+    let copied = if db == client.db {
+        let mut s = write_lock(&databases[db]);
+        let entry = read_live(&*s, source, now);
+        put_entry(&mut *s, destination, entry, replace, now)
+    } else {
+        let (src, mut dst) = write_lock_pair(databases, client.db, db);
+        let entry = read_live(&*src, source, now);
+        put_entry(&mut *dst, destination, entry, replace, now)
+    };
+
+    Ok(Value::Integer(copied as i64))
+}
+
+/// Handler for the [MOVE](https://redis.io/docs/latest/commands/move/) command
+///
+/// `MOVE key db` => `1 or 0`
+///
+/// Moves `key`, together with its time to live, from the current database to the database `db`.
+///
+/// Returns 1 as an [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers)
+/// if `key` was moved, and 0 if it does not exist, or if it already exists in `db`.
+///
+/// Example:
+/// - `"*3\r\n$4\r\nMOVE\r\n$3\r\nkey\r\n$1\r\n1\r\n"` => `:1\r\n`
+pub(crate) fn handle_move<KV: Crud, KE: Crud>(
+    databases: &[ConcurrentStorageType<KV, KE>],
+    client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let key = &args[1];
+    let db = parse_db_index(&args[2], databases.len())?;
+    if db == client.db {
+        return Err(CmdError::SameObject);
+    }
+
+    let now = now_ms()?;
+    let (mut src, mut dst) = write_lock_pair(databases, client.db, db);
+    if get_live(&*src, key, now).is_none() || get_live(&*dst, key, now).is_some() {
+        return Ok(Value::Integer(0));
+    }
+    let entry = take_entry(&mut *src, key).expect("The key exists");
+    insert_entry(&mut *dst, key, entry);
+
+    Ok(Value::Integer(1))
+}
+
+/// Handler for the [SCAN](https://redis.io/docs/latest/commands/scan/) command
+///
+/// `SCAN cursor [MATCH pattern] [COUNT count] [TYPE type]` => `[next cursor, array of keys]`
+///
+/// Incrementally iterates over the keys. A full iteration starts with the cursor 0, and continues with the cursor
+/// returned by each call, until it returns 0. Every key that is present for the whole iteration is returned.
+/// See [`cursor`](crate::storage::cursor) for how the cursors work.
+///
+/// Options:
+/// - `MATCH pattern` -- Only return keys that match the glob-style pattern. See [`glob`] for the supported patterns.
+/// - `COUNT count` -- The amount of work to do in a single call; the default is 10.
+/// - `TYPE type` -- Only return keys that hold values of the given type, such as `string` or `zset`.
+///
+/// The options are applied after the keys are selected, so a call may return fewer keys than `count`, or none,
+/// while the iteration isn't complete.
+///
+/// Returns an [array](https://redis.io/docs/latest/develop/reference/protocol-spec/#arrays) of two elements:
+/// the next cursor as a bulk string, and an array of keys.
+///
+/// Example:
+/// - `"*2\r\n$4\r\nSCAN\r\n$1\r\n0\r\n"` => `*2\r\n$1\r\n0\r\n*1\r\n$3\r\nkey\r\n`
+pub(crate) fn handle_scan<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let cursor = parse_cursor(&args[1])?;
+    let options = ScanOptions::parse(&args[2..], true, false)?;
+
+    let now = now_ms()?;
+    let s = read_lock(storage);
+    let (next, keys) = s.scan(cursor, options.count);
+    let keys = keys
+        .into_iter()
+        .filter(|key| s.expiry(key).is_some_and(|expiry| !is_expired(expiry, now)))
+        .filter(|key| options.matches(key))
+        .filter(|key| match options.type_name {
+            None => true,
+            Some(type_name) => s
+                .get(key)
+                .is_some_and(|(value, _)| value.type_name() == type_name),
+        })
+        .map(Value::BulkString)
+        .collect();
+
+    Ok(scan_reply(next, keys))
+}
+
+/// Handler for the [TOUCH](https://redis.io/docs/latest/commands/touch/) command
+///
+/// `TOUCH key [key ...]` => `number of keys that were touched`
+///
+/// Alters the last access time and the LFU counter of the specified keys, even if the client has turned on
+/// `CLIENT NO-TOUCH`. A key is ignored if it does not exist.
+///
+/// Returns the number of keys that were touched as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers).
+///
+/// Example:
+/// - `"*3\r\n$5\r\nTOUCH\r\n$4\r\nkey1\r\n$4\r\nkey2\r\n"` => `:2\r\n`
+pub(crate) fn handle_touch<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let now = now_ms()?;
+    let s = read_lock(storage);
+    let touched = args[1..]
+        .iter()
+        .filter(|key| s.expiry(key).is_some_and(|expiry| !is_expired(expiry, now)))
+        .inspect(|key| s.touch(key))
+        .count();
+    Ok(Value::Integer(touched as i64))
+}
+
+/// Handler for the [EXPIRE](https://redis.io/docs/latest/commands/expire/) command
+///
+/// `EXPIRE key seconds [NX | XX | GT | LT]` => `1 or 0`
+///
+/// Sets a timeout on `key` in seconds, after which the key is deleted. A timeout that is in the past, including
+/// a non-positive one, deletes the key immediately.
+///
+/// Options:
+/// - `NX` -- Set expiry only when the key has no expiry.
+/// - `XX` -- Set expiry only when the key has an existing expiry.
+/// - `GT` -- Set expiry only when the new expiry is greater than the current one. A key without an expiry
+///   is treated as having an infinite one.
+/// - `LT` -- Set expiry only when the new expiry is less than the current one.
+///
+/// Returns 1 as an [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers)
+/// if the timeout was set, and 0 if it wasn't, because the key doesn't exist or a condition wasn't met.
+///
+/// Example:
+/// - `"*3\r\n$6\r\nEXPIRE\r\n$5\r\nmykey\r\n$2\r\n10\r\n"` => `:1\r\n`
+pub(crate) fn handle_expire<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    expire_generic(storage, args, "expire", 1000, false)
+}
+
+/// Handler for the [PEXPIRE](https://redis.io/docs/latest/commands/pexpire/) command
+///
+/// `PEXPIRE key milliseconds [NX | XX | GT | LT]` => `1 or 0`
+///
+/// Works exactly like [EXPIRE](handle_expire), but the timeout is specified in milliseconds.
+///
+/// Example:
+/// - `"*3\r\n$7\r\nPEXPIRE\r\n$5\r\nmykey\r\n$4\r\n1500\r\n"` => `:1\r\n`
+pub(crate) fn handle_pexpire<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    expire_generic(storage, args, "pexpire", 1, false)
+}
+
+/// Handler for the [EXPIREAT](https://redis.io/docs/latest/commands/expireat/) command
+///
+/// `EXPIREAT key unix-time-seconds [NX | XX | GT | LT]` => `1 or 0`
+///
+/// Works exactly like [EXPIRE](handle_expire), but the key expires at an absolute Unix time in seconds.
+///
+/// Example:
+/// - `"*3\r\n$8\r\nEXPIREAT\r\n$5\r\nmykey\r\n$10\r\n1293840000\r\n"` => `:1\r\n`
+pub(crate) fn handle_expireat<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    expire_generic(storage, args, "expireat", 1000, true)
+}
+
+/// Handler for the [PEXPIREAT](https://redis.io/docs/latest/commands/pexpireat/) command
+///
+/// `PEXPIREAT key unix-time-milliseconds [NX | XX | GT | LT]` => `1 or 0`
+///
+/// Works exactly like [EXPIREAT](handle_expireat), but the Unix time is specified in milliseconds.
+///
+/// Example:
+/// - `"*3\r\n$9\r\nPEXPIREAT\r\n$5\r\nmykey\r\n$13\r\n1555555555005\r\n"` => `:1\r\n`
+pub(crate) fn handle_pexpireat<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    expire_generic(storage, args, "pexpireat", 1, true)
+}
+
+/// Handler for the [TTL](https://redis.io/docs/latest/commands/ttl/) command
+///
+/// `TTL key` => `remaining time to live in seconds`
+///
+/// Returns the remaining time to live of `key` in seconds as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers),
+/// -1 if the key exists but has no associated expiry, or -2 if the key does not exist.
+///
+/// Example:
+/// - `"*2\r\n$3\r\nTTL\r\n$5\r\nmykey\r\n"` => `:10\r\n`
+pub(crate) fn handle_ttl<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    ttl_generic(storage, &args[1], |expiry, now| {
+        (expiry.saturating_sub(now) + 500) / 1000
+    })
+}
+
+/// Handler for the [PTTL](https://redis.io/docs/latest/commands/pttl/) command
+///
+/// `PTTL key` => `remaining time to live in milliseconds`
+///
+/// Works exactly like [TTL](handle_ttl), but returns the time to live in milliseconds.
+///
+/// Example:
+/// - `"*2\r\n$4\r\nPTTL\r\n$5\r\nmykey\r\n"` => `:1499\r\n`
+pub(crate) fn handle_pttl<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    ttl_generic(storage, &args[1], |expiry, now| expiry.saturating_sub(now))
+}
+
+/// Handler for the [EXPIRETIME](https://redis.io/docs/latest/commands/expiretime/) command
+///
+/// `EXPIRETIME key` => `expiration Unix time in seconds`
+///
+/// Returns the absolute Unix time in seconds at which `key` will expire as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers),
+/// -1 if the key exists but has no associated expiry, or -2 if the key does not exist.
+///
+/// Example:
+/// - `"*2\r\n$10\r\nEXPIRETIME\r\n$5\r\nmykey\r\n"` => `:33177117420\r\n`
+pub(crate) fn handle_expiretime<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    ttl_generic(storage, &args[1], |expiry, _| expiry / 1000)
+}
+
+/// Handler for the [PEXPIRETIME](https://redis.io/docs/latest/commands/pexpiretime/) command
+///
+/// `PEXPIRETIME key` => `expiration Unix time in milliseconds`
+///
+/// Works exactly like [EXPIRETIME](handle_expiretime), but returns the Unix time in milliseconds.
+///
+/// Example:
+/// - `"*2\r\n$11\r\nPEXPIRETIME\r\n$5\r\nmykey\r\n"` => `:33177117420000\r\n`
+pub(crate) fn handle_pexpiretime<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    ttl_generic(storage, &args[1], |expiry, _| expiry)
+}
+
+/// Handler for the [PERSIST](https://redis.io/docs/latest/commands/persist/) command
+///
+/// `PERSIST key` => `1 or 0`
+///
+/// Removes the existing timeout on `key`, turning the key from volatile to persistent.
+///
+/// Returns 1 as an [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers)
+/// if the timeout was removed, and 0 if the key does not exist or does not have an associated timeout.
+///
+/// Example:
+/// - `"*2\r\n$7\r\nPERSIST\r\n$5\r\nmykey\r\n"` => `:1\r\n`
+pub(crate) fn handle_persist<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let key = &args[1];
+
+    let now = now_ms()?;
+    let mut s = write_lock(storage);
+    let persisted = match s.expiry(key) {
+        Some(Some(expiry)) if is_expired(Some(expiry), now) => {
+            s.delete(key);
+            false
+        }
+        Some(Some(_)) => {
+            s.set_expiry(key, None);
+            true
+        }
+        _ => false,
+    };
+
+    Ok(Value::Integer(persisted as i64))
+}
+
+/// Handler for the [TYPE](https://redis.io/docs/latest/commands/type/) command
+///
+/// `TYPE key` => `type`
+///
+/// Returns the string representation of the type of the value stored at `key`, as a
+/// [simple string](https://redis.io/docs/latest/develop/reference/protocol-spec/#simple-strings).
+/// The different types that can be returned are: `string`, `list`, `set`, `zset`, `hash` and `stream`,
+/// or `none` if the key doesn't exist.
+///
+/// Example:
+/// - `"*2\r\n$4\r\nTYPE\r\n$6\r\norange\r\n"` => `+string\r\n`
+pub(crate) fn handle_type<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let type_name = with_key(storage, &args[1], |value| {
+        value.map_or("none", |value| value.type_name())
+    })?;
+    Ok(Value::SimpleString(Bytes::from_static(
+        type_name.as_bytes(),
+    )))
+}
+
+/// Renames `key` to `newkey` under a single write lock, and returns whether it was renamed.
+///
+/// With `nx`, an existing `newkey` isn't overwritten.
+fn rename_generic<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    key: &StorageKey,
+    newkey: &StorageKey,
+    nx: bool,
+) -> Result<bool, CmdError> {
+    let now = now_ms()?;
+    let mut s = write_lock(storage);
+    if get_live(&*s, key, now).is_none() {
+        return Err(CmdError::NoSuchKey);
+    }
+    if key == newkey {
+        return Ok(!nx);
+    }
+    if nx && get_live(&*s, newkey, now).is_some() {
+        return Ok(false);
+    }
+    let entry = take_entry(&mut *s, key).expect("The key exists");
+    insert_entry(&mut *s, newkey, entry);
+    Ok(true)
+}
+
+/// Integers from 0 up to this number are shared between all keys that hold them, `OBJ_SHARED_INTEGERS`
+const OBJ_SHARED_INTEGERS: i64 = 10000;
+
+/// An entry removed from a store, together with its metadata, if the store keeps any
+type TakenEntry = (StorageValue, ExpirationTime, Option<Metadata>);
+
+/// Removes a key for `RENAME` or `MOVE`, and returns its entry, which is moved rather than copied.
+fn take_entry<S: Crud>(s: &mut S, key: &StorageKey) -> Option<TakenEntry> {
+    let metadata = s.metadata(key).cloned();
+    let (value, expiry) = s.remove(key)?;
+    Some((value, expiry, metadata))
+}
+
+/// Writes an entry taken by [`take_entry`] to `key`, which keeps the encoding and the access statistics of
+/// the value, the same as Redis keeps the same object when a key is renamed or moved.
+fn insert_entry<S: Crud>(s: &mut S, key: &StorageKey, (value, expiry, metadata): TakenEntry) {
+    s.create(key, value, expiry);
+    if let Some(metadata) = metadata {
+        s.set_metadata(key, metadata);
+    }
+}
+
+/// Writes an entry, read from the source of `COPY`, to `key` in the already locked destination storage.
+///
+/// Returns whether the entry was written, which it isn't if there is no entry, or if `key` exists,
+/// unless `replace` is set.
+fn put_entry<S: Crud>(
+    dst: &mut S,
+    key: &StorageKey,
+    entry: Option<(StorageValue, ExpirationTime)>,
+    replace: bool,
+    now: ExpirationTimeType,
+) -> bool {
+    let Some((value, expiry)) = entry else {
+        return false;
+    };
+    if !replace && get_live(dst, key, now).is_some() {
+        return false;
+    }
+    dst.create(key, value, expiry);
+    true
+}
+
+/// The options of the `SCAN` family of commands
+pub(crate) struct ScanOptions {
+    /// Only elements that match this glob-style pattern are returned.
+    pub(crate) pattern: Option<Bytes>,
+    /// The number of elements to select in a single call
+    pub(crate) count: usize,
+    /// Only keys that hold values of this type are returned; `SCAN` only.
+    pub(crate) type_name: Option<&'static str>,
+    /// Only fields are returned, without their values; `HSCAN` only.
+    pub(crate) novalues: bool,
+}
+
+impl ScanOptions {
+    /// Parses the options that follow the cursor, allowing `TYPE` and `NOVALUES` only where they're supported.
+    pub(crate) fn parse(
+        args: &[Bytes],
+        allow_type: bool,
+        allow_novalues: bool,
+    ) -> Result<Self, CmdError> {
+        let mut options = Self {
+            pattern: None,
+            count: 10,
+            type_name: None,
+            novalues: false,
+        };
+        let mut i = 0;
+        while i < args.len() {
+            let option = args[i].to_ascii_uppercase();
+            let value = args.get(i + 1);
+            match (option.as_slice(), value) {
+                (b"MATCH", Some(pattern)) => {
+                    // `*` matches everything, so it's the same as no pattern.
+                    options.pattern = (pattern.as_ref() != b"*").then(|| pattern.clone());
+                }
+                (b"COUNT", Some(count)) => {
+                    options.count = match parse_integer(count)? {
+                        count if count < 1 => return Err(CmdError::SyntaxError),
+                        count => count as usize,
+                    };
+                }
+                (b"TYPE", Some(type_name)) if allow_type => {
+                    let type_name = ["string", "list", "set", "zset", "hash", "stream"]
+                        .into_iter()
+                        .find(|name| type_name.eq_ignore_ascii_case(name.as_bytes()))
+                        .ok_or_else(|| {
+                            CmdError::UnknownTypeName(
+                                String::from_utf8_lossy(type_name).to_string(),
+                            )
+                        })?;
+                    options.type_name = Some(type_name);
+                }
+                (b"NOVALUES", _) if allow_novalues => {
+                    options.novalues = true;
+                    i += 1;
+                    continue;
+                }
+                _ => return Err(CmdError::SyntaxError),
+            }
+            i += 2;
+        }
+        Ok(options)
+    }
+
+    /// Whether an element matches the pattern, if there is one
+    pub(crate) fn matches(&self, element: &[u8]) -> bool {
+        self.pattern
+            .as_ref()
+            .is_none_or(|pattern| glob::string_match(pattern, element, false))
+    }
+}
+
+/// Parses the cursor of the `SCAN` family of commands, which is an unsigned 64-bit integer.
+pub(crate) fn parse_cursor(arg: &[u8]) -> Result<u64, CmdError> {
+    std::str::from_utf8(arg)
+        .ok()
+        .filter(|cursor| cursor.bytes().all(|b| b.is_ascii_digit()))
+        .and_then(|cursor| cursor.parse().ok())
+        .ok_or(CmdError::InvalidCursor)
+}
+
+/// Builds the reply of the `SCAN` family of commands: the next cursor and the elements.
+pub(crate) fn scan_reply(cursor: u64, elements: Vec<Value>) -> Value {
+    Value::Array(vec![
+        Value::BulkString(Bytes::from(cursor.to_string())),
+        Value::Array(elements),
+    ])
+}
+
+/// Sets the expiration time of a key, for the `EXPIRE` family of commands.
+///
+/// The time is given in `unit_ms` milliseconds, and is relative to now unless `absolute` is set.
+fn expire_generic<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    args: &[Bytes],
+    cmd: &str,
+    unit_ms: i64,
+    absolute: bool,
+) -> Result<Value, CmdError> {
+    let key = &args[1];
+    let when = parse_integer(&args[2])?;
+
+    let (mut nx, mut xx, mut gt, mut lt) = (false, false, false, false);
+    for option in &args[3..] {
+        match option.to_ascii_uppercase().as_slice() {
+            b"NX" => nx = true,
+            b"XX" => xx = true,
+            b"GT" => gt = true,
+            b"LT" => lt = true,
+            _ => {
+                return Err(CmdError::UnsupportedOption(
+                    String::from_utf8_lossy(option).to_string(),
+                ))
+            }
+        }
+    }
+    if nx && (xx || gt || lt) {
+        return Err(CmdError::NxAndXxGtLt);
+    }
+    if gt && lt {
+        return Err(CmdError::GtAndLt);
+    }
+
+    let now = now_ms()?;
+    let invalid = || CmdError::InvalidExpireTime(cmd.to_string());
+    let mut when = when.checked_mul(unit_ms).ok_or_else(invalid)?;
+    if !absolute {
+        when = when.checked_add(now as i64).ok_or_else(invalid)?;
+    }
+
+    let mut s = write_lock(storage);
+    let current = match s.expiry(key) {
+        None => return Ok(Value::Integer(0)),
+        Some(expiry) if is_expired(expiry, now) => {
+            s.delete(key);
+            return Ok(Value::Integer(0));
+        }
+        Some(expiry) => expiry.map(|expiry| expiry as i64),
+    };
+    let skip = match current {
+        None => xx || gt,
+        Some(current) => nx || (gt && when <= current) || (lt && when >= current),
+    };
+    if skip {
+        return Ok(Value::Integer(0));
+    }
+
+    // A deadline in the past deletes the key right away, the same as Redis does it.
+    if when <= now as i64 {
+        s.delete(key);
+    } else {
+        s.set_expiry(key, Some(when as ExpirationTimeType));
+    }
+
+    Ok(Value::Integer(1))
+}
+
+/// Reports the expiration time of a key, for the `TTL` family of commands, converted by `convert`,
+/// which receives the expiration time and the current time in milliseconds.
+///
+/// Returns -2 if the key does not exist, and -1 if it has no expiration time.
+fn ttl_generic<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    key: &StorageKey,
+    convert: impl FnOnce(ExpirationTimeType, ExpirationTimeType) -> ExpirationTimeType,
+) -> Result<Value, CmdError> {
+    let now = now_ms()?;
+    let reply = match read_lock(storage).expiry(key) {
+        None => -2,
+        Some(expiry) if is_expired(expiry, now) => -2,
+        Some(None) => -1,
+        Some(Some(expiry)) => convert(expiry, now) as i64,
+    };
+    Ok(Value::Integer(reply))
+}
+
+/// Removes the keys under a single write lock, and returns the values of the ones that were live.
+///
+/// Expired keys are removed as well, but they aren't counted, as they logically don't exist anymore.
+fn remove_keys<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    keys: &[StorageKey],
+) -> Result<Vec<StorageValue>, CmdError> {
+    let now = now_ms()?;
+    let mut s = write_lock(storage);
+    Ok(keys
+        .iter()
+        .filter_map(|key| s.remove(key))
+        .filter(|(_, expiry)| !is_expired(*expiry, now))
+        .map(|(value, _)| value)
+        .collect())
+}
+
+/// Counts the keys that exist and haven't expired, without reading their values.
+fn count_live<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    keys: &[StorageKey],
+) -> Result<usize, CmdError> {
+    let now = now_ms()?;
+    let s = read_lock(storage);
+    Ok(keys
+        .iter()
+        .filter(|key| s.expiry(key).is_some_and(|expiry| !is_expired(expiry, now)))
+        .count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::tests::{new_databases, run, run_as, run_on, storage};
+    use crate::storage::object::Object;
+    use std::collections::VecDeque;
+    use std::time::Duration;
+
+    fn type_of(key: &'static str) -> Value {
+        let args = [Bytes::from("TYPE"), Bytes::from(key)];
+        handle_type(storage(), &mut Client::new(), &args).unwrap()
+    }
+
+    #[test]
+    fn handle_type_all() {
+        {
+            let mut s = storage().write().unwrap();
+            s.create(
+                &Bytes::from("type_string"),
+                Object::String(Bytes::from("a")),
+                None,
+            );
+            s.create(
+                &Bytes::from("type_list"),
+                Object::List(VecDeque::from([Bytes::from("a")])),
+                None,
+            );
+            s.create(&Bytes::from("type_expired"), Object::default(), Some(1));
+        }
+        assert_eq!(
+            Value::SimpleString(Bytes::from("string")),
+            type_of("type_string")
+        );
+        assert_eq!(
+            Value::SimpleString(Bytes::from("list")),
+            type_of("type_list")
+        );
+        assert_eq!(
+            Value::SimpleString(Bytes::from("none")),
+            type_of("type_expired")
+        );
+        assert_eq!(
+            Value::SimpleString(Bytes::from("none")),
+            type_of("type_missing")
+        );
+    }
+
+    #[test]
+    fn handle_del_exists() {
+        run(&["SET", "del1", "a"]).unwrap();
+        run(&["SET", "del2", "b"]).unwrap();
+        storage()
+            .write()
+            .unwrap()
+            .create(&Bytes::from("del_expired"), Object::default(), Some(1));
+        assert_eq!(
+            Value::Integer(3),
+            run(&[
+                "EXISTS",
+                "del1",
+                "del2",
+                "del1",
+                "del_expired",
+                "del_missing"
+            ])
+            .unwrap()
+        );
+        assert_eq!(
+            Value::Integer(2),
+            run(&["TOUCH", "del1", "del2", "del_expired", "del_missing"]).unwrap()
+        );
+        assert_eq!(
+            Value::Integer(2),
+            run(&["DEL", "del1", "del2", "del1", "del_expired", "del_missing"]).unwrap()
+        );
+        assert_eq!(Value::Integer(0), run(&["EXISTS", "del1", "del2"]).unwrap());
+        assert_eq!(
+            None,
+            storage().read().unwrap().read(&Bytes::from("del_expired"))
+        );
+    }
+
+    #[test]
+    fn handle_unlink_large() {
+        let list = (0..1000).map(|i| Bytes::from(i.to_string())).collect();
+        {
+            let mut s = storage().write().unwrap();
+            s.create(
+                &Bytes::from("unlink_large"),
+                Object::List(list),
+                Some(u128::MAX),
+            );
+            s.create(&Bytes::from("unlink_small"), Object::default(), None);
+        }
+        assert_eq!(
+            Value::Integer(2),
+            run(&["UNLINK", "unlink_large", "unlink_small", "unlink_missing"]).unwrap()
+        );
+        assert_eq!(
+            Value::Integer(0),
+            run(&["EXISTS", "unlink_large", "unlink_small"]).unwrap()
+        );
+    }
+
+    fn int(value: i64) -> Value {
+        Value::Integer(value)
+    }
+
+    #[test]
+    fn handle_expire_ttl() {
+        run(&["SET", "expire", "a"]).unwrap();
+        assert_eq!(int(-1), run(&["TTL", "expire"]).unwrap());
+        assert_eq!(int(-1), run(&["PEXPIRETIME", "expire"]).unwrap());
+        assert_eq!(int(-2), run(&["TTL", "expire_missing"]).unwrap());
+        assert_eq!(int(-2), run(&["EXPIRETIME", "expire_missing"]).unwrap());
+        assert_eq!(int(0), run(&["EXPIRE", "expire_missing", "100"]).unwrap());
+
+        assert_eq!(int(1), run(&["EXPIRE", "expire", "100"]).unwrap());
+        assert_eq!(int(100), run(&["TTL", "expire"]).unwrap());
+        let Value::Integer(pttl) = run(&["PTTL", "expire"]).unwrap() else {
+            panic!("Expected an integer");
+        };
+        assert!((99_000..=100_000).contains(&pttl), "{pttl}");
+
+        assert_eq!(int(1), run(&["EXPIREAT", "expire", "33177117420"]).unwrap());
+        assert_eq!(int(33177117420), run(&["EXPIRETIME", "expire"]).unwrap());
+        assert_eq!(
+            int(33177117420000),
+            run(&["PEXPIRETIME", "expire"]).unwrap()
+        );
+        assert_eq!(
+            int(1),
+            run(&["PEXPIREAT", "expire", "33177117420123"]).unwrap()
+        );
+        assert_eq!(int(33177117420), run(&["EXPIRETIME", "expire"]).unwrap());
+
+        assert_eq!(int(1), run(&["PERSIST", "expire"]).unwrap());
+        assert_eq!(int(0), run(&["PERSIST", "expire"]).unwrap());
+        assert_eq!(int(0), run(&["PERSIST", "expire_missing"]).unwrap());
+        assert_eq!(int(-1), run(&["TTL", "expire"]).unwrap());
+        assert_eq!(
+            Value::BulkString(Bytes::from("a")),
+            run(&["GET", "expire"]).unwrap()
+        );
+
+        assert_eq!(int(1), run(&["PEXPIRE", "expire", "-1"]).unwrap());
+        assert_eq!(int(0), run(&["EXISTS", "expire"]).unwrap());
+        assert_eq!(None, storage().read().unwrap().read(&Bytes::from("expire")));
+    }
+
+    #[test]
+    fn handle_expire_conditions() {
+        run(&["SET", "expire_cond", "a"]).unwrap();
+        assert_eq!(
+            int(0),
+            run(&["EXPIRE", "expire_cond", "100", "XX"]).unwrap()
+        );
+        assert_eq!(
+            int(0),
+            run(&["EXPIRE", "expire_cond", "100", "gt"]).unwrap()
+        );
+        assert_eq!(
+            int(1),
+            run(&["EXPIRE", "expire_cond", "100", "LT"]).unwrap()
+        );
+        assert_eq!(
+            int(0),
+            run(&["EXPIRE", "expire_cond", "200", "NX"]).unwrap()
+        );
+        assert_eq!(
+            int(0),
+            run(&["EXPIRE", "expire_cond", "200", "LT"]).unwrap()
+        );
+        assert_eq!(
+            int(1),
+            run(&["EXPIRE", "expire_cond", "200", "GT"]).unwrap()
+        );
+        assert_eq!(
+            int(1),
+            run(&["EXPIRE", "expire_cond", "50", "XX", "LT"]).unwrap()
+        );
+        assert_eq!(int(50), run(&["TTL", "expire_cond"]).unwrap());
+
+        assert!(matches!(
+            run(&["EXPIRE", "expire_cond", "10", "NX", "GT"]),
+            Err(CmdError::NxAndXxGtLt)
+        ));
+        assert!(matches!(
+            run(&["EXPIRE", "expire_cond", "10", "GT", "LT"]),
+            Err(CmdError::GtAndLt)
+        ));
+        assert!(matches!(
+            run(&["EXPIRE", "expire_cond", "10", "YY"]),
+            Err(CmdError::UnsupportedOption(option)) if option == "YY"
+        ));
+        assert!(matches!(
+            run(&["EXPIRE", "expire_cond", "ten"]),
+            Err(CmdError::NotAnInteger)
+        ));
+        assert!(matches!(
+            run(&["EXPIRE", "expire_cond", "9223372036854775807"]),
+            Err(CmdError::InvalidExpireTime(cmd)) if cmd == "expire"
+        ));
+        assert_eq!(int(50), run(&["TTL", "expire_cond"]).unwrap());
+    }
+
+    #[test]
+    fn handle_keys_pattern() {
+        for key in ["keys:a1", "keys:b2", "keys:c3", "keys:*"] {
+            run(&["SET", key, "v"]).unwrap();
+        }
+        storage()
+            .write()
+            .unwrap()
+            .create(&Bytes::from("keys:expired"), Object::default(), Some(1));
+
+        let keys = |pattern: &str| {
+            let Value::Array(keys) = run(&["KEYS", pattern]).unwrap() else {
+                panic!("Expected an array");
+            };
+            let mut keys = keys
+                .into_iter()
+                .map(|key| match key {
+                    Value::BulkString(key) => String::from_utf8(key.to_vec()).unwrap(),
+                    key => panic!("Expected a bulk string, got {key:?}"),
+                })
+                .collect::<Vec<_>>();
+            keys.sort();
+            keys
+        };
+        assert_eq!(
+            vec!["keys:*", "keys:a1", "keys:b2", "keys:c3"],
+            keys("keys:*")
+        );
+        assert_eq!(vec!["keys:a1", "keys:b2"], keys("keys:[a-b]?"));
+        assert_eq!(vec!["keys:c3"], keys("keys:[^ab]3"));
+        assert_eq!(vec!["keys:*"], keys("keys:\\*"));
+        assert!(keys("keys:x*").is_empty());
+        assert!(!keys("*").contains(&"keys:expired".to_string()));
+    }
+
+    /// Runs a full `SCAN` iteration with the given options, calling `between` after each call,
+    /// and returns the keys and the number of calls.
+    fn scan_all(options: &[&str], mut between: impl FnMut(usize)) -> (Vec<Bytes>, usize) {
+        let mut cursor = "0".to_string();
+        let mut keys = vec![];
+        let mut calls = 0;
+        loop {
+            let mut words = vec!["SCAN", cursor.as_str()];
+            words.extend(options);
+            let Value::Array(reply) = run(&words).unwrap() else {
+                panic!("Expected an array");
+            };
+            let [Value::BulkString(next), Value::Array(batch)] = &reply[..] else {
+                panic!("Expected a cursor and an array, got {reply:?}");
+            };
+            keys.extend(batch.iter().map(|key| match key {
+                Value::BulkString(key) => key.clone(),
+                key => panic!("Expected a bulk string, got {key:?}"),
+            }));
+            calls += 1;
+            between(calls);
+            cursor = String::from_utf8(next.to_vec()).unwrap();
+            if cursor == "0" {
+                return (keys, calls);
+            }
+        }
+    }
+
+    #[test]
+    fn handle_scan_while_growing() {
+        for i in 0..200 {
+            run(&["SET", &format!("scan:{i}"), "v"]).unwrap();
+        }
+        // Keys are added during the iteration, which must not cause any of the original keys to be missed.
+        let (keys, calls) = scan_all(&["MATCH", "scan:*", "COUNT", "20"], |call| {
+            for i in 0..50 {
+                run(&["SET", &format!("scan:new:{call}:{i}"), "v"]).unwrap();
+            }
+        });
+        assert!(calls > 1);
+        for i in 0..200 {
+            let key = Bytes::from(format!("scan:{i}"));
+            assert_eq!(1, keys.iter().filter(|k| **k == key).count(), "{key:?}");
+        }
+    }
+
+    #[test]
+    fn handle_scan_options() {
+        run(&["SET", "scantype:string", "v"]).unwrap();
+        storage().write().unwrap().create(
+            &Bytes::from("scantype:list"),
+            Object::List(VecDeque::from([Bytes::from("a")])),
+            None,
+        );
+        storage().write().unwrap().create(
+            &Bytes::from("scantype:expired"),
+            Object::default(),
+            Some(1),
+        );
+        let (keys, _) = scan_all(&["MATCH", "scantype:*", "TYPE", "LIST"], |_| {});
+        assert_eq!(vec![Bytes::from("scantype:list")], keys);
+        let (mut keys, _) = scan_all(&["COUNT", "1000", "MATCH", "scantype:*"], |_| {});
+        keys.sort();
+        assert_eq!(
+            vec![Bytes::from("scantype:list"), Bytes::from("scantype:string")],
+            keys
+        );
+
+        assert!(matches!(run(&["SCAN", "-1"]), Err(CmdError::InvalidCursor)));
+        assert!(matches!(run(&["SCAN", "x"]), Err(CmdError::InvalidCursor)));
+        assert!(matches!(
+            run(&["SCAN", "0", "COUNT", "0"]),
+            Err(CmdError::SyntaxError)
+        ));
+        assert!(matches!(
+            run(&["SCAN", "0", "MATCH"]),
+            Err(CmdError::SyntaxError)
+        ));
+        assert!(matches!(
+            run(&["SCAN", "0", "NOVALUES"]),
+            Err(CmdError::SyntaxError)
+        ));
+        assert!(matches!(
+            run(&["SCAN", "0", "TYPE", "nope"]),
+            Err(CmdError::UnknownTypeName(name)) if name == "nope"
+        ));
+    }
+
+    #[test]
+    fn handle_rename_keeps_ttl() {
+        run(&["SET", "rename_src", "a", "EX", "100"]).unwrap();
+        run(&["SET", "rename_dst", "b"]).unwrap();
+        assert_eq!(
+            Value::OK,
+            run(&["RENAME", "rename_src", "rename_dst"]).unwrap()
+        );
+        assert_eq!(int(0), run(&["EXISTS", "rename_src"]).unwrap());
+        assert_eq!(
+            Value::BulkString(Bytes::from("a")),
+            run(&["GET", "rename_dst"]).unwrap()
+        );
+        assert_eq!(int(100), run(&["TTL", "rename_dst"]).unwrap());
+
+        run(&["SET", "rename_dst2", "c", "EX", "50"]).unwrap();
+        run(&["SET", "rename_src", "d"]).unwrap();
+        assert_eq!(
+            Value::OK,
+            run(&["RENAME", "rename_src", "rename_dst2"]).unwrap()
+        );
+        assert_eq!(int(-1), run(&["TTL", "rename_dst2"]).unwrap());
+
+        assert_eq!(
+            Value::OK,
+            run(&["RENAME", "rename_dst", "rename_dst"]).unwrap()
+        );
+        assert!(matches!(
+            run(&["RENAME", "rename_missing", "rename_dst"]),
+            Err(CmdError::NoSuchKey)
+        ));
+    }
+
+    #[test]
+    fn handle_renamenx() {
+        run(&["SET", "renamenx_src", "a"]).unwrap();
+        run(&["SET", "renamenx_dst", "b"]).unwrap();
+        assert_eq!(
+            int(0),
+            run(&["RENAMENX", "renamenx_src", "renamenx_dst"]).unwrap()
+        );
+        assert_eq!(
+            int(0),
+            run(&["RENAMENX", "renamenx_src", "renamenx_src"]).unwrap()
+        );
+        assert_eq!(
+            int(1),
+            run(&["RENAMENX", "renamenx_src", "renamenx_new"]).unwrap()
+        );
+        assert_eq!(
+            Value::BulkString(Bytes::from("a")),
+            run(&["GET", "renamenx_new"]).unwrap()
+        );
+        assert!(matches!(
+            run(&["RENAMENX", "renamenx_src", "renamenx_other"]),
+            Err(CmdError::NoSuchKey)
+        ));
+    }
+
+    #[test]
+    fn handle_copy_move() {
+        run(&["SET", "copy_src", "a", "PX", "100000"]).unwrap();
+        run(&["SET", "copy_dst", "b"]).unwrap();
+        assert_eq!(int(0), run(&["COPY", "copy_src", "copy_dst"]).unwrap());
+        assert_eq!(
+            int(1),
+            run(&["COPY", "copy_src", "copy_dst", "replace", "DB", "0"]).unwrap()
+        );
+        assert_eq!(
+            Value::BulkString(Bytes::from("a")),
+            run(&["GET", "copy_dst"]).unwrap()
+        );
+        assert_eq!(int(100), run(&["TTL", "copy_dst"]).unwrap());
+        assert_eq!(int(1), run(&["EXISTS", "copy_src"]).unwrap());
+        assert_eq!(int(0), run(&["COPY", "copy_missing", "copy_dst"]).unwrap());
+
+        assert!(matches!(
+            run(&["COPY", "copy_src", "copy_src"]),
+            Err(CmdError::SameObject)
+        ));
+        assert!(matches!(
+            run(&["COPY", "copy_src", "copy_dst", "DB", "16"]),
+            Err(CmdError::DbIndexOutOfRange)
+        ));
+        assert!(matches!(
+            run(&["COPY", "copy_src", "copy_dst", "DB"]),
+            Err(CmdError::SyntaxError)
+        ));
+        assert!(matches!(
+            run(&["MOVE", "copy_src", "0"]),
+            Err(CmdError::SameObject)
+        ));
+        assert!(matches!(
+            run(&["MOVE", "copy_src", "16"]),
+            Err(CmdError::DbIndexOutOfRange)
+        ));
+        assert!(matches!(
+            run(&["MOVE", "copy_src", "x"]),
+            Err(CmdError::NotAnInteger)
+        ));
+    }
+
+    #[test]
+    fn handle_copy_move_across_databases() {
+        let mut client = Client::new();
+        run_as(&mut client, &["SELECT", "2"]).unwrap();
+        run_as(&mut client, &["SET", "across_src", "a", "PX", "100000"]).unwrap();
+        assert_eq!(
+            int(1),
+            run_as(
+                &mut client,
+                &["COPY", "across_src", "across_src", "DB", "3"]
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            int(0),
+            run_as(&mut client, &["MOVE", "across_src", "3"]).unwrap()
+        );
+        assert_eq!(
+            int(1),
+            run_as(&mut client, &["MOVE", "across_src", "4"]).unwrap()
+        );
+        assert_eq!(
+            int(0),
+            run_as(&mut client, &["EXISTS", "across_src"]).unwrap()
+        );
+        assert_eq!(
+            int(0),
+            run_as(&mut client, &["MOVE", "across_src", "4"]).unwrap()
+        );
+
+        for db in ["3", "4"] {
+            run_as(&mut client, &["SELECT", db]).unwrap();
+            assert_eq!(
+                Value::BulkString(Bytes::from("a")),
+                run_as(&mut client, &["GET", "across_src"]).unwrap()
+            );
+            assert_eq!(
+                int(100),
+                run_as(&mut client, &["TTL", "across_src"]).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn handle_rename_move_keep_metadata() {
+        let databases = new_databases();
+        let mut client = Client::new();
+        run_on(&databases, &mut client, &["SET", "a", "1"]).unwrap();
+        let metadata = |db: usize, key: &str| {
+            databases[db]
+                .read()
+                .unwrap()
+                .0
+                .get(&Bytes::from(key.to_string()))
+                .unwrap()
+                .metadata
+                .clone()
+        };
+        let a = metadata(0, "a");
+        a.set_idle_time_ms(10_000);
+        a.set_frequency(42);
+        databases[0]
+            .write()
+            .unwrap()
+            .set_metadata(&Bytes::from("a"), a);
+
+        run_on(&databases, &mut client, &["RENAME", "a", "b"]).unwrap();
+        run_on(&databases, &mut client, &["MOVE", "b", "1"]).unwrap();
+        let b = metadata(1, "b");
+        assert_eq!(10, b.idle_time_ms() / 1000);
+        assert_eq!(42, b.frequency());
+        assert_eq!(Encoding::Int, b.encoding());
+    }
+
+    #[test]
+    fn handle_randomkey() {
+        let databases = new_databases();
+        let mut client = Client::new();
+        assert_eq!(
+            Value::Null,
+            run_on(&databases, &mut client, &["RANDOMKEY"]).unwrap()
+        );
+
+        run_on(&databases, &mut client, &["SET", "expired", "0", "PX", "1"]).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(
+            Value::Null,
+            run_on(&databases, &mut client, &["RANDOMKEY"]).unwrap()
+        );
+        assert_eq!(
+            int(0),
+            run_on(&databases, &mut client, &["DBSIZE"]).unwrap()
+        );
+
+        let keys = ["a", "b", "c", "d"];
+        for key in keys {
+            run_on(&databases, &mut client, &["SET", key, "0"]).unwrap();
+        }
+        for i in 0..50 {
+            let key = format!("expired:{i}");
+            run_on(&databases, &mut client, &["SET", &key, "0", "PX", "1"]).unwrap();
+        }
+        std::thread::sleep(Duration::from_millis(5));
+        for _ in 0..10 {
+            let reply = run_on(&databases, &mut client, &["RANDOMKEY"]).unwrap();
+            assert!(
+                matches!(reply, Value::BulkString(key) if keys.contains(&&*String::from_utf8_lossy(&key)))
+            );
+        }
+    }
+
+    #[test]
+    fn handle_object_encoding() {
+        let encoding = |key| run(&["OBJECT", "ENCODING", key]).unwrap();
+        run(&["SET", "object_int", "123"]).unwrap();
+        run(&["SET", "object_embstr", "hello"]).unwrap();
+        run(&["SET", "object_raw", &"a".repeat(45)]).unwrap();
+        let set = |members: &[&'static str]| {
+            let members = members.iter().copied().map(Bytes::from).collect();
+            let mut s = storage().write().unwrap();
+            s.create(&Bytes::from("object_set"), Object::Set(members), None);
+        };
+        set(&["1", "2"]);
+        assert_eq!(
+            Value::BulkString(Bytes::from("int")),
+            encoding("object_int")
+        );
+        assert_eq!(
+            Value::BulkString(Bytes::from("embstr")),
+            encoding("object_embstr")
+        );
+        assert_eq!(
+            Value::BulkString(Bytes::from("raw")),
+            encoding("object_raw")
+        );
+        assert_eq!(
+            Value::BulkString(Bytes::from("intset")),
+            encoding("object_set")
+        );
+        // A set doesn't go back to a more compact encoding when it shrinks.
+        set(&["1", "2", "a"]);
+        set(&["1", "2"]);
+        assert_eq!(
+            Value::BulkString(Bytes::from("listpack")),
+            encoding("object_set")
+        );
+        assert_eq!(Value::Null, encoding("object_missing"));
+
+        assert_eq!(
+            int(i32::MAX as i64),
+            run(&["OBJECT", "REFCOUNT", "object_int"]).unwrap()
+        );
+        assert_eq!(
+            int(1),
+            run(&["OBJECT", "REFCOUNT", "object_embstr"]).unwrap()
+        );
+
+        assert!(matches!(
+            run(&["OBJECT", "ENCODING"]),
+            Err(CmdError::WrongNumberOfArgs(name)) if name == "object|encoding"
+        ));
+        assert!(matches!(
+            run(&["OBJECT", "NOPE", "object_int"]),
+            Err(CmdError::UnknownSubcommand(..))
+        ));
+    }
+
+    #[test]
+    fn handle_object_idletime_freq() {
+        run(&["SET", "object_idle", "a"]).unwrap();
+        storage()
+            .read()
+            .unwrap()
+            .0
+            .get(&Bytes::from("object_idle"))
+            .unwrap()
+            .metadata
+            .set_idle_time_ms(10_000);
+        assert_eq!(
+            int(10),
+            run(&["OBJECT", "IDLETIME", "object_idle"]).unwrap()
+        );
+        assert_eq!(int(5), run(&["OBJECT", "FREQ", "object_idle"]).unwrap());
+
+        let mut client = Client::new();
+        run_as(&mut client, &["CLIENT", "NO-TOUCH", "ON"]).unwrap();
+        run_as(&mut client, &["GET", "object_idle"]).unwrap();
+        assert_eq!(
+            int(10),
+            run(&["OBJECT", "IDLETIME", "object_idle"]).unwrap()
+        );
+        run_as(&mut client, &["TOUCH", "object_idle"]).unwrap();
+        assert_eq!(int(0), run(&["OBJECT", "IDLETIME", "object_idle"]).unwrap());
+
+        storage()
+            .read()
+            .unwrap()
+            .0
+            .get(&Bytes::from("object_idle"))
+            .unwrap()
+            .metadata
+            .set_idle_time_ms(10_000);
+        run(&["GET", "object_idle"]).unwrap();
+        assert_eq!(int(0), run(&["OBJECT", "IDLETIME", "object_idle"]).unwrap());
+        // The counter is always incremented while it's at most the initial value, so `TOUCH` did it.
+        assert!(matches!(
+            run(&["OBJECT", "FREQ", "object_idle"]).unwrap(),
+            Value::Integer(6..)
+        ));
+    }
+
+    /// Runs `RESTORE key ttl payload [options ...]` with a binary payload.
+    fn restore(key: &str, ttl: &str, payload: &Bytes, options: &[&str]) -> Result<Value, CmdError> {
+        let mut args = vec![
+            Bytes::from("RESTORE"),
+            Bytes::copy_from_slice(key.as_bytes()),
+            Bytes::copy_from_slice(ttl.as_bytes()),
+            payload.clone(),
+        ];
+        args.extend(
+            options
+                .iter()
+                .map(|option| Bytes::copy_from_slice(option.as_bytes())),
+        );
+        handle_restore(storage(), &mut Client::new(), &args)
+    }
+
+    #[test]
+    fn handle_dump_restore() {
+        run(&["SET", "dump_src", "10"]).unwrap();
+        let Value::BulkString(payload) = run(&["DUMP", "dump_src"]).unwrap() else {
+            panic!("Expected a bulk string");
+        };
+        assert_eq!(b"\x00\xc0\n\x0c\x00", &payload[..5]);
+        assert_eq!(Value::Null, run(&["DUMP", "dump_missing"]).unwrap());
+
+        assert_eq!(Value::OK, restore("dump_dst", "0", &payload, &[]).unwrap());
+        assert_eq!(
+            Value::BulkString(Bytes::from("10")),
+            run(&["GET", "dump_dst"]).unwrap()
+        );
+        assert_eq!(int(-1), run(&["TTL", "dump_dst"]).unwrap());
+        assert!(matches!(
+            restore("dump_dst", "0", &payload, &[]),
+            Err(CmdError::BusyKey)
+        ));
+        // The existing key is reported before the invalid TTL and payload.
+        assert!(matches!(
+            restore("dump_dst", "-1", &Bytes::from("x"), &[]),
+            Err(CmdError::BusyKey)
+        ));
+
+        assert_eq!(
+            Value::OK,
+            restore(
+                "dump_dst",
+                "10000",
+                &payload,
+                &["REPLACE", "IDLETIME", "100"]
+            )
+            .unwrap()
+        );
+        assert!(matches!(
+            run(&["PTTL", "dump_dst"]).unwrap(),
+            Value::Integer(1..=10000)
+        ));
+        assert_eq!(int(100), run(&["OBJECT", "IDLETIME", "dump_dst"]).unwrap());
+
+        assert_eq!(
+            Value::OK,
+            restore("dump_dst", "0", &payload, &["REPLACE", "FREQ", "42"]).unwrap()
+        );
+        assert_eq!(int(42), run(&["OBJECT", "FREQ", "dump_dst"]).unwrap());
+
+        // An absolute time in the past replaces the key with nothing.
+        assert_eq!(
+            Value::OK,
+            restore("dump_dst", "1", &payload, &["ABSTTL", "REPLACE"]).unwrap()
+        );
+        assert_eq!(int(0), run(&["EXISTS", "dump_dst"]).unwrap());
+    }
+
+    #[test]
+    fn handle_restore_errors() {
+        run(&["SET", "restore_errors_src", "value"]).unwrap();
+        let Value::BulkString(payload) = run(&["DUMP", "restore_errors_src"]).unwrap() else {
+            panic!("Expected a bulk string");
+        };
+
+        let key = "restore_errors";
+        assert!(matches!(
+            restore(key, "0", &payload, &["IDLETIME", "1", "FREQ", "1"]),
+            Err(CmdError::SyntaxError)
+        ));
+        assert!(matches!(
+            restore(key, "0", &payload, &["IDLETIME", "-1"]),
+            Err(CmdError::InvalidIdleTime)
+        ));
+        assert!(matches!(
+            restore(key, "0", &payload, &["FREQ", "256"]),
+            Err(CmdError::InvalidFrequency)
+        ));
+        assert!(matches!(
+            restore(key, "-1", &payload, &[]),
+            Err(CmdError::NegativeTtl)
+        ));
+
+        let mut corrupted = payload.to_vec();
+        corrupted[2] ^= 1;
+        assert!(matches!(
+            restore(key, "0", &Bytes::from(corrupted), &[]),
+            Err(CmdError::DumpPayloadInvalid)
+        ));
+
+        // Truncated and garbage payloads with valid checksums
+        for body in [
+            &[16, 6, 6, 0, 0, 0, 0, 0xff][..],
+            &[16, 12, 12, 0, 0, 0, 2, 0, 0x81, b'a', 2, 1, 1],
+            &[0, 5, b'v', b'a'],
+            &[0],
+            &[],
+            &[0xff; 32],
+            &[18, 0x80, 0xff, 0xff, 0xff, 0xff],
+        ] {
+            let garbage = Bytes::from(rdb::tests::payload(body, rdb::RDB_VERSION));
+            assert!(matches!(
+                restore(key, "0", &garbage, &[]),
+                Err(CmdError::BadDataFormat)
+            ));
+        }
+        assert_eq!(int(0), run(&["EXISTS", key]).unwrap());
+    }
+}
@@ -0,0 +1,1100 @@
+//! # Request and Command Handlers
+//!
+//! [Commands](https://redis.io/docs/latest/commands/)
+//!
+//! [COMMAND](https://redis.io/docs/latest/commands/command/)
+//! - Redis command names are case-insensitive.
+//!
+//! [Command key specifications](https://redis.io/docs/latest/develop/reference/key-specs/)
+//!
+//! [Redis serialization protocol specification](https://redis.io/docs/latest/develop/reference/protocol-spec/)
+//! - A client sends a request to the Redis server as an array of strings. The array's contents are the command and
+//!   its arguments that the server should execute. The server's reply type is command-specific.
+//!
+//! [Request-Response model](https://redis.io/docs/latest/develop/reference/protocol-spec/#request-response-model)
+//! - The Redis server accepts commands composed of different arguments.
+//!   Then, the server processes the command and sends the reply back to the client.
+//!   This is the simplest model possible; however, there are some exceptions:
+//!   - Redis requests can be pipelined.
+//!     Pipelining enables clients to send multiple commands at once and wait for replies later.
+//!
+//! [RESP protocol description](https://redis.io/docs/latest/develop/reference/protocol-spec/#resp-protocol-description)
+//! - RESP is essentially a serialization protocol that supports several data types.
+//!   In RESP, the first byte of data determines its type.
+//! - Redis generally uses RESP as a request-response protocol in the following way:
+//!   - Clients send commands to a Redis server as an array of bulk strings.
+//!     The first (and sometimes also the second) bulk string in the array is the command's name.
+//!     Subsequent elements of the array are the arguments for the command.
+//!   - The server replies with a RESP type.
+//!     The reply's type is determined by the command's implementation and possibly by the client's protocol version.
+//!
+//! [Sending commands to a Redis server](https://redis.io/docs/latest/develop/reference/protocol-spec/#sending-commands-to-a-redis-server)
+//! - We can further specify how the interaction between the client and the server works:
+//!   - A client sends the Redis server an array consisting of only bulk strings.
+//!   - A Redis server replies to clients, sending any valid RESP data type as a reply.
+//!
+//! [Multiple commands and pipelining](https://redis.io/docs/latest/develop/reference/protocol-spec/#multiple-commands-and-pipelining)
+//! - A client can use the same connection to issue multiple commands.
+//!   Pipelining is supported, so multiple commands can be sent with a single write operation by the client.
+//!   The client can skip reading replies and continue to send the commands one after the other.
+//!   All the replies can be read at the end.
+//!   For more information, see [Pipelining](https://redis.io/docs/latest/develop/use/pipelining/).
+
+mod bitmap;
+mod connection;
+mod hash;
+mod hyperloglog;
+mod keyspace;
+mod server;
+mod set;
+mod sorted_set;
+mod string;
+mod table;
+
+use crate::cmd::table::Handler;
+use crate::conn::Client;
+use crate::errors::CmdError;
+use crate::expiry::{is_expired, now_ms};
+use crate::is_enum_variant;
+use crate::resp::{Message, Value};
+use crate::storage::generic::Crud;
+use crate::types::{
+    ConcurrentStorageType, ExpirationTime, ExpirationTimeType, StorageKey, StorageType,
+    StorageValue,
+};
+use anyhow::Result;
+use bytes::{Bytes, BytesMut};
+use log::debug;
+use std::sync::{RwLockReadGuard, RwLockWriteGuard};
+
+/// Routes request bytes to the appropriate command handler(s) and returns the response bytes.
+///
+/// The received byte stream is either a RESP request, and consequently the RESP Array type,
+/// or an [inline command](https://redis.io/docs/latest/develop/reference/protocol-spec/#inline-commands),
+/// which is a single line of space-separated words, as typed by a human in `telnet`, for example.
+///
+/// The byte stream buffer should not contain excess bytes, i.e., it is expected to end with the appropriate `CRLF`,
+/// or with `LF` in case of an inline command.
+///
+/// A single request (frame) is always exactly one command with its arguments. Clients
+/// [pipeline](https://redis.io/docs/latest/develop/reference/protocol-spec/#multiple-commands-and-pipelining)
+/// commands by sending multiple requests at once, which the connection handler splits into separate frames,
+/// and then calls this function once per frame, in order.
+pub(crate) async fn handle_request<KV: Crud + 'static, KE: Crud + 'static>(
+    databases: &[ConcurrentStorageType<KV, KE>],
+    client: &mut Client,
+    bytes: &Bytes,
+) -> Result<BytesMut, CmdError> {
+    let (msg, _bytes_read) = if bytes.first() == Some(&b'*') {
+        // Do these checks here once per request, so that [`resp::deserialize`] doesn't have to do it multiple times,
+        // and it would have to do it, as it depends on the byte stream ending in CRLF.
+        let len = bytes.len();
+        if len < 2 {
+            return Err(CmdError::InputTooShort(
+                String::from_utf8_lossy(bytes).to_string(),
+            ));
+        }
+        if bytes[len - 2].ne(&b'\r') || bytes[len - 1].ne(&b'\n') {
+            return Err(CmdError::CRLFNotAtEnd);
+        }
+
+        // Get the command-array's length. Null and empty arrays are silently ignored, the same as Redis does it.
+        let (bytes_arr_len, _) = Message::parse_len(bytes)?;
+        if matches!(bytes_arr_len, None | Some(0)) {
+            return Ok(BytesMut::new());
+        }
+
+        // Parse (deserialize) the received byte stream into words (into a Message which holds the words).
+        // A client sends a request to the Redis server as an array of strings.
+        // The array's contents are the command and its arguments that the server should execute.
+        Message::deserialize(bytes)?
+    } else {
+        // Anything that isn't a RESP array is an inline command, which is split into the same words.
+        let inline = Message::deserialize_inline(bytes)?;
+        // Empty lines are silently ignored, the same as Redis does it.
+        if matches!(&inline.0.data, Value::Array(array) if array.is_empty()) {
+            return Ok(BytesMut::new());
+        }
+        inline
+    };
+    let request_arr = match &msg.data {
+        Value::Array(array) => array,
+        _ => return Err(CmdError::CmdNotArray),
+    };
+    // Check in advance whether all array elements are bulk strings, because they all need to be.
+    // Return early if at least one command is not a bulk string.
+    if !request_arr
+        .iter()
+        .all(|cmd| is_enum_variant!(cmd, Value::BulkString))
+    {
+        return Err(CmdError::NotAllBulk);
+    }
+
+    // The words are slices of the connection's read buffer, so they're copied, the same as Redis copies them
+    // into its own strings. Otherwise, every key and value that a command stores would keep the whole buffer
+    // allocated for as long as it exists.
+    let args = request_arr
+        .iter()
+        .map(|word| match word {
+            Value::BulkString(arg) => Bytes::copy_from_slice(arg),
+            _ => unreachable!("All words have been checked to be bulk strings"),
+        })
+        .collect::<Vec<_>>();
+
+    let reply = dispatch(databases, client, &args)?;
+
+    // All replies are encoded here, in one place, in the protocol version of the connection.
+    let mut result = BytesMut::new();
+    reply.write_to(&mut result, client.protocol);
+
+    Ok(result)
+}
+
+/// Routes a single command and its arguments to the appropriate command handler.
+///
+/// Clients send commands to a Redis server as an array of bulk strings.
+/// The first bulk string in the array is the command's name, and the subsequent ones are its arguments.
+/// For example: `["ECHO", "raspberry"]` => `$9\r\nraspberry\r\n`
+///
+/// The command is looked up in the [command table](table::commands), and the number of arguments is checked against
+/// the command's arity before calling its handler, so handlers can index into their mandatory arguments directly.
+///
+/// Most handlers get the database that the client has selected, and the rest get all databases.
+fn dispatch<KV: Crud + 'static, KE: Crud + 'static>(
+    databases: &[ConcurrentStorageType<KV, KE>],
+    client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let Some(cmd) = table::lookup::<KV, KE>(&args[0]) else {
+        return Err(unknown_command(args));
+    };
+    if !cmd.check_arity(args.len()) {
+        return Err(CmdError::WrongNumberOfArgs(cmd.name.to_string()));
+    }
+
+    match cmd.handler {
+        Handler::Db(handler) => handler(&databases[client.db], client, args),
+        Handler::AllDbs(handler) => handler(databases, client, args),
+    }
+}
+
+/// Creates the error for an unknown command, which quotes the command and the beginning of its arguments.
+///
+/// The arguments are quoted one by one, for as long as the quoted text is shorter than 128 bytes,
+/// the same as Redis does it.
+fn unknown_command(args: &[Bytes]) -> CmdError {
+    const MAX_LEN: usize = 128;
+
+    let name = String::from_utf8_lossy(&args[0][..args[0].len().min(MAX_LEN)]).to_string();
+    let mut quoted = String::new();
+    for arg in &args[1..] {
+        if quoted.len() >= MAX_LEN {
+            break;
+        }
+        let arg = &arg[..arg.len().min(MAX_LEN - quoted.len())];
+        quoted.push_str(&format!("'{}' ", String::from_utf8_lossy(arg)));
+    }
+
+    CmdError::UnrecognizedCmd(name, quoted)
+}
+
+/// Reads the value of a key by reference, for commands that only read from the storage, and passes it to `f`.
+///
+/// Expired keys are treated as nonexistent. If a key is found to be expired, it's deleted, which is how
+/// keys are passively expired.
+///
+/// From the [EXPIRE](https://redis.io/docs/latest/commands/expire/#how-redis-expires-keys) docs:
+/// "A key is passively expired simply when some client tries to access it, and the key is found to be timed out."
+///
+/// The value isn't copied, as `f` is called under the read lock, so `f` must not lock the storage itself.
+pub(crate) fn with_key<KV: Crud, KE: Crud, T>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    key: &StorageKey,
+    f: impl FnOnce(Option<&StorageValue>) -> T,
+) -> Result<T, CmdError> {
+    with_locked_key(storage, key, |_, value| f(value))
+}
+
+/// The same as [`with_key`], but `f` is also given the locked storage, such as to record an access of the key
+/// under the same lock that the value was read under.
+pub(crate) fn with_locked_key<KV: Crud, KE: Crud, T>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    key: &StorageKey,
+    f: impl FnOnce(&StorageType<KV, KE>, Option<&StorageValue>) -> T,
+) -> Result<T, CmdError> {
+    let now = now_ms()?;
+    {
+        let s = read_lock(storage);
+        match s.get(key) {
+            Some((_, expiry)) if is_expired(expiry, now) => {}
+            entry => return Ok(f(&s, entry.map(|(value, _)| value))),
+        }
+    }
+
+    let mut s = write_lock(storage);
+    // The key could have been set again in the meantime, so it's checked once more under the write lock.
+    if s.expiry(key).is_some_and(|expiry| is_expired(expiry, now)) {
+        s.delete(key);
+    }
+    Ok(f(&s, None))
+}
+
+/// Reads the string stored at `key`, or `None` if the key doesn't exist.
+///
+/// Strings are reference-counted, so they're cheap to copy, while values of other types are rejected without copying.
+pub(crate) fn get_string<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    key: &StorageKey,
+) -> Result<Option<Bytes>, CmdError> {
+    with_key(storage, key, |value| {
+        value
+            .map(|value| value.as_string().cloned().ok_or(CmdError::WrongType))
+            .transpose()
+    })?
+}
+
+/// Acquires the read lock of the storage, for commands that read multiple keys at once, such as `MGET`.
+///
+/// A poisoned lock is recovered from, the same as in [`write_lock`].
+pub(crate) fn read_lock<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+) -> RwLockReadGuard<'_, StorageType<KV, KE>> {
+    storage.read().unwrap_or_else(|poisoned| {
+        debug!("RwLock is poisoned (RwLockReadGuard). Recovering...");
+        poisoned.into_inner()
+    })
+}
+
+/// Acquires the write lock of the storage, for commands that modify it.
+///
+/// Commands that read a key and then write it back, such as `INCR`, hold the lock for the whole operation,
+/// which makes them atomic with respect to other connections.
+///
+/// A poisoned lock is recovered from, as a panicking handler can't leave the storage in an inconsistent state.
+pub(crate) fn write_lock<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+) -> RwLockWriteGuard<'_, StorageType<KV, KE>> {
+    storage.write().unwrap_or_else(|poisoned| {
+        debug!("RwLock is poisoned (RwLockWriteGuard). Recovering...");
+        poisoned.into_inner()
+    })
+}
+
+/// The write lock of a single database
+type WriteGuard<'a, KV, KE> = RwLockWriteGuard<'a, StorageType<KV, KE>>;
+
+/// Acquires the write locks of two different databases, for commands that work with both, such as `MOVE`.
+///
+/// The locks are always acquired in the order of the databases' indexes, so that two commands that lock
+/// the same pair of databases in the opposite order can't deadlock.
+pub(crate) fn write_lock_pair<KV: Crud, KE: Crud>(
+    databases: &[ConcurrentStorageType<KV, KE>],
+    first: usize,
+    second: usize,
+) -> (WriteGuard<'_, KV, KE>, WriteGuard<'_, KV, KE>) {
+    assert_ne!(first, second, "A database can only be locked once");
+    if first < second {
+        let first = write_lock(&databases[first]);
+        (first, write_lock(&databases[second]))
+    } else {
+        let second = write_lock(&databases[second]);
+        (write_lock(&databases[first]), second)
+    }
+}
+
+/// Parses the index of a database, which must be one of the `count` databases.
+pub(crate) fn parse_db_index(arg: &[u8], count: usize) -> Result<usize, CmdError> {
+    let index = parse_integer(arg)?;
+    let index = i32::try_from(index).map_err(|_| CmdError::NotAnInteger)?;
+    usize::try_from(index)
+        .ok()
+        .filter(|&index| index < count)
+        .ok_or(CmdError::DbIndexOutOfRange)
+}
+
+/// Reads the value and the expiration time of a key by reference from an already locked storage.
+///
+/// Expired keys are treated as nonexistent, and are left for the caller to overwrite or delete.
+pub(crate) fn get_live<'a, S: Crud>(
+    s: &'a S,
+    key: &StorageKey,
+    now: ExpirationTimeType,
+) -> Option<(&'a StorageValue, ExpirationTime)> {
+    s.get(key).filter(|(_, expiry)| !is_expired(*expiry, now))
+}
+
+/// Reads the value and the expiration time of a key from an already locked storage.
+///
+/// The same as [`get_live`], but the value is copied, for commands that go on to modify it.
+pub(crate) fn read_live<S: Crud>(
+    s: &S,
+    key: &StorageKey,
+    now: ExpirationTimeType,
+) -> Option<(StorageValue, ExpirationTime)> {
+    get_live(s, key, now).map(|(value, expiry)| (value.clone(), expiry))
+}
+
+/// Parses an integer argument, the same way Redis does it.
+///
+/// Only the canonical representation is accepted: an optional minus sign followed by digits, without leading zeros,
+/// surrounding spaces or a plus sign, that fits in 64 bits.
+pub(crate) fn parse_integer(arg: &[u8]) -> Result<i64, CmdError> {
+    let digits = arg.strip_prefix(b"-").unwrap_or(arg);
+    let canonical = match digits {
+        [b'0'] => arg.len() == 1,
+        [b'1'..=b'9', rest @ ..] => rest.iter().all(u8::is_ascii_digit),
+        _ => false,
+    };
+    if !canonical {
+        return Err(CmdError::NotAnInteger);
+    }
+    std::str::from_utf8(arg)
+        .ok()
+        .and_then(|arg| arg.parse().ok())
+        .ok_or(CmdError::NotAnInteger)
+}
+
+/// Converts an error into an error reply, using the same wording as Redis does.
+///
+/// Errors get the generic `ERR` code, unless they have a more specific one, such as `WRONGTYPE`.
+/// Protocol errors are additionally marked as such, as the connection is closed after replying with them.
+///
+/// Error messages can quote user input, so CR and LF characters are replaced with spaces,
+/// as they aren't allowed in error replies.
+pub(crate) fn error_reply(err: &CmdError) -> Value {
+    let msg = if err.is_protocol_error() {
+        format!("ERR Protocol error: {err}")
+    } else {
+        format!("{} {err}", err.code())
+    };
+    let msg = msg.replace(['\r', '\n'], " ");
+
+    Value::Error(Bytes::from(msg))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::constants::DEFAULT_DATABASES;
+    use crate::errors::RESPError;
+    use crate::resp::FrameDecoder;
+    use crate::resp::Protocol;
+    use crate::storage::cursor::ScanSet;
+    use crate::storage::object::Object;
+    use crate::storage::Storage;
+    use crate::types::{InMemoryExpiryTimeHashMap, InMemoryStorageHashMap, StorageType};
+    use bytes::Bytes;
+    use std::sync::RwLock;
+    use std::sync::{Arc, OnceLock};
+    use std::time::Duration;
+
+    /// We only get one set of databases that is shared between all tests, which, by the way,
+    /// run concurrently, so pay attention when naming keys!
+    ///
+    /// Keys should be different in all tests that involve `SET` and `GET`, whether they employ
+    /// setting TTL or not, because TTL is implicitly set to infinity if not set explicitly,
+    /// and this may affect other tests that expect shorter TTL, obviously.
+    ///
+    /// Tests of commands that affect whole databases, such as `FLUSHALL`, use [`new_databases`] instead.
+    static DATABASES: OnceLock<
+        Vec<ConcurrentStorageType<InMemoryStorageHashMap, InMemoryExpiryTimeHashMap>>,
+    > = OnceLock::new();
+
+    /// Creates the default number of empty databases.
+    pub(crate) fn new_databases(
+    ) -> Vec<ConcurrentStorageType<InMemoryStorageHashMap, InMemoryExpiryTimeHashMap>> {
+        (0..DEFAULT_DATABASES)
+            .map(|_| {
+                Arc::new(RwLock::new(Storage::<
+                    StorageType<InMemoryStorageHashMap, InMemoryExpiryTimeHashMap>,
+                    InMemoryStorageHashMap,
+                    InMemoryExpiryTimeHashMap,
+                >::new()))
+            })
+            .collect()
+    }
+
+    /// Returns the databases that are shared between all tests.
+    pub(crate) fn databases(
+    ) -> &'static [ConcurrentStorageType<InMemoryStorageHashMap, InMemoryExpiryTimeHashMap>] {
+        DATABASES.get_or_init(new_databases)
+    }
+
+    /// Returns the database 0 of the shared databases.
+    pub(crate) fn storage(
+    ) -> &'static ConcurrentStorageType<InMemoryStorageHashMap, InMemoryExpiryTimeHashMap> {
+        &databases()[0]
+    }
+
+    /// Runs a single command, given as words, against the given databases, as the given client,
+    /// the same way a request is dispatched.
+    pub(crate) fn run_on(
+        databases: &[ConcurrentStorageType<InMemoryStorageHashMap, InMemoryExpiryTimeHashMap>],
+        client: &mut Client,
+        words: &[&str],
+    ) -> Result<Value, CmdError> {
+        let args = words
+            .iter()
+            .map(|word| Bytes::copy_from_slice(word.as_bytes()))
+            .collect::<Vec<_>>();
+        dispatch(databases, client, &args)
+    }
+
+    /// Runs a single command against the shared databases, as the given client, which keeps its selected database.
+    pub(crate) fn run_as(client: &mut Client, words: &[&str]) -> Result<Value, CmdError> {
+        run_on(databases(), client, words)
+    }
+
+    /// Runs a single command against the shared databases, as a new client, so in the database 0.
+    pub(crate) fn run(words: &[&str]) -> Result<Value, CmdError> {
+        run_as(&mut Client::new(), words)
+    }
+
+    /// Splits pipelined requests into frames, the same way the connection handler does it,
+    /// and handles them in order, concatenating their replies.
+    async fn handle_pipeline<KV: Crud + 'static, KE: Crud + 'static>(
+        databases: &[ConcurrentStorageType<KV, KE>],
+        client: &mut Client,
+        input: &str,
+    ) -> Result<BytesMut, CmdError> {
+        let mut decoder = FrameDecoder::default();
+        decoder.buffer_mut().extend_from_slice(input.as_bytes());
+        let mut result = BytesMut::new();
+        while let Some(frame) = decoder.decode()? {
+            result.extend_from_slice(&handle_request(databases, client, &frame).await?);
+        }
+        Ok(result)
+    }
+
+    #[tokio::test]
+    async fn handle_request_doesnt_store_slices_of_the_request() {
+        let databases = new_databases();
+        let input = Bytes::from("*3\r\n$3\r\nSET\r\n$9\r\nslice_key\r\n$5\r\nvalue\r\n");
+        handle_request(&databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+
+        let s = read_lock(&databases[0]);
+        let (key, entry) = s.0.iter().next().unwrap();
+        let value = entry.value.as_string().unwrap();
+        let request = input.as_ptr_range();
+        assert_eq!("slice_key", key);
+        assert_eq!("value", value);
+        assert!(!request.contains(&key.as_ptr()));
+        assert!(!request.contains(&value.as_ptr()));
+    }
+
+    #[tokio::test]
+    async fn handle_request_ping_pong_missing_crlf_at_end() {
+        let databases = databases();
+        let input = "*1\r\n$4\r\nPING";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input).await;
+
+        if let Err(CmdError::CRLFNotAtEnd) = result {
+        } else {
+            assert_eq!(0, 1)
+        };
+    }
+
+    #[tokio::test]
+    async fn handle_request_ping_pong_pass() {
+        let databases = databases();
+        let input = "*1\r\n$4\r\nPING\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+
+        let expected = Bytes::from("+PONG\r\n");
+
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_ping_pong_fail_missing_array_len() {
+        let databases = databases();
+        // Anything that isn't an array is an inline command, and `$4` isn't a command.
+        let input = "$4\r\nPING\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input).await;
+
+        if let Err(CmdError::UnrecognizedCmd(..)) = result {
+        } else {
+            assert_eq!(0, 1)
+        };
+    }
+
+    #[tokio::test]
+    async fn handle_request_ping_ping_ping() {
+        let databases = databases();
+        let input = "*1\r\n$4\r\nPinG\r\n*1\r\n$4\r\nPinG\r\n*1\r\n$4\r\nPinG\r\n";
+        let result = handle_pipeline(databases, &mut Client::new(), input)
+            .await
+            .unwrap();
+
+        let expected = Bytes::from("+PONG\r\n+PONG\r\n+PONG\r\n");
+
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_ping_with_arg() {
+        let databases = databases();
+        let input = "*2\r\n$4\r\nPinG\r\n$13\r\nHello, world!\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+
+        let expected = Bytes::from("$13\r\nHello, world!\r\n");
+
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_echo_hey() {
+        let databases = databases();
+        let input = "*2\r\n$4\r\nECHO\r\n$3\r\nHey\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+
+        let expected = Bytes::from("$3\r\nHey\r\n");
+
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_echo_hey_hey() {
+        let databases = databases();
+        let input = "*2\r\n$4\r\nEchO\r\n$3\r\nHey\r\n*2\r\n$4\r\nEchO\r\n$3\r\nHey\r\n";
+        let result = handle_pipeline(databases, &mut Client::new(), input)
+            .await
+            .unwrap();
+
+        let expected = Bytes::from("$3\r\nHey\r\n$3\r\nHey\r\n");
+
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_ping_echo_ping_arg() {
+        let databases = databases();
+        let input = "*1\r\n$4\r\nPinG\r\n*2\r\n$4\r\nEchO\r\n$15\r\nHey, what's up?\r\n*2\r\n$4\r\nPinG\r\n$13\r\nHello, world!\r\n";
+        let result = handle_pipeline(databases, &mut Client::new(), input)
+            .await
+            .unwrap();
+
+        let expected = Bytes::from("+PONG\r\n$15\r\nHey, what's up?\r\n$13\r\nHello, world!\r\n");
+
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_ping_arg_echo_ping() {
+        let databases = databases();
+        let input = "*2\r\n$4\r\nPinG\r\n$13\r\nHello, world!\r\n*2\r\n$4\r\nEchO\r\n$15\r\nHey, what's up?\r\n*1\r\n$4\r\nPinG\r\n";
+        let result = handle_pipeline(databases, &mut Client::new(), input)
+            .await
+            .unwrap();
+
+        let expected = Bytes::from("$13\r\nHello, world!\r\n$15\r\nHey, what's up?\r\n+PONG\r\n");
+
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_set_01_get() {
+        let databases = databases();
+
+        let input = "*3\r\n$3\r\nSET\r\n$5\r\nKey01\r\n$7\r\nValue01\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("+OK\r\n");
+        assert_eq!(expected, result);
+
+        let input = "*2\r\n$3\r\nGET\r\n$5\r\nApple\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("$-1\r\n");
+        assert_eq!(expected, result);
+
+        let input = "*2\r\n$3\r\nGET\r\n$5\r\nKey01\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("$7\r\nValue01\r\n");
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_set_02_px_get_on_time() {
+        let databases = databases();
+
+        let input = "*5\r\n$3\r\nSET\r\n$5\r\nkey02\r\n$7\r\nvalue02\r\n$2\r\nPX\r\n$3\r\n100\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("+OK\r\n");
+        assert_eq!(expected, result);
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let input = "*2\r\n$3\r\nGET\r\n$5\r\nkey02\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("$7\r\nvalue02\r\n");
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_set_03_px_get_expired() {
+        let databases = databases();
+
+        let input = "*5\r\n$3\r\nSET\r\n$5\r\nkey03\r\n$7\r\nvalue03\r\n$2\r\npx\r\n$3\r\n100\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("+OK\r\n");
+        assert_eq!(expected, result);
+
+        tokio::time::sleep(Duration::from_millis(120)).await;
+
+        let input = "*2\r\n$3\r\nGET\r\n$5\r\nkey03\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("$-1\r\n");
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_set_04_ex_get_on_time() {
+        let databases = databases();
+
+        let input = "*5\r\n$3\r\nSET\r\n$5\r\nkey04\r\n$7\r\nvalue04\r\n$2\r\nEX\r\n$2\r\n10\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("+OK\r\n");
+        assert_eq!(expected, result);
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let input = "*2\r\n$3\r\nGET\r\n$5\r\nkey04\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("$7\r\nvalue04\r\n");
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_set_05_ex_get_expired() {
+        let databases = databases();
+
+        let input = "*5\r\n$3\r\nSET\r\n$5\r\nkey05\r\n$7\r\nvalue05\r\n$2\r\nex\r\n$1\r\n1\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("+OK\r\n");
+        assert_eq!(expected, result);
+
+        tokio::time::sleep(Duration::from_millis(1200)).await;
+
+        let input = "*2\r\n$3\r\nGET\r\n$5\r\nkey05\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("$-1\r\n");
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_set_06_set_set_px_get_on_time_then_expired() {
+        let databases = databases();
+
+        let input = "*3\r\n$3\r\nSET\r\n$5\r\nkey06\r\n$7\r\nvalue06\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("+OK\r\n");
+        assert_eq!(expected, result);
+        let input = "*2\r\n$3\r\nGET\r\n$5\r\nkey06\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("$7\r\nvalue06\r\n");
+        assert_eq!(expected, result);
+
+        let input = "*5\r\n$3\r\nSET\r\n$5\r\nkey06\r\n$7\r\nvalue06\r\n$2\r\npX\r\n$3\r\n100\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("+OK\r\n");
+        assert_eq!(expected, result);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let input = "*2\r\n$3\r\nGET\r\n$5\r\nkey06\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("$7\r\nvalue06\r\n");
+        assert_eq!(expected, result);
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        let input = "*2\r\n$3\r\nGET\r\n$5\r\nkey06\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("$-1\r\n");
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_set_07_set_px_set_get_on_time_should_not_expire() {
+        let databases = databases();
+
+        let input = "*5\r\n$3\r\nSET\r\n$5\r\nkey07\r\n$7\r\nvalue07\r\n$2\r\nPx\r\n$3\r\n100\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("+OK\r\n");
+        assert_eq!(expected, result);
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let input = "*2\r\n$3\r\nGET\r\n$5\r\nkey07\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("$7\r\nvalue07\r\n");
+        assert_eq!(expected, result);
+
+        let input = "*3\r\n$3\r\nSET\r\n$5\r\nkey07\r\n$7\r\nvalue07\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("+OK\r\n");
+        assert_eq!(expected, result);
+        let input = "*2\r\n$3\r\nGET\r\n$5\r\nkey07\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("$7\r\nvalue07\r\n");
+        assert_eq!(expected, result);
+
+        tokio::time::sleep(Duration::from_millis(120)).await;
+
+        let input = "*2\r\n$3\r\nGET\r\n$5\r\nkey07\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("$7\r\nvalue07\r\n");
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_set_08_set_px_set_px_get_on_time_twice_then_expired() {
+        let databases = databases();
+
+        let input = "*5\r\n$3\r\nSET\r\n$5\r\nkey08\r\n$7\r\nvalue08\r\n$2\r\nPX\r\n$3\r\n100\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("+OK\r\n");
+        assert_eq!(expected, result);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let input = "*2\r\n$3\r\nGET\r\n$5\r\nkey08\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("$7\r\nvalue08\r\n");
+        assert_eq!(expected, result);
+
+        let input = "*5\r\n$3\r\nSET\r\n$5\r\nkey08\r\n$7\r\nvalue08\r\n$2\r\nPX\r\n$3\r\n100\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("+OK\r\n");
+        assert_eq!(expected, result);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let input = "*2\r\n$3\r\nGET\r\n$5\r\nkey08\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("$7\r\nvalue08\r\n");
+        assert_eq!(expected, result);
+
+        tokio::time::sleep(Duration::from_millis(70)).await;
+        let input = "*2\r\n$3\r\nGET\r\n$5\r\nkey08\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("$7\r\nvalue08\r\n");
+        assert_eq!(expected, result);
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let input = "*2\r\n$3\r\nGET\r\n$5\r\nkey08\r\n";
+        let input = Bytes::from(input);
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        let expected = Bytes::from("$-1\r\n");
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_hello_switches_protocol() {
+        let databases = databases();
+        let mut client = Client::new();
+
+        let input = Bytes::from("*2\r\n$3\r\nGET\r\n$9\r\nhello_key\r\n");
+        let result = handle_request(databases, &mut client, &input)
+            .await
+            .unwrap();
+        assert_eq!(Bytes::from("$-1\r\n"), result);
+
+        let input = Bytes::from("*4\r\n$5\r\nHELLO\r\n$1\r\n3\r\n$7\r\nSETNAME\r\n$4\r\nconn\r\n");
+        let result = handle_request(databases, &mut client, &input)
+            .await
+            .unwrap();
+        assert!(result.starts_with(b"%7\r\n$6\r\nserver\r\n$5\r\nredis\r\n"));
+        assert_eq!(Protocol::RESP3, client.protocol);
+        assert_eq!(Some(Bytes::from("conn")), client.name);
+
+        let input = Bytes::from("*2\r\n$3\r\nGET\r\n$9\r\nhello_key\r\n");
+        let result = handle_request(databases, &mut client, &input)
+            .await
+            .unwrap();
+        assert_eq!(Bytes::from("_\r\n"), result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_inline_ping() {
+        let databases = databases();
+        let input = Bytes::from("PING\r\n");
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        assert_eq!(Bytes::from("+PONG\r\n"), result);
+
+        let input = Bytes::from("  \r\n");
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        assert!(result.is_empty());
+    }
+
+    #[tokio::test]
+    async fn handle_request_inline_set_get_quoted() {
+        let databases = databases();
+        let input = Bytes::from("set inline_key \"bar baz\"\n");
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        assert_eq!(Bytes::from("+OK\r\n"), result);
+
+        let input = Bytes::from("*2\r\n$3\r\nGET\r\n$10\r\ninline_key\r\n");
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        assert_eq!(Bytes::from("$7\r\nbar baz\r\n"), result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_ping_too_many_args() {
+        let databases = databases();
+        let input = Bytes::from("*3\r\n$4\r\nPinG\r\n$4\r\nPinG\r\n$4\r\nPinG\r\n");
+        let result = handle_request(databases, &mut Client::new(), &input).await;
+
+        assert!(matches!(result, Err(CmdError::WrongNumberOfArgs(name)) if name == "ping"));
+    }
+
+    #[tokio::test]
+    async fn handle_request_wrong_arity() {
+        let databases = databases();
+        let input = Bytes::from("*1\r\n$4\r\nECHO\r\n");
+        let result = handle_request(databases, &mut Client::new(), &input).await;
+        assert!(matches!(result, Err(CmdError::WrongNumberOfArgs(name)) if name == "echo"));
+
+        let input = Bytes::from("*3\r\n$3\r\nGET\r\n$1\r\na\r\n$1\r\nb\r\n");
+        let result = handle_request(databases, &mut Client::new(), &input).await;
+        assert!(matches!(result, Err(CmdError::WrongNumberOfArgs(name)) if name == "get"));
+
+        let input = Bytes::from("*2\r\n$3\r\nset\r\n$1\r\na\r\n");
+        let result = handle_request(databases, &mut Client::new(), &input).await;
+        assert!(matches!(result, Err(CmdError::WrongNumberOfArgs(name)) if name == "set"));
+    }
+
+    #[tokio::test]
+    async fn handle_request_command_names_as_arguments() {
+        let databases = databases();
+        let input = "*3\r\n$3\r\nSET\r\n$6\r\nkey_09\r\n$4\r\nPING\r\n*2\r\n$4\r\nECHO\r\n$3\r\nGET\r\n*2\r\n$4\r\nPING\r\n$4\r\nECHO\r\n*2\r\n$3\r\nGET\r\n$6\r\nkey_09\r\n";
+        let result = handle_pipeline(databases, &mut Client::new(), input)
+            .await
+            .unwrap();
+
+        let expected = Bytes::from("+OK\r\n$3\r\nGET\r\n$4\r\nECHO\r\n$4\r\nPING\r\n");
+
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_unknown_command_reply() {
+        let databases = databases();
+        let input = Bytes::from("FOO a \"b\\r\\nc\"\r\n");
+        let err = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap_err();
+        assert!(!err.is_protocol_error());
+
+        let mut result = BytesMut::new();
+        error_reply(&err).write_to(&mut result, Protocol::RESP2);
+        let expected =
+            Bytes::from("-ERR unknown command 'FOO', with args beginning with: 'a' 'b  c' \r\n");
+        assert_eq!(expected, result);
+    }
+
+    #[tokio::test]
+    async fn handle_request_error_replies() {
+        let databases = databases();
+        let cases = [
+            (
+                "*1\r\n$3\r\nGET\r\n",
+                "-ERR wrong number of arguments for 'get' command\r\n",
+            ),
+            (
+                "*5\r\n$3\r\nSET\r\n$5\r\nkey10\r\n$1\r\nv\r\n$2\r\nXX\r\n$2\r\n10\r\n",
+                "-ERR syntax error\r\n",
+            ),
+            (
+                "*5\r\n$3\r\nSET\r\n$5\r\nkey10\r\n$1\r\nv\r\n$2\r\nEX\r\n$3\r\nten\r\n",
+                "-ERR value is not an integer or out of range\r\n",
+            ),
+        ];
+        for (input, expected) in cases {
+            let err = handle_request(databases, &mut Client::new(), &Bytes::from(input))
+                .await
+                .unwrap_err();
+            let mut result = BytesMut::new();
+            error_reply(&err).write_to(&mut result, Protocol::RESP2);
+            assert_eq!(Bytes::from(expected), result);
+        }
+    }
+
+    #[tokio::test]
+    async fn handle_request_null_and_empty_arrays_ignored() {
+        let databases = databases();
+        let input = "*-1\r\n*0\r\n*1\r\n$4\r\nPING\r\n";
+        let result = handle_pipeline(databases, &mut Client::new(), input)
+            .await
+            .unwrap();
+
+        assert_eq!(Bytes::from("+PONG\r\n"), result);
+    }
+
+    #[test]
+    fn error_reply_wrong_type() {
+        let expected = Value::Error(Bytes::from(
+            "WRONGTYPE Operation against a key holding the wrong kind of value",
+        ));
+        assert_eq!(expected, error_reply(&CmdError::WrongType));
+    }
+
+    #[test]
+    fn error_reply_protocol_error() {
+        let err = CmdError::from(RESPError::UnbalancedQuotes);
+        assert!(err.is_protocol_error());
+        let expected = Value::Error(Bytes::from(
+            "ERR Protocol error: unbalanced quotes in request",
+        ));
+        assert_eq!(expected, error_reply(&err));
+    }
+
+    #[tokio::test]
+    async fn handle_request_set_get_binary() {
+        let databases = databases();
+        let input =
+            Bytes::from_static(b"*3\r\n$3\r\nSET\r\n$4\r\nk\xff\x00\x01\r\n$4\r\n\x80\r\n\xfe\r\n");
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        assert_eq!(Bytes::from("+OK\r\n"), result);
+
+        let input = Bytes::from_static(b"*2\r\n$3\r\nGET\r\n$4\r\nk\xff\x00\x01\r\n");
+        let result = handle_request(databases, &mut Client::new(), &input)
+            .await
+            .unwrap();
+        assert_eq!(Bytes::from_static(b"$4\r\n\x80\r\n\xfe\r\n"), result);
+    }
+
+    #[test]
+    fn parse_integer_canonical() {
+        assert_eq!(0, parse_integer(b"0").unwrap());
+        assert_eq!(-15, parse_integer(b"-15").unwrap());
+        assert_eq!(i64::MAX, parse_integer(b"9223372036854775807").unwrap());
+        assert_eq!(i64::MIN, parse_integer(b"-9223372036854775808").unwrap());
+        for arg in [
+            &b""[..],
+            b"-",
+            b"-0",
+            b"01",
+            b"+1",
+            b" 1",
+            b"1 ",
+            b"1.5",
+            b"9223372036854775808",
+        ] {
+            assert!(matches!(parse_integer(arg), Err(CmdError::NotAnInteger)));
+        }
+    }
+
+    #[test]
+    fn with_key_borrows_live_values_and_deletes_expired_ones() {
+        let s = new_databases().swap_remove(0);
+        let now = now_ms().unwrap();
+        {
+            let mut s = write_lock(&s);
+            s.create(&Bytes::from("live"), Object::Set(ScanSet::new()), None);
+            s.create(&Bytes::from("gone"), Object::default(), Some(now - 1));
+        }
+
+        let type_name = with_key(&s, &Bytes::from("live"), |value| {
+            value.map(Object::type_name)
+        });
+        assert_eq!(Some("set"), type_name.unwrap());
+        assert!(with_key(&s, &Bytes::from("gone"), |value| value.is_none()).unwrap());
+        assert!(read_lock(&s).get(&Bytes::from("gone")).is_none());
+        assert_eq!(1, read_lock(&s).size());
+    }
+}
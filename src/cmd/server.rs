@@ -0,0 +1,449 @@
+//! # Server Commands
+//!
+//! Handlers for the commands of the [server](https://redis.io/docs/latest/commands/?group=server) group
+
+use crate::cmd::table::{self, Command};
+use crate::cmd::{parse_db_index, read_lock, write_lock, write_lock_pair};
+use crate::conn::Client;
+use crate::errors::CmdError;
+use crate::lazyfree;
+use crate::resp::Value;
+use crate::storage::generic::Crud;
+use crate::types::ConcurrentStorageType;
+use bytes::Bytes;
+
+/// Handler for the [COMMAND](https://redis.io/docs/latest/commands/command/) command
+///
+/// `COMMAND` => details about all commands
+///
+/// Serves information about the supported commands from the [command table](table::commands).
+/// Client libraries call it at startup, for example, to find out where the keys are in a command's arguments.
+///
+/// Subcommands:
+/// - `COUNT` => the number of commands
+/// - `DOCS [command-name ...]` => a map of command names to their documentation
+/// - `GETKEYS command [arg ...]` => the keys from a full command
+/// - `HELP` => the list of subcommands
+/// - `INFO [command-name ...]` => details about the given commands, or a nil value for each unknown command
+/// - `LIST [FILTERBY <MODULE module-name | ACLCAT category>]` => the names of the commands
+///
+/// Example:
+/// - `"*2\r\n$7\r\nCOMMAND\r\n$5\r\nCOUNT\r\n"` => `:6\r\n`
+pub(crate) fn handle_command<KV: Crud + 'static, KE: Crud + 'static>(
+    _storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let commands = table::commands::<KV, KE>();
+    let Some(subcommand) = args.get(1) else {
+        return Ok(Value::Array(commands.iter().map(Command::info).collect()));
+    };
+
+    let subcommand = subcommand.to_ascii_lowercase();
+    let arity = match subcommand.as_slice() {
+        b"count" | b"help" => 2,
+        b"docs" | b"info" | b"list" => -2,
+        b"getkeys" => -3,
+        _ => {
+            return Err(CmdError::UnknownSubcommand(
+                "COMMAND".to_string(),
+                String::from_utf8_lossy(&args[1]).to_string(),
+            ))
+        }
+    };
+    let num_words = args.len() as i64;
+    if (arity >= 0 && num_words != arity) || num_words < arity.abs() {
+        return Err(CmdError::WrongNumberOfArgs(format!(
+            "command|{}",
+            String::from_utf8_lossy(&subcommand)
+        )));
+    }
+
+    match subcommand.as_slice() {
+        b"count" => Ok(Value::Integer(commands.len() as i64)),
+        b"docs" => {
+            let docs = if args.len() == 2 {
+                commands.iter().collect::<Vec<_>>()
+            } else {
+                args[2..]
+                    .iter()
+                    .filter_map(|name| table::lookup::<KV, KE>(name))
+                    .collect()
+            };
+            Ok(Value::Map(
+                docs.into_iter()
+                    .map(|cmd| {
+                        (
+                            Value::BulkString(Bytes::from_static(cmd.name.as_bytes())),
+                            cmd.docs(),
+                        )
+                    })
+                    .collect(),
+            ))
+        }
+        b"getkeys" => {
+            let full_command = &args[2..];
+            let cmd = table::lookup::<KV, KE>(&full_command[0]).ok_or(CmdError::InvalidCommand)?;
+            if !cmd.check_arity(full_command.len()) {
+                return Err(CmdError::InvalidNumberOfArgs);
+            }
+            if cmd.key_specs.is_empty() {
+                return Err(CmdError::NoKeyArgs);
+            }
+            let keys = cmd.keys(full_command).ok_or(CmdError::InvalidKeyArgs)?;
+            if keys.is_empty() {
+                return Err(CmdError::NoKeyArgs);
+            }
+            Ok(Value::Array(
+                keys.into_iter().map(Value::BulkString).collect(),
+            ))
+        }
+        b"help" => Ok(Value::Array(
+            [
+                "COMMAND <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+                "(no subcommand)",
+                "    Return details about all Redis commands.",
+                "COUNT",
+                "    Return the total number of commands in this Redis server.",
+                "LIST [FILTERBY (MODULE <module-name>|ACLCAT <category>)]",
+                "    Return a list of all commands in this Redis server.",
+                "INFO [<command-name> ...]",
+                "    Return details about multiple Redis commands.",
+                "    If no command names are given, documentation details for all",
+                "    commands are returned.",
+                "DOCS [<command-name> ...]",
+                "    Return documentation details about multiple Redis commands.",
+                "    If no command names are given, documentation details for all",
+                "    commands are returned.",
+                "GETKEYS <full-command>",
+                "    Return the keys from a full Redis command.",
+                "HELP",
+                "    Print this help.",
+            ]
+            .into_iter()
+            .map(|line| Value::SimpleString(Bytes::from_static(line.as_bytes())))
+            .collect(),
+        )),
+        b"info" => Ok(Value::Array(if args.len() == 2 {
+            commands.iter().map(Command::info).collect()
+        } else {
+            args[2..]
+                .iter()
+                .map(|name| table::lookup::<KV, KE>(name).map_or(Value::Null, Command::info))
+                .collect()
+        })),
+        b"list" => {
+            let filtered = match &args[2..] {
+                [] => commands.iter().collect::<Vec<_>>(),
+                [filterby, kind, value] if filterby.eq_ignore_ascii_case(b"FILTERBY") => {
+                    match kind.to_ascii_uppercase().as_slice() {
+                        b"MODULE" => vec![],
+                        b"ACLCAT" => commands
+                            .iter()
+                            .filter(|cmd| cmd.has_acl_category(value))
+                            .collect(),
+                        _ => return Err(CmdError::SyntaxError),
+                    }
+                }
+                _ => return Err(CmdError::SyntaxError),
+            };
+            Ok(Value::Array(
+                filtered
+                    .into_iter()
+                    .map(|cmd| Value::BulkString(Bytes::from_static(cmd.name.as_bytes())))
+                    .collect(),
+            ))
+        }
+        _ => unreachable!("Unknown subcommands have been rejected above"),
+    }
+}
+
+/// Handler for the [DBSIZE](https://redis.io/docs/latest/commands/dbsize/) command
+///
+/// `DBSIZE` => `number of keys`
+///
+/// Returns the number of keys in the selected database as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers).
+/// Keys that have expired, but haven't been evicted yet, are counted as well, the same as Redis does it.
+///
+/// Example:
+/// - `"*1\r\n$6\r\nDBSIZE\r\n"` => `:3\r\n`
+pub(crate) fn handle_dbsize<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    _args: &[Bytes],
+) -> Result<Value, CmdError> {
+    Ok(Value::Integer(read_lock(storage).size() as i64))
+}
+
+/// Handler for the [FLUSHALL](https://redis.io/docs/latest/commands/flushall/) command
+///
+/// `FLUSHALL [ASYNC | SYNC]` => `OK`
+///
+/// Deletes all keys of all databases. All databases are flushed at once, so no command observes some of them
+/// flushed and the others not.
+///
+/// Options:
+/// - `ASYNC` -- Free the deleted keys in a background thread.
+/// - `SYNC` -- Free the deleted keys before replying; this is the default.
+///
+/// Example:
+/// - `"*1\r\n$8\r\nFLUSHALL\r\n"` => `+OK\r\n`
+pub(crate) fn handle_flushall<KV: Crud + 'static, KE: Crud + 'static>(
+    databases: &[ConcurrentStorageType<KV, KE>],
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let lazy = parse_flush_mode(args)?;
+    // The locks are acquired in the order of the databases' indexes, the same as in `write_lock_pair`.
+    let mut locked = databases.iter().map(write_lock).collect::<Vec<_>>();
+    let flushed = locked.iter_mut().map(|s| s.take()).collect::<Vec<_>>();
+    drop(locked);
+    if lazy {
+        lazyfree::free_async(flushed);
+    }
+    Ok(Value::OK)
+}
+
+/// Handler for the [FLUSHDB](https://redis.io/docs/latest/commands/flushdb/) command
+///
+/// `FLUSHDB [ASYNC | SYNC]` => `OK`
+///
+/// Deletes all keys of the selected database.
+///
+/// Options:
+/// - `ASYNC` -- Free the deleted keys in a background thread.
+/// - `SYNC` -- Free the deleted keys before replying; this is the default.
+///
+/// Example:
+/// - `"*2\r\n$7\r\nFLUSHDB\r\n$5\r\nASYNC\r\n"` => `+OK\r\n`
+pub(crate) fn handle_flushdb<KV: Crud + 'static, KE: Crud + 'static>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let lazy = parse_flush_mode(args)?;
+    let flushed = write_lock(storage).take();
+    if lazy {
+        lazyfree::free_async(flushed);
+    }
+    Ok(Value::OK)
+}
+
+/// Handler for the [SWAPDB](https://redis.io/docs/latest/commands/swapdb/) command
+///
+/// `SWAPDB index1 index2` => `OK`
+///
+/// Swaps the contents of two databases, together with the keys' expiration times, atomically.
+/// Clients that have one of the databases selected see the contents of the other one right away.
+///
+/// Example:
+/// - `"*3\r\n$6\r\nSWAPDB\r\n$1\r\n0\r\n$1\r\n1\r\n"` => `+OK\r\n`
+pub(crate) fn handle_swapdb<KV: Crud, KE: Crud>(
+    databases: &[ConcurrentStorageType<KV, KE>],
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let first = parse_swapdb_index(&args[1], databases.len(), "first")?;
+    let second = parse_swapdb_index(&args[2], databases.len(), "second")?;
+    if first != second {
+        let (mut first, mut second) = write_lock_pair(databases, first, second);
+        std::mem::swap(&mut *first, &mut *second);
+    }
+    Ok(Value::OK)
+}
+
+/// Parses the optional `ASYNC` or `SYNC` argument of the flush commands, and returns whether to free lazily.
+fn parse_flush_mode(args: &[Bytes]) -> Result<bool, CmdError> {
+    match args {
+        [_] => Ok(false),
+        [_, mode] if mode.eq_ignore_ascii_case(b"SYNC") => Ok(false),
+        [_, mode] if mode.eq_ignore_ascii_case(b"ASYNC") => Ok(true),
+        _ => Err(CmdError::SyntaxError),
+    }
+}
+
+/// Parses a database index of `SWAPDB`, which names the `ordinal` index in its error if it isn't an integer.
+fn parse_swapdb_index(arg: &[u8], count: usize, ordinal: &'static str) -> Result<usize, CmdError> {
+    parse_db_index(arg, count).map_err(|err| match err {
+        CmdError::NotAnInteger => CmdError::InvalidDbIndex(ordinal),
+        err => err,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::tests::{new_databases, run_on, storage};
+    use crate::types::{InMemoryExpiryTimeHashMap, InMemoryStorageHashMap};
+
+    fn command(words: &[&str]) -> Result<Value, CmdError> {
+        let args = words
+            .iter()
+            .map(|word| Bytes::copy_from_slice(word.as_bytes()))
+            .collect::<Vec<_>>();
+        handle_command(storage(), &mut Client::new(), &args)
+    }
+
+    #[test]
+    fn handle_command_count() {
+        let count = table::commands::<InMemoryStorageHashMap, InMemoryExpiryTimeHashMap>().len();
+        let result = command(&["COMMAND", "COUNT"]).unwrap();
+        assert_eq!(Value::Integer(count as i64), result);
+    }
+
+    #[test]
+    fn handle_command_info() {
+        let result = command(&["COMMAND", "INFO", "get", "nope"]).unwrap();
+        let Value::Array(infos) = result else {
+            panic!("Expected an array");
+        };
+        assert_eq!(2, infos.len());
+        assert!(
+            matches!(&infos[0], Value::Array(info) if info[0] == Value::BulkString(Bytes::from("get")))
+        );
+        assert_eq!(Value::Null, infos[1]);
+    }
+
+    #[test]
+    fn handle_command_list_aclcat() {
+        let result = command(&["COMMAND", "LIST", "FILTERBY", "ACLCAT", "string"]).unwrap();
+        let Value::Array(names) = result else {
+            panic!("Expected an array");
+        };
+        assert!(names.contains(&Value::BulkString(Bytes::from("get"))));
+        assert!(names.contains(&Value::BulkString(Bytes::from("set"))));
+        assert!(!names.contains(&Value::BulkString(Bytes::from("ping"))));
+        let string_commands =
+            table::commands::<InMemoryStorageHashMap, InMemoryExpiryTimeHashMap>()
+                .iter()
+                .filter(|cmd| cmd.docs.group == "string")
+                .count();
+        assert_eq!(string_commands, names.len());
+    }
+
+    #[test]
+    fn handle_command_getkeys() {
+        let result = command(&["COMMAND", "GETKEYS", "SET", "key", "value", "EX", "10"]).unwrap();
+        assert_eq!(
+            Value::Array(vec![Value::BulkString(Bytes::from("key"))]),
+            result
+        );
+
+        let result = command(&["COMMAND", "GETKEYS", "PING"]);
+        assert!(matches!(result, Err(CmdError::NoKeyArgs)));
+
+        let result = command(&["COMMAND", "GETKEYS", "GET"]);
+        assert!(matches!(result, Err(CmdError::InvalidNumberOfArgs)));
+
+        let result = command(&["COMMAND", "GETKEYS", "NOPE", "key"]);
+        assert!(matches!(result, Err(CmdError::InvalidCommand)));
+    }
+
+    #[test]
+    fn handle_command_errors() {
+        let result = command(&["COMMAND", "NOPE"]);
+        assert!(
+            matches!(result, Err(CmdError::UnknownSubcommand(cmd, sub)) if cmd == "COMMAND" && sub == "NOPE")
+        );
+
+        let result = command(&["COMMAND", "COUNT", "extra"]);
+        assert!(
+            matches!(result, Err(CmdError::WrongNumberOfArgs(name)) if name == "command|count")
+        );
+    }
+
+    #[test]
+    fn handle_swapdb() {
+        let databases = new_databases();
+        let (mut zero, mut one) = (Client::new(), Client::new());
+        run_on(&databases, &mut one, &["SELECT", "1"]).unwrap();
+        run_on(&databases, &mut zero, &["SET", "a", "0", "PX", "100000"]).unwrap();
+        run_on(&databases, &mut one, &["SET", "b", "1"]).unwrap();
+
+        assert_eq!(
+            Value::OK,
+            run_on(&databases, &mut zero, &["SWAPDB", "0", "1"]).unwrap()
+        );
+        assert_eq!(
+            Value::Null,
+            run_on(&databases, &mut zero, &["GET", "a"]).unwrap()
+        );
+        assert_eq!(
+            Value::BulkString(Bytes::from("1")),
+            run_on(&databases, &mut zero, &["GET", "b"]).unwrap()
+        );
+        assert_eq!(
+            Value::Integer(100),
+            run_on(&databases, &mut one, &["TTL", "a"]).unwrap()
+        );
+        assert_eq!(
+            Value::OK,
+            run_on(&databases, &mut zero, &["SWAPDB", "1", "1"]).unwrap()
+        );
+
+        assert!(matches!(
+            run_on(&databases, &mut zero, &["SWAPDB", "x", "1"]),
+            Err(CmdError::InvalidDbIndex("first"))
+        ));
+        assert!(matches!(
+            run_on(&databases, &mut zero, &["SWAPDB", "0", "x"]),
+            Err(CmdError::InvalidDbIndex("second"))
+        ));
+        assert!(matches!(
+            run_on(&databases, &mut zero, &["SWAPDB", "0", "16"]),
+            Err(CmdError::DbIndexOutOfRange)
+        ));
+    }
+
+    #[test]
+    fn handle_flushdb_flushall_dbsize() {
+        let databases = new_databases();
+        let (mut zero, mut one) = (Client::new(), Client::new());
+        run_on(&databases, &mut one, &["SELECT", "1"]).unwrap();
+        for key in ["a", "b", "c"] {
+            run_on(&databases, &mut zero, &["SET", key, "0"]).unwrap();
+            run_on(&databases, &mut one, &["SET", key, "1"]).unwrap();
+        }
+        assert_eq!(
+            Value::Integer(3),
+            run_on(&databases, &mut zero, &["DBSIZE"]).unwrap()
+        );
+
+        assert_eq!(
+            Value::OK,
+            run_on(&databases, &mut zero, &["FLUSHDB"]).unwrap()
+        );
+        assert_eq!(
+            Value::Integer(0),
+            run_on(&databases, &mut zero, &["DBSIZE"]).unwrap()
+        );
+        assert_eq!(
+            Value::Integer(3),
+            run_on(&databases, &mut one, &["DBSIZE"]).unwrap()
+        );
+
+        run_on(&databases, &mut zero, &["SET", "d", "0", "PX", "100000"]).unwrap();
+        assert_eq!(
+            Value::OK,
+            run_on(&databases, &mut zero, &["FLUSHALL", "async"]).unwrap()
+        );
+        for client in [&mut zero, &mut one] {
+            assert_eq!(
+                Value::Integer(0),
+                run_on(&databases, client, &["DBSIZE"]).unwrap()
+            );
+        }
+        assert_eq!(
+            Value::Integer(-2),
+            run_on(&databases, &mut zero, &["TTL", "d"]).unwrap()
+        );
+
+        for words in [&["FLUSHDB", "LAZY"][..], &["FLUSHALL", "SYNC", "ASYNC"]] {
+            assert!(matches!(
+                run_on(&databases, &mut zero, words),
+                Err(CmdError::SyntaxError)
+            ));
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! # Set Commands
+//!
+//! Handlers for the commands of the [set](https://redis.io/docs/latest/commands/?group=set) group
+
+use crate::cmd::keyspace::{parse_cursor, scan_reply, ScanOptions};
+use crate::cmd::with_key;
+use crate::conn::Client;
+use crate::errors::CmdError;
+use crate::resp::Value;
+use crate::storage::generic::Crud;
+use crate::storage::object::Object;
+use crate::types::ConcurrentStorageType;
+use bytes::Bytes;
+
+/// Handler for the [SSCAN](https://redis.io/docs/latest/commands/sscan/) command
+///
+/// `SSCAN key cursor [MATCH pattern] [COUNT count]` => `[next cursor, array of members]`
+///
+/// Incrementally iterates over the members of the set stored at `key`, the same way [SCAN](super::keyspace)
+/// iterates over the keys.
+///
+/// Returns an [array](https://redis.io/docs/latest/develop/reference/protocol-spec/#arrays) of two elements:
+/// the next cursor as a bulk string, and an array of members.
+///
+/// Example:
+/// - `"*3\r\n$5\r\nSSCAN\r\n$3\r\nset\r\n$1\r\n0\r\n"` => `*2\r\n$1\r\n0\r\n*1\r\n$1\r\na\r\n`
+pub(crate) fn handle_sscan<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let cursor = parse_cursor(&args[2])?;
+    let options = ScanOptions::parse(&args[3..], false, false)?;
+
+    with_key(storage, &args[1], |value| {
+        let set = match value {
+            None => return Ok(scan_reply(0, vec![])),
+            Some(Object::Set(set)) => set,
+            Some(_) => return Err(CmdError::WrongType),
+        };
+        let (next, members) = set.scan(cursor, options.count);
+        let elements = members
+            .into_iter()
+            .filter(|member| options.matches(member))
+            .map(|member| Value::BulkString(member.clone()))
+            .collect();
+
+        Ok(scan_reply(next, elements))
+    })?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::tests::{run, storage};
+
+    #[test]
+    fn handle_sscan() {
+        storage().write().unwrap().create(
+            &Bytes::from("sscan"),
+            Object::Set((0..50).map(|i| Bytes::from(i.to_string())).collect()),
+            None,
+        );
+        let mut elements = vec![];
+        let mut cursor = "0".to_string();
+        loop {
+            let reply = run(&["SSCAN", "sscan", &cursor, "COUNT", "7"]).unwrap();
+            let Value::Array(reply) = reply else {
+                panic!("Expected an array");
+            };
+            let Ok([Value::BulkString(next), Value::Array(batch)]) = <[Value; 2]>::try_from(reply)
+            else {
+                panic!("Expected a cursor and an array");
+            };
+            elements.extend(batch);
+            cursor = String::from_utf8(next.to_vec()).unwrap();
+            if cursor == "0" {
+                break;
+            }
+        }
+        elements.sort_by_key(|member| match member {
+            Value::BulkString(member) => String::from_utf8(member.to_vec())
+                .unwrap()
+                .parse::<i32>()
+                .unwrap(),
+            member => panic!("Expected a bulk string, got {member:?}"),
+        });
+        let expected = (0..50)
+            .map(|i| Value::BulkString(Bytes::from(i.to_string())))
+            .collect::<Vec<_>>();
+        assert_eq!(expected, elements);
+        assert_eq!(
+            Value::Array(vec![
+                Value::BulkString(Bytes::from("0")),
+                Value::Array(vec![])
+            ]),
+            run(&["SSCAN", "sscan_missing", "0"]).unwrap()
+        );
+        run(&["SET", "sscan_string", "v"]).unwrap();
+        assert!(matches!(
+            run(&["SSCAN", "sscan_string", "0"]),
+            Err(CmdError::WrongType)
+        ));
+        assert!(matches!(
+            run(&["SSCAN", "sscan", "0", "TYPE", "string"]),
+            Err(CmdError::SyntaxError)
+        ));
+    }
+}
@@ -0,0 +1,127 @@
+//! # Sorted Set Commands
+//!
+//! Handlers for the commands of the [sorted set](https://redis.io/docs/latest/commands/?group=sorted-set) group
+
+use crate::cmd::keyspace::{parse_cursor, scan_reply, ScanOptions};
+use crate::cmd::with_key;
+use crate::conn::Client;
+use crate::errors::CmdError;
+use crate::resp::Value;
+use crate::storage::generic::Crud;
+use crate::storage::object::Object;
+use crate::types::ConcurrentStorageType;
+use bytes::Bytes;
+
+/// Handler for the [ZSCAN](https://redis.io/docs/latest/commands/zscan/) command
+///
+/// `ZSCAN key cursor [MATCH pattern] [COUNT count]` => `[next cursor, array of members and scores]`
+///
+/// Incrementally iterates over the members of the sorted set stored at `key`, the same way
+/// [SCAN](super::keyspace) iterates over the keys. `MATCH` is applied to the members.
+///
+/// Returns an [array](https://redis.io/docs/latest/develop/reference/protocol-spec/#arrays) of two elements:
+/// the next cursor as a bulk string, and a flat array of members and their scores, as bulk strings.
+///
+/// Example:
+/// - `"*3\r\n$5\r\nZSCAN\r\n$4\r\nzset\r\n$1\r\n0\r\n"` => `*2\r\n$1\r\n0\r\n*2\r\n$1\r\na\r\n$3\r\n1.5\r\n`
+pub(crate) fn handle_zscan<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let cursor = parse_cursor(&args[2])?;
+    let options = ScanOptions::parse(&args[3..], false, false)?;
+
+    with_key(storage, &args[1], |value| {
+        let zset = match value {
+            None => return Ok(scan_reply(0, vec![])),
+            Some(Object::SortedSet(zset)) => zset,
+            Some(_) => return Err(CmdError::WrongType),
+        };
+        let (next, members) = zset.scan(cursor, options.count);
+        let elements = members
+            .into_iter()
+            .filter(|(member, _)| options.matches(member))
+            .flat_map(|(member, score)| {
+                [
+                    Value::BulkString(member.clone()),
+                    Value::BulkString(Bytes::from(format_score(*score))),
+                ]
+            })
+            .collect();
+
+        Ok(scan_reply(next, elements))
+    })?
+}
+
+/// Formats a score with the shortest representation that parses back into the same number, as Redis does it.
+fn format_score(score: f64) -> String {
+    // Negative zero is printed as `0`, the same as Redis does it.
+    if score == 0.0 {
+        return "0".to_string();
+    }
+    score.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::tests::{run, storage};
+
+    #[test]
+    fn handle_zscan() {
+        storage().write().unwrap().create(
+            &Bytes::from("zscan"),
+            Object::SortedSet(
+                (0..50)
+                    .map(|i| (Bytes::from(format!("m{i}")), i as f64 + 0.5))
+                    .collect(),
+            ),
+            None,
+        );
+        let mut elements = vec![];
+        let mut cursor = "0".to_string();
+        loop {
+            let reply = run(&["ZSCAN", "zscan", &cursor, "COUNT", "7", "MATCH", "m4?"]).unwrap();
+            let Value::Array(reply) = reply else {
+                panic!("Expected an array");
+            };
+            let Ok([Value::BulkString(next), Value::Array(batch)]) = <[Value; 2]>::try_from(reply)
+            else {
+                panic!("Expected a cursor and an array");
+            };
+            elements.extend(batch);
+            cursor = String::from_utf8(next.to_vec()).unwrap();
+            if cursor == "0" {
+                break;
+            }
+        }
+        assert_eq!(20, elements.len());
+        for pair in elements.chunks(2) {
+            let [Value::BulkString(member), Value::BulkString(score)] = pair else {
+                panic!("Expected a member and a score");
+            };
+            assert!(member.starts_with(b"m4"));
+            assert_eq!(
+                format!("{}.5", String::from_utf8_lossy(&member[1..])).as_bytes(),
+                &score[..]
+            );
+        }
+        assert_eq!(
+            Value::Array(vec![
+                Value::BulkString(Bytes::from("0")),
+                Value::Array(vec![])
+            ]),
+            run(&["ZSCAN", "zscan_missing", "0"]).unwrap()
+        );
+        run(&["SET", "zscan_string", "v"]).unwrap();
+        assert!(matches!(
+            run(&["ZSCAN", "zscan_string", "0"]),
+            Err(CmdError::WrongType)
+        ));
+        assert!(matches!(
+            run(&["ZSCAN", "zscan", "0", "TYPE", "string"]),
+            Err(CmdError::SyntaxError)
+        ));
+    }
+}
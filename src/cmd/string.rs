@@ -0,0 +1,1647 @@
+//! # String Commands
+//!
+//! Handlers for the commands of the [string](https://redis.io/docs/latest/commands/?group=string) group
+
+use crate::cmd::{
+    get_live, get_string, parse_integer, read_live, read_lock, with_key, with_locked_key,
+    write_lock,
+};
+use crate::conn::Client;
+use crate::constants::PROTO_MAX_BULK_LEN;
+use crate::errors::CmdError;
+use crate::expiry::now_ms;
+use crate::resp::Value;
+use crate::storage::generic::Crud;
+use crate::storage::object::Object;
+use crate::types::{ConcurrentStorageType, ExpirationTime, ExpirationTimeType};
+use bytes::{Bytes, BytesMut};
+use std::ops::Range;
+
+/// Handler for the [GET](https://redis.io/docs/latest/commands/get/) command
+///
+/// Handles a single `GET` request.
+///
+/// `GET key` => `value`
+///
+/// Get the value of key. If the key does not exist the special value nil is returned.
+/// An error is returned if the value stored at key is not a string, because GET only handles string values.
+///
+/// If the key exists, returns the value of the key as a
+/// [bulk string](https://redis.io/docs/latest/develop/reference/protocol-spec/#bulk-strings).
+///
+/// If a key is passively expired, deletes it.
+///
+/// Reading a key updates its last access time and its LFU counter, unless the client has turned on
+/// `CLIENT NO-TOUCH`.
+///
+/// Examples:
+/// - `"*2\r\n$3\r\nGET\r\n$6\r\norange\r\n"` => `$9\r\npineapple\r\n` - returns value `pineapple` for existing key `orange`
+/// - `"*2\r\n$3\r\nGET\r\n$11\r\nnonexistent\r\n"` => `$-1\r\n` - returns `nil` value for nonexistent key `nonexistent`
+pub(crate) fn handle_get<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let value = with_locked_key(storage, &args[1], |s, value| {
+        value.map(|value| {
+            if !client.no_touch {
+                s.touch(&args[1]);
+            }
+            value.as_string().cloned()
+        })
+    })?;
+    match value {
+        None => Ok(Value::Null),
+        Some(value) => Ok(Value::BulkString(value.ok_or(CmdError::WrongType)?)),
+    }
+}
+
+/// Handler for the [SET](https://redis.io/docs/latest/commands/set/) command
+///
+/// Handles a single `SET` request.
+///
+/// `SET key value [NX | XX] [GET] [EX seconds | PX milliseconds | EXAT unix-time-seconds |
+/// PXAT unix-time-milliseconds | KEEPTTL]`
+///
+/// Sets `key` to hold the string `value`. If key already holds a value, it is overwritten, regardless of its type.
+/// Any previous time to live associated with the key is discarded on successful SET operation, unless `KEEPTTL`
+/// is given.
+///
+/// Options, which can be given in any order:
+/// - `EX seconds`, `PX milliseconds`: sets the time to live of the key
+/// - `EXAT unix-time-seconds`, `PXAT unix-time-milliseconds`: sets the absolute expiration time of the key;
+///   the key is deleted instead if the time is in the past
+/// - `NX`: only sets the key if it doesn't already exist
+/// - `XX`: only sets the key if it already exists
+/// - `KEEPTTL`: retains the time to live of the key
+/// - `GET`: returns the old value stored at key, or nil when key didn't exist
+///
+/// The whole operation is atomic, as it is performed under a single write lock.
+///
+/// Returns `OK` as a [simple string](https://redis.io/docs/latest/develop/reference/protocol-spec/#simple-strings)
+/// if the key was set, or nil if it wasn't, because of `NX` or `XX`. With `GET`, returns the old value
+/// as a [bulk string](https://redis.io/docs/latest/develop/reference/protocol-spec/#bulk-strings), or nil,
+/// whether the key was set or not.
+///
+/// Example:
+/// - `"*3\r\n$3\r\nSET\r\n$6\r\norange\r\n$9\r\npineapple\r\n"` => `+OK\r\n` - sets key `orange` to value `pineapple`
+/// - `"*5\r\n$3\r\nSET\r\n$6\r\nbanana\r\n$5\r\nmango\r\n$2\r\nEX\r\n$3\r\n10\r\n"` => `+OK\r\n` - sets key `banana`
+///   to value `mango` with expiry time of 10 s
+/// - `"*6\r\n$3\r\nSET\r\n$4\r\nlock\r\n$2\r\nme\r\n$2\r\nNX\r\n$2\r\nPX\r\n$5\r\n30000\r\n"` => `$-1\r\n`
+///   if key `lock` already exists
+pub(crate) fn handle_set<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let key = &args[1];
+    let value = args[2].clone();
+
+    let mut condition: Option<Condition> = None;
+    let mut get = false;
+    let mut keep_ttl = false;
+    let mut expire: Option<(&[u8], &[u8])> = None;
+    let mut i = 3;
+    while i < args.len() {
+        let option = args[i].to_ascii_uppercase();
+        match option.as_slice() {
+            b"NX" if condition != Some(Condition::Xx) => condition = Some(Condition::Nx),
+            b"XX" if condition != Some(Condition::Nx) => condition = Some(Condition::Xx),
+            b"GET" => get = true,
+            b"KEEPTTL" if expire.is_none() => keep_ttl = true,
+            b"EX" | b"PX" | b"EXAT" | b"PXAT"
+                if !keep_ttl
+                    && expire.is_none_or(|(unit, _)| unit.eq_ignore_ascii_case(&option))
+                    && i + 1 < args.len() =>
+            {
+                expire = Some((&args[i], &args[i + 1]));
+                i += 1;
+            }
+            _ => return Err(CmdError::SyntaxError),
+        }
+        i += 1;
+    }
+
+    let now = now_ms()?;
+    let deadline = match expire {
+        Some((unit, value)) => Some(deadline_ms(unit, value, "set", now)?),
+        None => None,
+    };
+
+    set_generic(
+        storage,
+        key,
+        value,
+        SetOptions {
+            condition,
+            get,
+            keep_ttl,
+            deadline,
+        },
+        now,
+    )
+}
+
+/// Sets `key` to the string `value` under a single write lock, which is the common part of `SET` and its variants.
+///
+/// Returns the reply of `SET`: `OK` or nil if the key wasn't set, or the old value with `GET`.
+fn set_generic<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    key: &Bytes,
+    value: Bytes,
+    options: SetOptions,
+    now: ExpirationTimeType,
+) -> Result<Value, CmdError> {
+    let SetOptions {
+        condition,
+        get,
+        keep_ttl,
+        deadline,
+    } = options;
+
+    let mut s = write_lock(storage);
+    let old = read_live(&*s, key, now);
+    // With `GET`, the old value has to be a string, and the key is left untouched if it isn't.
+    if get
+        && old
+            .as_ref()
+            .is_some_and(|(old, _)| old.as_string().is_none())
+    {
+        return Err(CmdError::WrongType);
+    }
+    let blocked = match condition {
+        Some(Condition::Nx) => old.is_some(),
+        Some(Condition::Xx) => old.is_none(),
+        None => false,
+    };
+    if !blocked {
+        match deadline {
+            Some(deadline) if deadline <= now => s.delete(key),
+            _ => {
+                let expiry = if keep_ttl {
+                    old.as_ref().and_then(|(_, expiry)| *expiry)
+                } else {
+                    deadline
+                };
+                s.create(key, Object::String(value), expiry);
+            }
+        }
+    }
+
+    Ok(if get {
+        old.and_then(|(old, _)| old.as_string().cloned())
+            .map_or(Value::Null, Value::BulkString)
+    } else if blocked {
+        Value::Null
+    } else {
+        Value::OK
+    })
+}
+
+/// The options of `SET`, after they have been parsed and validated
+#[derive(Clone, Copy, Debug, Default)]
+struct SetOptions {
+    condition: Option<Condition>,
+    /// Whether to return the old value
+    get: bool,
+    /// Whether to retain the time to live of the key
+    keep_ttl: bool,
+    /// The absolute expiration time in milliseconds
+    deadline: ExpirationTime,
+}
+
+/// The condition under which `SET` writes the key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Condition {
+    /// Only if the key doesn't exist
+    Nx,
+    /// Only if the key exists
+    Xx,
+}
+
+/// Converts the value of an `EX`, `PX`, `EXAT` or `PXAT` option into an absolute expiration time in milliseconds.
+///
+/// The value must be a positive integer, and the resulting time must fit in 64 bits, otherwise
+/// an "invalid expire time" error is returned for the command `cmd`.
+fn deadline_ms(
+    unit: &[u8],
+    value: &[u8],
+    cmd: &str,
+    now: ExpirationTimeType,
+) -> Result<ExpirationTimeType, CmdError> {
+    let invalid = || CmdError::InvalidExpireTime(cmd.to_string());
+    let value = parse_integer(value)?;
+    if value <= 0 {
+        return Err(invalid());
+    }
+    let unit = unit.to_ascii_uppercase();
+    let (ms, relative) = match unit.as_slice() {
+        b"EX" => (value.checked_mul(1000).ok_or_else(invalid)?, true),
+        b"PX" => (value, true),
+        b"EXAT" => (value.checked_mul(1000).ok_or_else(invalid)?, false),
+        b"PXAT" => (value, false),
+        _ => unreachable!("Only expiration options are passed in"),
+    };
+    if relative {
+        let ms = ms as ExpirationTimeType;
+        if ms > i64::MAX as ExpirationTimeType - now {
+            return Err(invalid());
+        }
+        Ok(now + ms)
+    } else {
+        Ok(ms as ExpirationTimeType)
+    }
+}
+
+/// Handler for the [INCR](https://redis.io/docs/latest/commands/incr/) command
+///
+/// `INCR key` => `value`
+///
+/// Increments the number stored at `key` by one. If the key does not exist, it is set to 0 before performing
+/// the operation. An error is returned if the key contains a value of the wrong type or contains a string
+/// that can not be represented as a 64-bit signed integer. The time to live of the key is retained.
+///
+/// Returns the value of the key after the increment as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers).
+///
+/// Example:
+/// - `"*2\r\n$4\r\nINCR\r\n$7\r\ncounter\r\n"` => `:11\r\n` - if key `counter` held `10`
+pub(crate) fn handle_incr<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    incr_by(storage, &args[1], 1).map(Value::Integer)
+}
+
+/// Handler for the [DECR](https://redis.io/docs/latest/commands/decr/) command
+///
+/// `DECR key` => `value`
+///
+/// Decrements the number stored at `key` by one, the same way as [`INCR`](handle_incr) increments it.
+///
+/// Example:
+/// - `"*2\r\n$4\r\nDECR\r\n$7\r\ncounter\r\n"` => `:9\r\n` - if key `counter` held `10`
+pub(crate) fn handle_decr<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    incr_by(storage, &args[1], -1).map(Value::Integer)
+}
+
+/// Handler for the [INCRBY](https://redis.io/docs/latest/commands/incrby/) command
+///
+/// `INCRBY key increment` => `value`
+///
+/// Increments the number stored at `key` by `increment`, the same way as [`INCR`](handle_incr) increments it by one.
+///
+/// Example:
+/// - `"*3\r\n$6\r\nINCRBY\r\n$7\r\ncounter\r\n$1\r\n5\r\n"` => `:15\r\n` - if key `counter` held `10`
+pub(crate) fn handle_incrby<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let increment = parse_integer(&args[2])?;
+    incr_by(storage, &args[1], increment).map(Value::Integer)
+}
+
+/// Handler for the [DECRBY](https://redis.io/docs/latest/commands/decrby/) command
+///
+/// `DECRBY key decrement` => `value`
+///
+/// Decrements the number stored at `key` by `decrement`, the same way as [`INCR`](handle_incr) increments it by one.
+/// The decrement can't be the smallest 64-bit integer, as it can't be negated.
+///
+/// Example:
+/// - `"*3\r\n$6\r\nDECRBY\r\n$7\r\ncounter\r\n$1\r\n5\r\n"` => `:5\r\n` - if key `counter` held `10`
+pub(crate) fn handle_decrby<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let decrement = parse_integer(&args[2])?;
+    let increment = decrement.checked_neg().ok_or(CmdError::DecrementOverflow)?;
+    incr_by(storage, &args[1], increment).map(Value::Integer)
+}
+
+/// Handler for the [INCRBYFLOAT](https://redis.io/docs/latest/commands/incrbyfloat/) command
+///
+/// `INCRBYFLOAT key increment` => `value`
+///
+/// Increments the floating point number stored at `key` by `increment`, which can be negative.
+/// If the key does not exist, it is set to 0 before performing the operation. An error is returned if the key
+/// contains a value of the wrong type, or a string that can't be parsed as a floating point number,
+/// or if the result would be NaN or infinity. The time to live of the key is retained.
+///
+/// Both the value and the increment can use the exponential notation, but the result is always stored
+/// in the human-friendly fixed-point notation, without trailing zeros, such as `5000` for `5.0e3`.
+///
+/// Returns the value of the key after the increment as a
+/// [bulk string](https://redis.io/docs/latest/develop/reference/protocol-spec/#bulk-strings).
+///
+/// Example:
+/// - `"*3\r\n$11\r\nINCRBYFLOAT\r\n$3\r\nkey\r\n$3\r\n0.1\r\n"` => `$4\r\n10.6\r\n` - if key `key` held `10.50`
+pub(crate) fn handle_incrbyfloat<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let key = &args[1];
+    let increment = parse_float(&args[2])?;
+
+    let now = now_ms()?;
+    let mut s = write_lock(storage);
+    let (current, expiry) = match read_live(&*s, key, now) {
+        None => (0.0, None),
+        Some((value, expiry)) => (
+            parse_float(value.as_string().ok_or(CmdError::WrongType)?)?,
+            expiry,
+        ),
+    };
+    let result = current + increment;
+    if !result.is_finite() {
+        return Err(CmdError::NanOrInfinity);
+    }
+    let result = Bytes::from(format_float(result));
+    s.update(key, Object::String(result.clone()), expiry);
+
+    Ok(Value::BulkString(result))
+}
+
+/// Handler for the [SETNX](https://redis.io/docs/latest/commands/setnx/) command
+///
+/// `SETNX key value` => `0 | 1`
+///
+/// Sets `key` to hold string `value` if `key` does not exist, the same as `SET key value NX`.
+///
+/// Returns 1 as an [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers)
+/// if the key was set, or 0 if it wasn't.
+///
+/// Example:
+/// - `"*3\r\n$5\r\nSETNX\r\n$5\r\nmykey\r\n$5\r\nHello\r\n"` => `:1\r\n`
+pub(crate) fn handle_setnx<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let options = SetOptions {
+        condition: Some(Condition::Nx),
+        ..Default::default()
+    };
+    let reply = set_generic(storage, &args[1], args[2].clone(), options, now_ms()?)?;
+    Ok(Value::Integer((reply == Value::OK) as i64))
+}
+
+/// Handler for the [SETEX](https://redis.io/docs/latest/commands/setex/) command
+///
+/// `SETEX key seconds value` => `OK`
+///
+/// Sets `key` to hold the string `value` and sets `key` to timeout after a given number of seconds,
+/// the same as `SET key value EX seconds`.
+///
+/// Example:
+/// - `"*4\r\n$5\r\nSETEX\r\n$5\r\nmykey\r\n$2\r\n10\r\n$5\r\nHello\r\n"` => `+OK\r\n`
+pub(crate) fn handle_setex<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    set_with_expire(storage, args, b"EX", "setex")
+}
+
+/// Handler for the [PSETEX](https://redis.io/docs/latest/commands/psetex/) command
+///
+/// `PSETEX key milliseconds value` => `OK`
+///
+/// Works exactly like [`SETEX`](handle_setex) with the sole difference that the expire time
+/// is specified in milliseconds instead of seconds.
+///
+/// Example:
+/// - `"*4\r\n$6\r\nPSETEX\r\n$5\r\nmykey\r\n$4\r\n1000\r\n$5\r\nHello\r\n"` => `+OK\r\n`
+pub(crate) fn handle_psetex<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    set_with_expire(storage, args, b"PX", "psetex")
+}
+
+/// Sets a key with a relative expiration time in the given `unit`, for `SETEX` and `PSETEX`.
+///
+/// The arguments are `cmd key expire value`.
+fn set_with_expire<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    args: &[Bytes],
+    unit: &[u8],
+    cmd: &str,
+) -> Result<Value, CmdError> {
+    let now = now_ms()?;
+    let options = SetOptions {
+        deadline: Some(deadline_ms(unit, &args[2], cmd, now)?),
+        ..Default::default()
+    };
+    set_generic(storage, &args[1], args[3].clone(), options, now)
+}
+
+/// Handler for the [GETSET](https://redis.io/docs/latest/commands/getset/) command
+///
+/// `GETSET key value` => `old value`
+///
+/// Atomically sets `key` to `value` and returns the old value stored at `key`, the same as `SET key value GET`.
+/// Returns an error when `key` exists but does not hold a string value. Any previous time to live associated
+/// with the key is discarded on successful `SET` operation.
+///
+/// Returns the old value as a [bulk string](https://redis.io/docs/latest/develop/reference/protocol-spec/#bulk-strings),
+/// or nil if the key did not exist.
+///
+/// Example:
+/// - `"*3\r\n$6\r\nGETSET\r\n$5\r\nmykey\r\n$5\r\nWorld\r\n"` => `$5\r\nHello\r\n` - if key `mykey` held `Hello`
+pub(crate) fn handle_getset<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let options = SetOptions {
+        get: true,
+        ..Default::default()
+    };
+    set_generic(storage, &args[1], args[2].clone(), options, now_ms()?)
+}
+
+/// Handler for the [GETDEL](https://redis.io/docs/latest/commands/getdel/) command
+///
+/// `GETDEL key` => `value`
+///
+/// Gets the value of `key` and deletes the key. This command is similar to `GET`, except for the fact that
+/// it also deletes the key on success, if and only if the key's value type is a string.
+///
+/// Returns the value of the key as a
+/// [bulk string](https://redis.io/docs/latest/develop/reference/protocol-spec/#bulk-strings),
+/// or nil if the key did not exist.
+///
+/// Example:
+/// - `"*2\r\n$6\r\nGETDEL\r\n$5\r\nmykey\r\n"` => `$5\r\nHello\r\n` - if key `mykey` held `Hello`
+pub(crate) fn handle_getdel<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let key = &args[1];
+
+    let now = now_ms()?;
+    let mut s = write_lock(storage);
+    match read_live(&*s, key, now) {
+        None => Ok(Value::Null),
+        Some((value, _)) => {
+            let value = value.as_string().ok_or(CmdError::WrongType)?.clone();
+            s.delete(key);
+            Ok(Value::BulkString(value))
+        }
+    }
+}
+
+/// Handler for the [GETEX](https://redis.io/docs/latest/commands/getex/) command
+///
+/// `GETEX key [EX seconds | PX milliseconds | EXAT unix-time-seconds | PXAT unix-time-milliseconds | PERSIST]`
+///
+/// Gets the value of `key` and optionally sets its expiration. `GETEX` is similar to `GET`,
+/// but is a write command with additional options:
+/// - `EX seconds`, `PX milliseconds`: sets the time to live of the key
+/// - `EXAT unix-time-seconds`, `PXAT unix-time-milliseconds`: sets the absolute expiration time of the key;
+///   the key is deleted instead if the time is in the past
+/// - `PERSIST`: removes the time to live associated with the key
+///
+/// Returns the value of the key as a
+/// [bulk string](https://redis.io/docs/latest/develop/reference/protocol-spec/#bulk-strings),
+/// or nil if the key did not exist.
+///
+/// Example:
+/// - `"*4\r\n$5\r\nGETEX\r\n$5\r\nmykey\r\n$2\r\nEX\r\n$2\r\n60\r\n"` => `$5\r\nHello\r\n` - if key `mykey`
+///   held `Hello`, which now expires in 60 seconds
+pub(crate) fn handle_getex<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let key = &args[1];
+
+    let mut persist = false;
+    let mut expire: Option<(&[u8], &[u8])> = None;
+    let mut i = 2;
+    while i < args.len() {
+        let option = args[i].to_ascii_uppercase();
+        match option.as_slice() {
+            b"PERSIST" if expire.is_none() => persist = true,
+            b"EX" | b"PX" | b"EXAT" | b"PXAT"
+                if !persist && expire.is_none() && i + 1 < args.len() =>
+            {
+                expire = Some((&args[i], &args[i + 1]));
+                i += 1;
+            }
+            _ => return Err(CmdError::SyntaxError),
+        }
+        i += 1;
+    }
+
+    let now = now_ms()?;
+    let deadline = match expire {
+        Some((unit, value)) => Some(deadline_ms(unit, value, "getex", now)?),
+        None => None,
+    };
+
+    let mut s = write_lock(storage);
+    let Some((value, expiry)) = get_live(&*s, key, now) else {
+        return Ok(Value::Null);
+    };
+    let value = value.as_string().ok_or(CmdError::WrongType)?.clone();
+    match deadline {
+        Some(deadline) if deadline <= now => s.delete(key),
+        Some(deadline) => s.set_expiry(key, Some(deadline)),
+        None if persist && expiry.is_some() => s.set_expiry(key, None),
+        None => {}
+    }
+
+    Ok(Value::BulkString(value))
+}
+
+/// Handler for the [MGET](https://redis.io/docs/latest/commands/mget/) command
+///
+/// `MGET key [key ...]` => `values`
+///
+/// Returns the values of all specified keys. For every key that does not hold a string value or does not exist,
+/// the special value nil is returned. Because of this, the operation never fails.
+///
+/// All keys are read under a single read lock, so the values are a consistent snapshot of the storage.
+/// Expired keys are reported as nil, the same as with `GET`, and are left for the eviction loop to delete.
+///
+/// Returns an [array](https://redis.io/docs/latest/develop/reference/protocol-spec/#arrays) of values at the
+/// specified keys.
+///
+/// Example:
+/// - `"*3\r\n$4\r\nMGET\r\n$4\r\nkey1\r\n$11\r\nnonexistent\r\n"` => `*2\r\n$5\r\nHello\r\n$-1\r\n`
+pub(crate) fn handle_mget<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let now = now_ms()?;
+    let s = read_lock(storage);
+    let values = args[1..]
+        .iter()
+        .map(|key| {
+            read_live(&*s, key, now)
+                .and_then(|(value, _)| value.as_string().cloned())
+                .map_or(Value::Null, Value::BulkString)
+        })
+        .collect();
+
+    Ok(Value::Array(values))
+}
+
+/// Handler for the [MSET](https://redis.io/docs/latest/commands/mset/) command
+///
+/// `MSET key value [key value ...]` => `OK`
+///
+/// Sets the given keys to their respective values. `MSET` replaces existing values with new values,
+/// just as regular `SET`, and discards their time to live.
+///
+/// `MSET` is atomic, so all given keys are set at once. It is not possible for clients to see
+/// that some of the keys were updated while others are unchanged.
+///
+/// Example:
+/// - `"*5\r\n$4\r\nMSET\r\n$4\r\nkey1\r\n$5\r\nHello\r\n$4\r\nkey2\r\n$5\r\nWorld\r\n"` => `+OK\r\n`
+pub(crate) fn handle_mset<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let pairs = key_value_pairs(args)?;
+    let mut s = write_lock(storage);
+    for (key, value) in pairs {
+        s.create(key, Object::String(value.clone()), None);
+    }
+
+    Ok(Value::OK)
+}
+
+/// Handler for the [MSETNX](https://redis.io/docs/latest/commands/msetnx/) command
+///
+/// `MSETNX key value [key value ...]` => `0 | 1`
+///
+/// Sets the given keys to their respective values, the same as `MSET`, but only if none of the keys exist.
+/// `MSETNX` will not perform any operation at all even if just a single key already exists.
+///
+/// Returns 1 as an [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers)
+/// if all the keys were set, or 0 if no key was set, because at least one of them already existed.
+///
+/// Example:
+/// - `"*5\r\n$6\r\nMSETNX\r\n$4\r\nkey1\r\n$5\r\nHello\r\n$4\r\nkey2\r\n$5\r\nthere\r\n"` => `:1\r\n`
+pub(crate) fn handle_msetnx<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let pairs = key_value_pairs(args)?;
+    let now = now_ms()?;
+    let mut s = write_lock(storage);
+    if pairs
+        .clone()
+        .any(|(key, _)| read_live(&*s, key, now).is_some())
+    {
+        return Ok(Value::Integer(0));
+    }
+    for (key, value) in pairs {
+        s.create(key, Object::String(value.clone()), None);
+    }
+
+    Ok(Value::Integer(1))
+}
+
+/// Splits the arguments of `MSET` and `MSETNX` into key-value pairs.
+///
+/// Returns an error if a key doesn't have a value.
+fn key_value_pairs(
+    args: &[Bytes],
+) -> Result<impl Iterator<Item = (&Bytes, &Bytes)> + Clone, CmdError> {
+    if args.len().is_multiple_of(2) {
+        return Err(CmdError::WrongNumberOfArgs(
+            String::from_utf8_lossy(&args[0]).to_lowercase(),
+        ));
+    }
+    Ok(args[1..].chunks_exact(2).map(|pair| (&pair[0], &pair[1])))
+}
+
+/// Handler for the [APPEND](https://redis.io/docs/latest/commands/append/) command
+///
+/// `APPEND key value` => `length`
+///
+/// If `key` already exists and is a string, appends the `value` at the end of the string.
+/// If `key` does not exist it is created and set as an empty string, so `APPEND` will be similar to `SET`
+/// in this special case. The time to live of the key is retained.
+///
+/// Returns the length of the string after the append operation as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers).
+///
+/// Example:
+/// - `"*3\r\n$6\r\nAPPEND\r\n$5\r\nmykey\r\n$6\r\n World\r\n"` => `:11\r\n` - if key `mykey` held `Hello`
+pub(crate) fn handle_append<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let key = &args[1];
+
+    let now = now_ms()?;
+    let mut s = write_lock(storage);
+    let len = match get_live(&*s, key, now) {
+        None => {
+            s.update(key, Object::String(args[2].clone()), None);
+            args[2].len()
+        }
+        Some((old, _)) => {
+            let len = old.as_string().ok_or(CmdError::WrongType)?.len() + args[2].len();
+            check_string_length(len)?;
+            s.modify(key, |value| {
+                value.modify_string(|string| string.extend_from_slice(&args[2]))
+            });
+            len
+        }
+    };
+
+    Ok(Value::Integer(len as i64))
+}
+
+/// Handler for the [STRLEN](https://redis.io/docs/latest/commands/strlen/) command
+///
+/// `STRLEN key` => `length`
+///
+/// Returns the length of the string value stored at `key` as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers),
+/// or 0 when `key` does not exist. An error is returned when `key` holds a non-string value.
+///
+/// Example:
+/// - `"*2\r\n$6\r\nSTRLEN\r\n$5\r\nmykey\r\n"` => `:11\r\n` - if key `mykey` held `Hello world`
+pub(crate) fn handle_strlen<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let len = with_key(storage, &args[1], |value| match value {
+        None => Ok(0),
+        Some(value) => value.as_string().map(Bytes::len).ok_or(CmdError::WrongType),
+    })??;
+    Ok(Value::Integer(len as i64))
+}
+
+/// Handler for the [GETRANGE](https://redis.io/docs/latest/commands/getrange/) command
+///
+/// `GETRANGE key start end` => `substring`
+///
+/// Returns the substring of the string value stored at `key`, determined by the offsets `start` and `end`,
+/// both inclusive. Negative offsets provide an offset starting from the end of the string,
+/// so -1 means the last character, -2 the penultimate and so forth.
+///
+/// The function handles out of range requests by limiting the resulting range to the actual length of the string,
+/// and returns an empty string for a nonexistent key.
+///
+/// Example:
+/// - `"*4\r\n$8\r\nGETRANGE\r\n$5\r\nmykey\r\n$2\r\n-3\r\n$2\r\n-1\r\n"` => `$3\r\ning\r\n` - if key `mykey`
+///   held `This is a string`
+pub(crate) fn handle_getrange<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let start = parse_integer(&args[2])?;
+    let end = parse_integer(&args[3])?;
+    let value = get_string(storage, &args[1])?.unwrap_or_default();
+
+    let range = string_range(value.len(), start, end).unwrap_or(0..0);
+    Ok(Value::BulkString(value.slice(range)))
+}
+
+/// Handler for the [SETRANGE](https://redis.io/docs/latest/commands/setrange/) command
+///
+/// `SETRANGE key offset value` => `length`
+///
+/// Overwrites part of the string stored at `key`, starting at the specified `offset`, for the entire length
+/// of `value`. If the offset is larger than the current length of the string at `key`, the string is padded
+/// with zero-bytes to make `offset` fit. Nonexistent keys are considered as empty strings.
+/// The time to live of the key is retained.
+///
+/// The maximum offset is 2^29-1 (536870911), as Redis strings are limited to 512 megabytes.
+///
+/// Returns the length of the string after it was modified by the command as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers).
+///
+/// Example:
+/// - `"*4\r\n$8\r\nSETRANGE\r\n$4\r\nkey2\r\n$1\r\n6\r\n$5\r\nRedis\r\n"` => `:11\r\n` - if key `key2` didn't exist,
+///   it now holds `\x00\x00\x00\x00\x00\x00Redis`
+pub(crate) fn handle_setrange<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let key = &args[1];
+    let offset = parse_integer(&args[2])?;
+    if offset < 0 {
+        return Err(CmdError::OffsetOutOfRange);
+    }
+    let offset = offset as usize;
+    let patch = &args[3];
+
+    let now = now_ms()?;
+    let mut s = write_lock(storage);
+    let old_len = match get_live(&*s, key, now) {
+        None => None,
+        Some((old, _)) => Some(old.as_string().ok_or(CmdError::WrongType)?.len()),
+    };
+    // An empty value doesn't modify the string, nor does it create the key.
+    if patch.is_empty() {
+        return Ok(Value::Integer(old_len.unwrap_or(0) as i64));
+    }
+    let end = offset + patch.len();
+    check_string_length(end)?;
+
+    let len = match old_len {
+        None => {
+            let mut value = BytesMut::zeroed(offset);
+            value.extend_from_slice(patch);
+            s.update(key, Object::String(value.freeze()), None);
+            end
+        }
+        Some(old_len) => {
+            s.modify(key, |value| {
+                value.modify_string(|string| {
+                    if string.len() < end {
+                        string.resize(end, 0);
+                    }
+                    string[offset..end].copy_from_slice(patch);
+                })
+            });
+            old_len.max(end)
+        }
+    };
+
+    Ok(Value::Integer(len as i64))
+}
+
+/// Handler for the [LCS](https://redis.io/docs/latest/commands/lcs/) command
+///
+/// `LCS key1 key2 [LEN] [IDX] [MINMATCHLEN min-match-len] [WITHMATCHLEN]`
+///
+/// Implements the longest common subsequence algorithm on the strings stored at `key1` and `key2`.
+/// Nonexistent keys are considered as empty strings.
+///
+/// Options:
+/// - `LEN`: returns the length of the longest common subsequence instead of the subsequence itself
+/// - `IDX`: returns the ranges of the matching substrings in both strings, from the last to the first one,
+///   and the length of the longest common subsequence
+/// - `MINMATCHLEN min-match-len`: with `IDX`, only returns the matches of at least the given length
+/// - `WITHMATCHLEN`: with `IDX`, also returns the length of each match
+///
+/// Returns the longest common subsequence as a
+/// [bulk string](https://redis.io/docs/latest/develop/reference/protocol-spec/#bulk-strings), its length as an
+/// [integer](https://redis.io/docs/latest/develop/reference/protocol-spec/#integers), or, with `IDX`, a
+/// [map](https://redis.io/docs/latest/develop/reference/protocol-spec/#maps) with the `matches` and `len` keys.
+///
+/// Example:
+/// - `"*3\r\n$3\r\nLCS\r\n$4\r\nkey1\r\n$4\r\nkey2\r\n"` => `$6\r\nmytext\r\n` - if keys `key1` and `key2`
+///   held `ohmytext` and `mynewtext`
+pub(crate) fn handle_lcs<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    _client: &mut Client,
+    args: &[Bytes],
+) -> Result<Value, CmdError> {
+    let mut get_len = false;
+    let mut get_idx = false;
+    let mut with_match_len = false;
+    let mut min_match_len = 0;
+    let mut i = 3;
+    while i < args.len() {
+        let option = args[i].to_ascii_uppercase();
+        match option.as_slice() {
+            b"LEN" => get_len = true,
+            b"IDX" => get_idx = true,
+            b"WITHMATCHLEN" => with_match_len = true,
+            b"MINMATCHLEN" if i + 1 < args.len() => {
+                min_match_len = parse_integer(&args[i + 1])?.max(0) as usize;
+                i += 1;
+            }
+            _ => return Err(CmdError::SyntaxError),
+        }
+        i += 1;
+    }
+    if get_len && get_idx {
+        return Err(CmdError::LcsLenAndIdx);
+    }
+
+    let mut strings = [Bytes::new(), Bytes::new()];
+    for (string, key) in strings.iter_mut().zip(&args[1..3]) {
+        *string = with_key(storage, key, |value| match value {
+            None => Ok(Bytes::new()),
+            Some(value) => value.as_string().cloned().ok_or(CmdError::NotStringValues),
+        })??;
+    }
+    let [a, b] = strings;
+    if (a.len() + 1).saturating_mul(b.len() + 1) > PROTO_MAX_BULK_LEN / size_of::<u32>() {
+        return Err(CmdError::LcsTooLong);
+    }
+
+    // `lcs[i][j]` is the length of the LCS of the first `i` bytes of `a` and the first `j` bytes of `b`.
+    let width = b.len() + 1;
+    let mut lcs = vec![0u32; (a.len() + 1) * width];
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            lcs[i * width + j] = if a[i - 1] == b[j - 1] {
+                lcs[(i - 1) * width + j - 1] + 1
+            } else {
+                lcs[(i - 1) * width + j].max(lcs[i * width + j - 1])
+            };
+        }
+    }
+    let len = lcs[a.len() * width + b.len()] as usize;
+    if get_len {
+        return Ok(Value::Integer(len as i64));
+    }
+
+    // Walk back from the end of both strings, collecting the subsequence and the ranges of contiguous matches.
+    let mut result = vec![0u8; len];
+    let mut matches = vec![];
+    // The current range, as `(a_start, a_end, b_start, b_end)`
+    let mut range: Option<(usize, usize, usize, usize)> = None;
+    let (mut i, mut j, mut idx) = (a.len(), b.len(), len);
+    while i > 0 && j > 0 {
+        let mut emit = false;
+        if a[i - 1] == b[j - 1] {
+            result[idx - 1] = a[i - 1];
+            range = match range {
+                None => Some((i - 1, i - 1, j - 1, j - 1)),
+                // Extend the range backward, as it is contiguous.
+                Some((a_start, a_end, b_start, b_end)) if a_start == i && b_start == j => {
+                    Some((a_start - 1, a_end, b_start - 1, b_end))
+                }
+                Some(range) => {
+                    emit = true;
+                    Some(range)
+                }
+            };
+            // Emit the range if it reached the beginning of either string, as the loop is about to end.
+            if range.is_some_and(|(a_start, _, b_start, _)| a_start == 0 || b_start == 0) {
+                emit = true;
+            }
+            idx -= 1;
+            i -= 1;
+            j -= 1;
+        } else {
+            if lcs[(i - 1) * width + j] > lcs[i * width + j - 1] {
+                i -= 1;
+            } else {
+                j -= 1;
+            }
+            emit = range.is_some();
+        }
+
+        if emit {
+            if let Some((a_start, a_end, b_start, b_end)) = range.take() {
+                let match_len = a_end - a_start + 1;
+                if get_idx && match_len >= min_match_len {
+                    let mut entry = vec![
+                        Value::Array(vec![
+                            Value::Integer(a_start as i64),
+                            Value::Integer(a_end as i64),
+                        ]),
+                        Value::Array(vec![
+                            Value::Integer(b_start as i64),
+                            Value::Integer(b_end as i64),
+                        ]),
+                    ];
+                    if with_match_len {
+                        entry.push(Value::Integer(match_len as i64));
+                    }
+                    matches.push(Value::Array(entry));
+                }
+            }
+        }
+    }
+
+    Ok(if get_idx {
+        Value::Map(vec![
+            (
+                Value::BulkString(Bytes::from_static(b"matches")),
+                Value::Array(matches),
+            ),
+            (
+                Value::BulkString(Bytes::from_static(b"len")),
+                Value::Integer(len as i64),
+            ),
+        ])
+    } else {
+        Value::BulkString(Bytes::from(result))
+    })
+}
+
+/// Converts the inclusive `start` and `end` offsets of `GETRANGE`, which can be negative,
+/// into a range of a string of length `len`.
+///
+/// Returns `None` if the range is empty.
+pub(crate) fn string_range(len: usize, start: i64, end: i64) -> Option<Range<usize>> {
+    let len = len as i64;
+    if len == 0 || (start < 0 && end < 0 && start > end) {
+        return None;
+    }
+    let start = if start < 0 { len + start } else { start }.max(0);
+    let end = if end < 0 { len + end } else { end }.clamp(0, len - 1);
+    if start > end {
+        return None;
+    }
+    Some(start as usize..end as usize + 1)
+}
+
+/// Checks that a string of the given length doesn't exceed the maximum size of a Redis string, which is 512 MB.
+pub(crate) fn check_string_length(len: usize) -> Result<(), CmdError> {
+    if len > PROTO_MAX_BULK_LEN {
+        return Err(CmdError::StringTooLong);
+    }
+    Ok(())
+}
+
+/// Adds `increment` to the integer stored at `key` and returns the new value.
+///
+/// The whole operation is atomic, as it is performed under a single write lock.
+fn incr_by<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    key: &Bytes,
+    increment: i64,
+) -> Result<i64, CmdError> {
+    let now = now_ms()?;
+    let mut s = write_lock(storage);
+    let (current, expiry) = match read_live(&*s, key, now) {
+        None => (0, None),
+        Some((value, expiry)) => (
+            parse_integer(value.as_string().ok_or(CmdError::WrongType)?)?,
+            expiry,
+        ),
+    };
+    let result = current.checked_add(increment).ok_or(CmdError::Overflow)?;
+    s.update(key, Object::String(Bytes::from(result.to_string())), expiry);
+
+    Ok(result)
+}
+
+/// Parses a floating point number, the same way Redis does it.
+///
+/// The exponential notation is accepted, while surrounding spaces and NaN aren't.
+pub(crate) fn parse_float(arg: &[u8]) -> Result<f64, CmdError> {
+    std::str::from_utf8(arg)
+        .ok()
+        .and_then(|arg| arg.parse::<f64>().ok())
+        .filter(|value| !value.is_nan())
+        .ok_or(CmdError::NotAFloat)
+}
+
+/// Formats a floating point number the way Redis stores the result of `INCRBYFLOAT`.
+///
+/// Redis prints a long double with 17 decimal digits and then removes trailing zeros, which never uses
+/// the exponential notation. An `f64` only has 15 reliable significant digits, so the number is rounded
+/// to that many, and at most 17 decimal digits, before the trailing zeros are removed.
+/// This gives `0.3` for `0.1 + 0.2` and `3.3` for `1.1 + 2.2`, as Redis does, instead of exposing
+/// binary rounding errors.
+pub(crate) fn format_float(value: f64) -> String {
+    // Negative zero is printed as `0`, the same as Redis does it.
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    let magnitude = value.abs().log10().floor() as i32;
+    let decimals = (f64::DIGITS as i32 - 1 - magnitude).clamp(0, 17) as usize;
+    let mut formatted = format!("{value:.decimals$}");
+    if formatted.contains('.') {
+        let len = formatted.trim_end_matches('0').trim_end_matches('.').len();
+        formatted.truncate(len);
+    }
+    // Tiny negative numbers are rounded to `-0`.
+    if formatted == "-0" {
+        formatted.remove(0);
+    }
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::tests::{run, storage};
+
+    fn set(words: &[&str]) -> Result<Value, CmdError> {
+        let args = words
+            .iter()
+            .map(|word| Bytes::copy_from_slice(word.as_bytes()))
+            .collect::<Vec<_>>();
+        handle_set(storage(), &mut Client::new(), &args)
+    }
+
+    fn get(key: &str) -> Value {
+        let args = [Bytes::from("GET"), Bytes::copy_from_slice(key.as_bytes())];
+        handle_get(storage(), &mut Client::new(), &args).unwrap()
+    }
+
+    fn bulk(value: &str) -> Value {
+        Value::BulkString(Bytes::copy_from_slice(value.as_bytes()))
+    }
+
+    fn expiry(key: &str) -> Option<ExpirationTimeType> {
+        let s = storage().read().unwrap();
+        s.read(&Bytes::copy_from_slice(key.as_bytes()))
+            .and_then(|(_, expiry)| expiry)
+    }
+
+    #[test]
+    fn handle_set_nx_xx() {
+        assert_eq!(Value::Null, set(&["SET", "set_nx_xx", "a", "XX"]).unwrap());
+        assert_eq!(Value::Null, get("set_nx_xx"));
+        assert_eq!(Value::OK, set(&["SET", "set_nx_xx", "b", "nx"]).unwrap());
+        assert_eq!(Value::Null, set(&["SET", "set_nx_xx", "c", "NX"]).unwrap());
+        assert_eq!(bulk("b"), get("set_nx_xx"));
+        assert_eq!(Value::OK, set(&["SET", "set_nx_xx", "d", "XX"]).unwrap());
+        assert_eq!(bulk("d"), get("set_nx_xx"));
+    }
+
+    #[test]
+    fn handle_set_get() {
+        assert_eq!(Value::Null, set(&["SET", "set_get", "a", "GET"]).unwrap());
+        assert_eq!(bulk("a"), set(&["SET", "set_get", "b", "GET"]).unwrap());
+        assert_eq!(
+            bulk("b"),
+            set(&["SET", "set_get", "c", "NX", "GET"]).unwrap()
+        );
+        assert_eq!(bulk("b"), get("set_get"));
+    }
+
+    #[test]
+    fn handle_set_keepttl() {
+        set(&["SET", "set_keepttl", "a", "PX", "100000"]).unwrap();
+        let ttl = expiry("set_keepttl");
+        assert!(ttl.is_some());
+
+        set(&["SET", "set_keepttl", "b", "KEEPTTL"]).unwrap();
+        assert_eq!(ttl, expiry("set_keepttl"));
+        assert_eq!(bulk("b"), get("set_keepttl"));
+
+        set(&["SET", "set_keepttl", "c"]).unwrap();
+        assert_eq!(None, expiry("set_keepttl"));
+    }
+
+    #[test]
+    fn handle_set_exat_pxat() {
+        let now = now_ms().unwrap();
+        let at = (now / 1000 + 100).to_string();
+        set(&["SET", "set_exat", "a", "EXAT", &at]).unwrap();
+        assert_eq!(
+            Some(at.parse::<ExpirationTimeType>().unwrap() * 1000),
+            expiry("set_exat")
+        );
+
+        let at = (now + 100_000).to_string();
+        set(&["SET", "set_exat", "a", "pxat", &at]).unwrap();
+        assert_eq!(Some(now + 100_000), expiry("set_exat"));
+
+        // A deadline in the past deletes the key.
+        assert_eq!(
+            Value::OK,
+            set(&["SET", "set_exat", "b", "PXAT", "1"]).unwrap()
+        );
+        assert_eq!(Value::Null, get("set_exat"));
+    }
+
+    #[test]
+    fn handle_set_syntax_errors() {
+        for words in [
+            &["SET", "set_syntax", "a", "NX", "XX"][..],
+            &["SET", "set_syntax", "a", "EX", "10", "PX", "100"],
+            &["SET", "set_syntax", "a", "EX", "10", "KEEPTTL"],
+            &["SET", "set_syntax", "a", "KEEPTTL", "PXAT", "100"],
+            &["SET", "set_syntax", "a", "EX"],
+            &["SET", "set_syntax", "a", "FOO"],
+        ] {
+            assert!(
+                matches!(set(words), Err(CmdError::SyntaxError)),
+                "{words:?}"
+            );
+        }
+        assert_eq!(Value::Null, get("set_syntax"));
+    }
+
+    #[test]
+    fn handle_set_invalid_expire_time() {
+        for words in [
+            &["SET", "set_invalid_expire", "a", "EX", "0"][..],
+            &["SET", "set_invalid_expire", "a", "PX", "-5"],
+            &[
+                "SET",
+                "set_invalid_expire",
+                "a",
+                "EX",
+                "9223372036854775807",
+            ],
+        ] {
+            assert!(
+                matches!(set(words), Err(CmdError::InvalidExpireTime(cmd)) if cmd == "set"),
+                "{words:?}"
+            );
+        }
+        assert!(matches!(
+            set(&["SET", "set_invalid_expire", "a", "EX", "ten"]),
+            Err(CmdError::NotAnInteger)
+        ));
+    }
+
+    #[test]
+    fn handle_get_set_wrong_type() {
+        let key = Bytes::from("string_wrong_type");
+        storage().write().unwrap().create(
+            &key,
+            Object::Set([Bytes::from("a")].into_iter().collect()),
+            None,
+        );
+        let args = [Bytes::from("GET"), key.clone()];
+        let result = handle_get(storage(), &mut Client::new(), &args);
+        assert!(matches!(result, Err(CmdError::WrongType)));
+
+        let result = set(&["SET", "string_wrong_type", "a", "GET"]);
+        assert!(matches!(result, Err(CmdError::WrongType)));
+        assert_eq!(
+            "set",
+            storage().read().unwrap().read(&key).unwrap().0.type_name()
+        );
+
+        assert_eq!(Value::OK, set(&["SET", "string_wrong_type", "a"]).unwrap());
+        assert_eq!(bulk("a"), get("string_wrong_type"));
+    }
+
+    #[test]
+    fn handle_incr_decr() {
+        assert_eq!(Value::Integer(1), run(&["INCR", "incr_decr"]).unwrap());
+        assert_eq!(Value::Integer(2), run(&["INCR", "incr_decr"]).unwrap());
+        assert_eq!(
+            Value::Integer(12),
+            run(&["INCRBY", "incr_decr", "10"]).unwrap()
+        );
+        assert_eq!(
+            Value::Integer(-8),
+            run(&["DECRBY", "incr_decr", "20"]).unwrap()
+        );
+        assert_eq!(Value::Integer(-9), run(&["DECR", "incr_decr"]).unwrap());
+        assert_eq!(bulk("-9"), get("incr_decr"));
+    }
+
+    #[test]
+    fn handle_incr_keeps_ttl() {
+        set(&["SET", "incr_ttl", "5", "PX", "100000"]).unwrap();
+        let ttl = expiry("incr_ttl");
+        assert_eq!(Value::Integer(6), run(&["INCR", "incr_ttl"]).unwrap());
+        assert_eq!(ttl, expiry("incr_ttl"));
+    }
+
+    #[test]
+    fn handle_incr_errors() {
+        set(&["SET", "incr_errors", "abc"]).unwrap();
+        assert!(matches!(
+            run(&["INCR", "incr_errors"]),
+            Err(CmdError::NotAnInteger)
+        ));
+        set(&["SET", "incr_errors", " 1"]).unwrap();
+        assert!(matches!(
+            run(&["INCR", "incr_errors"]),
+            Err(CmdError::NotAnInteger)
+        ));
+        set(&["SET", "incr_errors", "9223372036854775807"]).unwrap();
+        assert!(matches!(
+            run(&["INCR", "incr_errors"]),
+            Err(CmdError::Overflow)
+        ));
+        assert!(matches!(
+            run(&["INCRBY", "incr_errors", "1.5"]),
+            Err(CmdError::NotAnInteger)
+        ));
+        assert!(matches!(
+            run(&["DECRBY", "incr_errors", "-9223372036854775808"]),
+            Err(CmdError::DecrementOverflow)
+        ));
+        assert_eq!(bulk("9223372036854775807"), get("incr_errors"));
+
+        let key = Bytes::from("incr_wrong_type");
+        storage()
+            .write()
+            .unwrap()
+            .create(&key, Object::List(Default::default()), None);
+        assert!(matches!(
+            run(&["INCR", "incr_wrong_type"]),
+            Err(CmdError::WrongType)
+        ));
+    }
+
+    #[test]
+    fn handle_incrbyfloat() {
+        set(&["SET", "incrbyfloat", "10.50"]).unwrap();
+        assert_eq!(
+            bulk("10.6"),
+            run(&["INCRBYFLOAT", "incrbyfloat", "0.1"]).unwrap()
+        );
+        assert_eq!(
+            bulk("5.6"),
+            run(&["INCRBYFLOAT", "incrbyfloat", "-5"]).unwrap()
+        );
+        set(&["SET", "incrbyfloat", "5.0e3"]).unwrap();
+        assert_eq!(
+            bulk("5200"),
+            run(&["INCRBYFLOAT", "incrbyfloat", "2.0e2"]).unwrap()
+        );
+        assert_eq!(
+            bulk("3"),
+            run(&["INCRBYFLOAT", "incrbyfloat_new", "3.0"]).unwrap()
+        );
+
+        // Binary rounding errors are hidden, as Redis does it.
+        set(&["SET", "incrbyfloat_rounding", "0.1"]).unwrap();
+        run(&["INCRBYFLOAT", "incrbyfloat_rounding", "0.1"]).unwrap();
+        assert_eq!(
+            bulk("0.3"),
+            run(&["INCRBYFLOAT", "incrbyfloat_rounding", "0.1"]).unwrap()
+        );
+        assert_eq!(bulk("0.3"), get("incrbyfloat_rounding"));
+        set(&["SET", "incrbyfloat_rounding", "1.1"]).unwrap();
+        assert_eq!(
+            bulk("3.3"),
+            run(&["INCRBYFLOAT", "incrbyfloat_rounding", "2.2"]).unwrap()
+        );
+
+        for (value, increment) in [("abc", "1"), ("1", "abc"), ("1", " 1"), ("1", "nan")] {
+            set(&["SET", "incrbyfloat_errors", value]).unwrap();
+            assert!(
+                matches!(
+                    run(&["INCRBYFLOAT", "incrbyfloat_errors", increment]),
+                    Err(CmdError::NotAFloat)
+                ),
+                "{value} {increment}"
+            );
+        }
+        assert!(matches!(
+            run(&["INCRBYFLOAT", "incrbyfloat_errors", "inf"]),
+            Err(CmdError::NanOrInfinity)
+        ));
+        assert_eq!(bulk("1"), get("incrbyfloat_errors"));
+    }
+
+    #[test]
+    fn format_float_like_redis() {
+        assert_eq!("1.5", format_float(1.5));
+        assert_eq!("-2", format_float(-2.0));
+        assert_eq!("0", format_float(-0.0));
+        assert_eq!("0", format_float(-1e-20));
+        assert_eq!("0.00000000000000001", format_float(1e-17));
+        assert_eq!("100000000000000000000", format_float(1e20));
+        assert_eq!("0.3", format_float(0.1 + 0.2));
+    }
+
+    #[test]
+    fn handle_incr_concurrent() {
+        let threads = (0..8)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    for _ in 0..100 {
+                        run(&["INCR", "incr_concurrent"]).unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(bulk("800"), get("incr_concurrent"));
+    }
+
+    #[test]
+    fn handle_append_strlen() {
+        assert_eq!(Value::Integer(0), run(&["STRLEN", "append"]).unwrap());
+        assert_eq!(
+            Value::Integer(5),
+            run(&["APPEND", "append", "Hello"]).unwrap()
+        );
+        assert_eq!(
+            Value::Integer(11),
+            run(&["APPEND", "append", " World"]).unwrap()
+        );
+        assert_eq!(bulk("Hello World"), get("append"));
+        assert_eq!(Value::Integer(11), run(&["STRLEN", "append"]).unwrap());
+
+        set(&["SET", "append_ttl", "a", "PX", "100000"]).unwrap();
+        let ttl = expiry("append_ttl");
+        run(&["APPEND", "append_ttl", "b"]).unwrap();
+        assert_eq!(ttl, expiry("append_ttl"));
+    }
+
+    #[test]
+    fn handle_append_in_place() {
+        let key = Bytes::from("append_in_place");
+        let ptr = || {
+            let s = read_lock(storage());
+            s.get(&key).unwrap().0.as_string().unwrap().as_ptr()
+        };
+        set(&["SET", "append_in_place", "a"]).unwrap();
+        // Copying the string on every call would move it every time, while growing its buffer only moves it
+        // whenever the capacity doubles.
+        let mut moves = 0;
+        for _ in 0..100 {
+            let before = ptr();
+            run(&["APPEND", "append_in_place", "b"]).unwrap();
+            moves += usize::from(before != ptr());
+        }
+        assert!(moves < 20, "{moves}");
+        assert_eq!(
+            Value::Integer(101),
+            run(&["STRLEN", "append_in_place"]).unwrap()
+        );
+    }
+
+    #[test]
+    fn handle_getrange() {
+        set(&["SET", "getrange", "This is a string"]).unwrap();
+        for (start, end, expected) in [
+            ("0", "3", "This"),
+            ("-3", "-1", "ing"),
+            ("0", "-1", "This is a string"),
+            ("10", "100", "string"),
+            ("-100", "3", "This"),
+            ("5", "3", ""),
+            ("-1", "-5", ""),
+            ("100", "200", ""),
+        ] {
+            assert_eq!(
+                bulk(expected),
+                run(&["GETRANGE", "getrange", start, end]).unwrap(),
+                "{start} {end}"
+            );
+        }
+        assert_eq!(
+            bulk(""),
+            run(&["GETRANGE", "getrange_missing", "0", "-1"]).unwrap()
+        );
+        assert!(matches!(
+            run(&["GETRANGE", "getrange", "a", "1"]),
+            Err(CmdError::NotAnInteger)
+        ));
+    }
+
+    #[test]
+    fn handle_setrange() {
+        set(&["SET", "setrange", "Hello World"]).unwrap();
+        assert_eq!(
+            Value::Integer(11),
+            run(&["SETRANGE", "setrange", "6", "Redis"]).unwrap()
+        );
+        assert_eq!(bulk("Hello Redis"), get("setrange"));
+
+        assert_eq!(
+            Value::Integer(11),
+            run(&["SETRANGE", "setrange_pad", "6", "Redis"]).unwrap()
+        );
+        assert_eq!(bulk("\0\0\0\0\0\0Redis"), get("setrange_pad"));
+
+        assert_eq!(
+            Value::Integer(0),
+            run(&["SETRANGE", "setrange_empty", "5", ""]).unwrap()
+        );
+        assert_eq!(Value::Null, get("setrange_empty"));
+
+        assert!(matches!(
+            run(&["SETRANGE", "setrange", "-1", "a"]),
+            Err(CmdError::OffsetOutOfRange)
+        ));
+        assert!(matches!(
+            run(&["SETRANGE", "setrange", "536870911", "ab"]),
+            Err(CmdError::StringTooLong)
+        ));
+    }
+
+    #[test]
+    fn handle_lcs() {
+        set(&["SET", "lcs1", "ohmytext"]).unwrap();
+        set(&["SET", "lcs2", "mynewtext"]).unwrap();
+        assert_eq!(bulk("mytext"), run(&["LCS", "lcs1", "lcs2"]).unwrap());
+        assert_eq!(
+            Value::Integer(6),
+            run(&["LCS", "lcs1", "lcs2", "LEN"]).unwrap()
+        );
+        assert_eq!(bulk(""), run(&["LCS", "lcs1", "lcs_missing"]).unwrap());
+
+        let range = |start, end| Value::Array(vec![Value::Integer(start), Value::Integer(end)]);
+        let expected = Value::Map(vec![
+            (
+                bulk("matches"),
+                Value::Array(vec![
+                    Value::Array(vec![range(4, 7), range(5, 8), Value::Integer(4)]),
+                    Value::Array(vec![range(2, 3), range(0, 1), Value::Integer(2)]),
+                ]),
+            ),
+            (bulk("len"), Value::Integer(6)),
+        ]);
+        assert_eq!(
+            expected,
+            run(&["LCS", "lcs1", "lcs2", "IDX", "WITHMATCHLEN"]).unwrap()
+        );
+        let expected = Value::Map(vec![
+            (
+                bulk("matches"),
+                Value::Array(vec![Value::Array(vec![range(4, 7), range(5, 8)])]),
+            ),
+            (bulk("len"), Value::Integer(6)),
+        ]);
+        assert_eq!(
+            expected,
+            run(&["LCS", "lcs1", "lcs2", "IDX", "MINMATCHLEN", "4"]).unwrap()
+        );
+
+        assert!(matches!(
+            run(&["LCS", "lcs1", "lcs2", "LEN", "IDX"]),
+            Err(CmdError::LcsLenAndIdx)
+        ));
+        assert!(matches!(
+            run(&["LCS", "lcs1", "lcs2", "MINMATCHLEN"]),
+            Err(CmdError::SyntaxError)
+        ));
+    }
+
+    #[test]
+    fn handle_mset_mget() {
+        assert_eq!(
+            Value::OK,
+            run(&["MSET", "mset1", "a", "mset2", "b"]).unwrap()
+        );
+        storage().write().unwrap().create(
+            &Bytes::from("mset_expired"),
+            Object::String(Bytes::from("c")),
+            Some(1),
+        );
+        storage().write().unwrap().create(
+            &Bytes::from("mset_list"),
+            Object::List(Default::default()),
+            None,
+        );
+        assert_eq!(
+            Value::Array(vec![
+                bulk("a"),
+                Value::Null,
+                bulk("b"),
+                Value::Null,
+                Value::Null
+            ]),
+            run(&[
+                "MGET",
+                "mset1",
+                "mset_missing",
+                "mset2",
+                "mset_expired",
+                "mset_list"
+            ])
+            .unwrap()
+        );
+        assert!(matches!(
+            run(&["MSET", "mset1", "a", "mset2"]),
+            Err(CmdError::WrongNumberOfArgs(cmd)) if cmd == "mset"
+        ));
+    }
+
+    #[test]
+    fn handle_msetnx() {
+        set(&["SET", "msetnx1", "a", "PX", "100000"]).unwrap();
+        assert_eq!(
+            Value::Integer(0),
+            run(&["MSETNX", "msetnx2", "b", "msetnx1", "c"]).unwrap()
+        );
+        assert_eq!(Value::Null, get("msetnx2"));
+        assert_eq!(bulk("a"), get("msetnx1"));
+        assert_eq!(
+            Value::Integer(1),
+            run(&["MSETNX", "msetnx2", "b", "msetnx3", "c"]).unwrap()
+        );
+        assert_eq!(bulk("b"), get("msetnx2"));
+        assert_eq!(bulk("c"), get("msetnx3"));
+    }
+
+    #[test]
+    fn handle_setnx_setex_psetex() {
+        assert_eq!(Value::Integer(1), run(&["SETNX", "setnx", "a"]).unwrap());
+        assert_eq!(Value::Integer(0), run(&["SETNX", "setnx", "b"]).unwrap());
+        assert_eq!(bulk("a"), get("setnx"));
+
+        let now = now_ms().unwrap();
+        assert_eq!(Value::OK, run(&["SETEX", "setex", "100", "a"]).unwrap());
+        assert!(expiry("setex").is_some_and(|expiry| expiry >= now + 100_000));
+        assert_eq!(
+            Value::OK,
+            run(&["PSETEX", "psetex", "100000", "a"]).unwrap()
+        );
+        assert!(expiry("psetex").is_some_and(|expiry| expiry >= now + 100_000));
+
+        assert!(matches!(
+            run(&["SETEX", "setex", "0", "a"]),
+            Err(CmdError::InvalidExpireTime(cmd)) if cmd == "setex"
+        ));
+        assert!(matches!(
+            run(&["PSETEX", "psetex", "-1", "a"]),
+            Err(CmdError::InvalidExpireTime(cmd)) if cmd == "psetex"
+        ));
+    }
+
+    #[test]
+    fn handle_getset_getdel() {
+        set(&["SET", "getset", "a", "PX", "100000"]).unwrap();
+        assert_eq!(bulk("a"), run(&["GETSET", "getset", "b"]).unwrap());
+        assert_eq!(None, expiry("getset"));
+        assert_eq!(bulk("b"), run(&["GETDEL", "getset"]).unwrap());
+        assert_eq!(Value::Null, get("getset"));
+        assert_eq!(Value::Null, run(&["GETDEL", "getset"]).unwrap());
+        assert_eq!(Value::Null, run(&["GETSET", "getset", "c"]).unwrap());
+
+        storage().write().unwrap().create(
+            &Bytes::from("getdel_list"),
+            Object::List(Default::default()),
+            None,
+        );
+        assert!(matches!(
+            run(&["GETDEL", "getdel_list"]),
+            Err(CmdError::WrongType)
+        ));
+        assert!(matches!(
+            run(&["GETSET", "getdel_list", "a"]),
+            Err(CmdError::WrongType)
+        ));
+    }
+
+    #[test]
+    fn handle_getex() {
+        set(&["SET", "getex", "a"]).unwrap();
+        assert_eq!(bulk("a"), run(&["GETEX", "getex"]).unwrap());
+        assert_eq!(None, expiry("getex"));
+
+        let now = now_ms().unwrap();
+        assert_eq!(bulk("a"), run(&["GETEX", "getex", "EX", "100"]).unwrap());
+        assert!(expiry("getex").is_some_and(|expiry| expiry >= now + 100_000));
+        assert_eq!(bulk("a"), run(&["GETEX", "getex", "PERSIST"]).unwrap());
+        assert_eq!(None, expiry("getex"));
+
+        assert!(matches!(
+            run(&["GETEX", "getex", "EX", "10", "PERSIST"]),
+            Err(CmdError::SyntaxError)
+        ));
+        assert!(matches!(
+            run(&["GETEX", "getex", "PX", "0"]),
+            Err(CmdError::InvalidExpireTime(cmd)) if cmd == "getex"
+        ));
+
+        assert_eq!(bulk("a"), run(&["GETEX", "getex", "PXAT", "1"]).unwrap());
+        assert_eq!(Value::Null, get("getex"));
+        assert_eq!(Value::Null, run(&["GETEX", "getex", "PERSIST"]).unwrap());
+    }
+}
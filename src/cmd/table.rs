@@ -0,0 +1,1852 @@
+//! # Command Table
+//!
+//! The registry of all supported commands.
+//!
+//! Each command declares its name, arity, flags, ACL categories, key specifications, documentation and handler.
+//! Requests are routed to the handlers through the table, and the [COMMAND](https://redis.io/docs/latest/commands/command/)
+//! command and its subcommands are served from it.
+//!
+//! - [Command key specifications](https://redis.io/docs/latest/develop/reference/key-specs/)
+//! - [Redis command tips](https://redis.io/docs/latest/develop/reference/command-tips/)
+//! - [ACL categories](https://redis.io/docs/latest/operate/oss_and_stack/management/security/acl/#command-categories)
+
+use crate::cmd::{
+    bitmap, connection, hash, hyperloglog, keyspace, server, set, sorted_set, string,
+};
+use crate::conn::Client;
+use crate::errors::CmdError;
+use crate::resp::Value;
+use crate::storage::generic::Crud;
+use crate::types::ConcurrentStorageType;
+use bytes::Bytes;
+
+/// Command handler
+///
+/// Handlers come in two kinds, so that they can be stored in the command table. Most commands only work with
+/// the database that the client has selected, and get just that one. The few commands that work across
+/// databases, such as `SWAPDB`, get all of them, indexed by their number.
+///
+/// A handler is only called after the number of words in the command has been checked against the command's arity,
+/// so it can index into its mandatory arguments directly. The first word, `args[0]`, is the command's name.
+pub(crate) enum Handler<KV, KE> {
+    Db(DbHandler<KV, KE>),
+    AllDbs(AllDbsHandler<KV, KE>),
+}
+
+/// Handler of a command that works with the selected database
+pub(crate) type DbHandler<KV, KE> =
+    fn(&ConcurrentStorageType<KV, KE>, &mut Client, &[Bytes]) -> Result<Value, CmdError>;
+
+/// Handler of a command that works across databases
+pub(crate) type AllDbsHandler<KV, KE> =
+    fn(&[ConcurrentStorageType<KV, KE>], &mut Client, &[Bytes]) -> Result<Value, CmdError>;
+
+/// A single entry of the command table
+pub(crate) struct Command<KV, KE> {
+    /// Lowercase name of the command
+    pub(crate) name: &'static str,
+    /// Number of words in the command, including the command's name
+    ///
+    /// A negative arity `-N` means that the command takes at least `N` words.
+    pub(crate) arity: i64,
+    pub(crate) flags: &'static [Flag],
+    pub(crate) acl_categories: &'static [AclCategory],
+    /// Where the keys are in the command's arguments; empty for commands that don't take keys
+    pub(crate) key_specs: &'static [KeySpec],
+    /// Hints for clients, such as `nondeterministic_output`
+    pub(crate) tips: &'static [&'static str],
+    pub(crate) docs: Docs,
+    pub(crate) handler: Handler<KV, KE>,
+}
+
+/// Command flags
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Flag {
+    /// The command may modify the data set.
+    Write,
+    /// The command doesn't modify the data set.
+    ReadOnly,
+    /// The command may increase memory usage, so it's denied when the server is out of memory.
+    DenyOom,
+    /// The command isn't allowed in scripts.
+    NoScript,
+    /// The command is allowed while the database is loading.
+    Loading,
+    /// The command is allowed while a replica has stale data.
+    Stale,
+    /// The command runs in constant or logarithmic time.
+    Fast,
+    /// The command doesn't require authentication.
+    NoAuth,
+    /// The command is an administrative command.
+    Admin,
+}
+
+impl Flag {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Write => "write",
+            Self::ReadOnly => "readonly",
+            Self::DenyOom => "denyoom",
+            Self::NoScript => "noscript",
+            Self::Loading => "loading",
+            Self::Stale => "stale",
+            Self::Fast => "fast",
+            Self::NoAuth => "no_auth",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+/// ACL categories
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum AclCategory {
+    Keyspace,
+    Bitmap,
+    Read,
+    Write,
+    String,
+    Fast,
+    Slow,
+    Connection,
+    Hyperloglog,
+    Hash,
+    Set,
+    SortedSet,
+    Admin,
+    Dangerous,
+}
+
+impl AclCategory {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Keyspace => "keyspace",
+            Self::Bitmap => "bitmap",
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::String => "string",
+            Self::Fast => "fast",
+            Self::Slow => "slow",
+            Self::Connection => "connection",
+            Self::Hyperloglog => "hyperloglog",
+            Self::Hash => "hash",
+            Self::Set => "set",
+            Self::SortedSet => "sortedset",
+            Self::Admin => "admin",
+            Self::Dangerous => "dangerous",
+        }
+    }
+}
+
+/// Key specification, which tells where a command's keys are in its arguments
+///
+/// Only the `index` begin-search type and the `range` find-keys type are supported, as they cover all our commands.
+pub(crate) struct KeySpec {
+    /// Flags such as `RO`, `RW`, `OW`, `RM` and `ACCESS`, `UPDATE`, `INSERT`, `DELETE`
+    pub(crate) flags: &'static [&'static str],
+    /// Index of the first key
+    pub(crate) begin_search: usize,
+    /// Index of the last key, relative to the first key, or relative to the end of the arguments if negative
+    pub(crate) last_key: i64,
+    /// Number of words between two consecutive keys
+    pub(crate) key_step: usize,
+    /// Limits the number of keys to `1 / limit` of the remaining arguments when `last_key` is negative
+    pub(crate) limit: usize,
+}
+
+/// Command documentation, as returned by `COMMAND DOCS`
+pub(crate) struct Docs {
+    pub(crate) summary: &'static str,
+    /// The Redis version that added the command
+    pub(crate) since: &'static str,
+    pub(crate) group: &'static str,
+    pub(crate) complexity: &'static str,
+}
+
+/// Returns the command table.
+///
+/// Commands are ordered by name.
+pub(crate) fn commands<KV: Crud + 'static, KE: Crud + 'static>() -> &'static [Command<KV, KE>] {
+    const {
+        &[
+            Command {
+                name: "append",
+                arity: 3,
+                flags: &[Flag::Write, Flag::DenyOom, Flag::Fast],
+                acl_categories: &[AclCategory::Write, AclCategory::String, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "INSERT"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Appends a string to the value of a key. Creates the key if it doesn't exist.",
+                    since: "2.0.0",
+                    group: "string",
+                    complexity: "O(1). The amortized time complexity is O(1) assuming the appended value is small and the already present value is of any size, since the dynamic string library used by Redis will double the free space available on every reallocation.",
+                },
+                handler: Handler::Db(string::handle_append::<KV, KE>),
+            },
+            Command {
+                name: "bitcount",
+                arity: -2,
+                flags: &[Flag::ReadOnly],
+                acl_categories: &[AclCategory::Read, AclCategory::Bitmap, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Counts the number of set bits (population counting) in a string.",
+                    since: "2.6.0",
+                    group: "bitmap",
+                    complexity: "O(N)",
+                },
+                handler: Handler::Db(bitmap::handle_bitcount::<KV, KE>),
+            },
+            Command {
+                name: "bitfield",
+                arity: -2,
+                flags: &[Flag::Write, Flag::DenyOom],
+                acl_categories: &[AclCategory::Write, AclCategory::Bitmap, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "UPDATE", "ACCESS", "VARIABLE_FLAGS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Performs arbitrary bitfield integer operations on strings.",
+                    since: "3.2.0",
+                    group: "bitmap",
+                    complexity: "O(1) for each subcommand specified",
+                },
+                handler: Handler::Db(bitmap::handle_bitfield::<KV, KE>),
+            },
+            Command {
+                name: "bitfield_ro",
+                arity: -2,
+                flags: &[Flag::ReadOnly, Flag::Fast],
+                acl_categories: &[AclCategory::Read, AclCategory::Bitmap, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Performs arbitrary read-only bitfield integer operations on strings.",
+                    since: "6.0.0",
+                    group: "bitmap",
+                    complexity: "O(1) for each subcommand specified",
+                },
+                handler: Handler::Db(bitmap::handle_bitfield_ro::<KV, KE>),
+            },
+            Command {
+                name: "bitop",
+                arity: -4,
+                flags: &[Flag::Write, Flag::DenyOom],
+                acl_categories: &[AclCategory::Write, AclCategory::Bitmap, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["OW", "UPDATE"],
+                    begin_search: 2,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }, KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 3,
+                    last_key: -1,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Performs bitwise operations on multiple strings, and stores the result.",
+                    since: "2.6.0",
+                    group: "bitmap",
+                    complexity: "O(N)",
+                },
+                handler: Handler::Db(bitmap::handle_bitop::<KV, KE>),
+            },
+            Command {
+                name: "bitpos",
+                arity: -3,
+                flags: &[Flag::ReadOnly],
+                acl_categories: &[AclCategory::Read, AclCategory::Bitmap, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Finds the first set (1) or clear (0) bit in a string.",
+                    since: "2.8.7",
+                    group: "bitmap",
+                    complexity: "O(N)",
+                },
+                handler: Handler::Db(bitmap::handle_bitpos::<KV, KE>),
+            },
+            Command {
+                name: "client",
+                arity: -2,
+                flags: &[],
+                acl_categories: &[AclCategory::Slow],
+                key_specs: &[],
+                tips: &[],
+                docs: Docs {
+                    summary: "A container for client connection commands.",
+                    since: "2.4.0",
+                    group: "connection",
+                    complexity: "Depends on subcommand.",
+                },
+                handler: Handler::Db(connection::handle_client::<KV, KE>),
+            },
+            Command {
+                name: "command",
+                arity: -1,
+                flags: &[Flag::Loading, Flag::Stale],
+                acl_categories: &[AclCategory::Slow, AclCategory::Connection],
+                key_specs: &[],
+                tips: &["nondeterministic_output_order"],
+                docs: Docs {
+                    summary: "Returns detailed information about all commands.",
+                    since: "2.8.13",
+                    group: "server",
+                    complexity: "O(N) where N is the total number of Redis commands",
+                },
+                handler: Handler::Db(server::handle_command::<KV, KE>),
+            },
+            Command {
+                name: "copy",
+                arity: -3,
+                flags: &[Flag::Write, Flag::DenyOom],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Write, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }, KeySpec {
+                    flags: &["OW", "UPDATE"],
+                    begin_search: 2,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Copies the value of a key to a new key.",
+                    since: "6.2.0",
+                    group: "generic",
+                    complexity: "O(N) worst case for collections, where N is the number of nested items. O(1) for string values.",
+                },
+                handler: Handler::AllDbs(keyspace::handle_copy::<KV, KE>),
+            },
+            Command {
+                name: "dbsize",
+                arity: 1,
+                flags: &[Flag::ReadOnly, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Read, AclCategory::Fast],
+                key_specs: &[],
+                tips: &["request_policy:all_shards", "response_policy:agg_sum"],
+                docs: Docs {
+                    summary: "Returns the number of keys in the database.",
+                    since: "1.0.0",
+                    group: "server",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(server::handle_dbsize::<KV, KE>),
+            },
+            Command {
+                name: "decr",
+                arity: 2,
+                flags: &[Flag::Write, Flag::DenyOom, Flag::Fast],
+                acl_categories: &[AclCategory::Write, AclCategory::String, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "ACCESS", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Decrements the integer value of a key by one. Uses 0 as initial value if the key doesn't exist.",
+                    since: "1.0.0",
+                    group: "string",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(string::handle_decr::<KV, KE>),
+            },
+            Command {
+                name: "decrby",
+                arity: 3,
+                flags: &[Flag::Write, Flag::DenyOom, Flag::Fast],
+                acl_categories: &[AclCategory::Write, AclCategory::String, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "ACCESS", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Decrements a number from the integer value of a key. Uses 0 as initial value if the key doesn't exist.",
+                    since: "1.0.0",
+                    group: "string",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(string::handle_decrby::<KV, KE>),
+            },
+            Command {
+                name: "del",
+                arity: -2,
+                flags: &[Flag::Write],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Write, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RM", "DELETE"],
+                    begin_search: 1,
+                    last_key: -1,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &["request_policy:multi_shard", "response_policy:agg_sum"],
+                docs: Docs {
+                    summary: "Deletes one or more keys.",
+                    since: "1.0.0",
+                    group: "generic",
+                    complexity: "O(N) where N is the number of keys that will be removed. When a key to remove holds a value other than a string, the individual complexity for this key is O(M) where M is the number of elements in the list, set, sorted set or hash. Removing a single key that holds a string value is O(1).",
+                },
+                handler: Handler::Db(keyspace::handle_del::<KV, KE>),
+            },
+            Command {
+                name: "dump",
+                arity: 2,
+                flags: &[Flag::ReadOnly],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Read, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &["nondeterministic_output"],
+                docs: Docs {
+                    summary: "Returns a serialized representation of the value stored at a key.",
+                    since: "2.6.0",
+                    group: "generic",
+                    complexity: "O(1) to access the key and additional O(N*M) to serialize it, where N is the number of Redis objects composing the value and M their average size. For small string values the time complexity is thus O(1)+O(1*M) where M is small, so simply O(1).",
+                },
+                handler: Handler::Db(keyspace::handle_dump::<KV, KE>),
+            },
+            Command {
+                name: "echo",
+                arity: 2,
+                flags: &[Flag::Loading, Flag::Stale, Flag::Fast],
+                acl_categories: &[AclCategory::Fast, AclCategory::Connection],
+                key_specs: &[],
+                tips: &[],
+                docs: Docs {
+                    summary: "Returns the given string.",
+                    since: "1.0.0",
+                    group: "connection",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(connection::handle_echo::<KV, KE>),
+            },
+            Command {
+                name: "exists",
+                arity: -2,
+                flags: &[Flag::ReadOnly, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Read, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RO"],
+                    begin_search: 1,
+                    last_key: -1,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &["request_policy:multi_shard", "response_policy:agg_sum"],
+                docs: Docs {
+                    summary: "Determines whether one or more keys exist.",
+                    since: "1.0.0",
+                    group: "generic",
+                    complexity: "O(N) where N is the number of keys to check.",
+                },
+                handler: Handler::Db(keyspace::handle_exists::<KV, KE>),
+            },
+            Command {
+                name: "expire",
+                arity: -3,
+                flags: &[Flag::Write, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Write, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Sets the expiration time of a key in seconds.",
+                    since: "1.0.0",
+                    group: "generic",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(keyspace::handle_expire::<KV, KE>),
+            },
+            Command {
+                name: "expireat",
+                arity: -3,
+                flags: &[Flag::Write, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Write, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Sets the expiration time of a key to a Unix timestamp.",
+                    since: "1.2.0",
+                    group: "generic",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(keyspace::handle_expireat::<KV, KE>),
+            },
+            Command {
+                name: "expiretime",
+                arity: 2,
+                flags: &[Flag::ReadOnly, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Read, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Returns the expiration time of a key as a Unix timestamp.",
+                    since: "7.0.0",
+                    group: "generic",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(keyspace::handle_expiretime::<KV, KE>),
+            },
+            Command {
+                name: "flushall",
+                arity: -1,
+                flags: &[Flag::Write],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Write, AclCategory::Slow, AclCategory::Dangerous],
+                key_specs: &[],
+                tips: &["request_policy:all_shards", "response_policy:all_succeeded"],
+                docs: Docs {
+                    summary: "Removes all keys from all databases.",
+                    since: "1.0.0",
+                    group: "server",
+                    complexity: "O(N) where N is the total number of keys in all databases",
+                },
+                handler: Handler::AllDbs(server::handle_flushall::<KV, KE>),
+            },
+            Command {
+                name: "flushdb",
+                arity: -1,
+                flags: &[Flag::Write],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Write, AclCategory::Slow, AclCategory::Dangerous],
+                key_specs: &[],
+                tips: &["request_policy:all_shards", "response_policy:all_succeeded"],
+                docs: Docs {
+                    summary: "Remove all keys from the current database.",
+                    since: "1.0.0",
+                    group: "server",
+                    complexity: "O(N) where N is the number of keys in the selected database",
+                },
+                handler: Handler::Db(server::handle_flushdb::<KV, KE>),
+            },
+            Command {
+                name: "get",
+                arity: 2,
+                flags: &[Flag::ReadOnly, Flag::Fast],
+                acl_categories: &[AclCategory::Read, AclCategory::String, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Returns the string value of a key.",
+                    since: "1.0.0",
+                    group: "string",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(string::handle_get::<KV, KE>),
+            },
+            Command {
+                name: "getbit",
+                arity: 3,
+                flags: &[Flag::ReadOnly, Flag::Fast],
+                acl_categories: &[AclCategory::Read, AclCategory::Bitmap, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Returns a bit value by offset.",
+                    since: "2.2.0",
+                    group: "bitmap",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(bitmap::handle_getbit::<KV, KE>),
+            },
+            Command {
+                name: "getdel",
+                arity: 2,
+                flags: &[Flag::Write, Flag::Fast],
+                acl_categories: &[AclCategory::Write, AclCategory::String, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "ACCESS", "DELETE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Returns the string value of a key after deleting the key.",
+                    since: "6.2.0",
+                    group: "string",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(string::handle_getdel::<KV, KE>),
+            },
+            Command {
+                name: "getex",
+                arity: -2,
+                flags: &[Flag::Write, Flag::Fast],
+                acl_categories: &[AclCategory::Write, AclCategory::String, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "ACCESS", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Returns the string value of a key after setting its expiration time.",
+                    since: "6.2.0",
+                    group: "string",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(string::handle_getex::<KV, KE>),
+            },
+            Command {
+                name: "getrange",
+                arity: 4,
+                flags: &[Flag::ReadOnly],
+                acl_categories: &[AclCategory::Read, AclCategory::String, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Returns a substring of the string stored at a key.",
+                    since: "2.4.0",
+                    group: "string",
+                    complexity: "O(N) where N is the length of the returned string. The complexity is ultimately determined by the returned length, but because creating a substring from an existing string is very cheap, it can be considered O(1) for small strings.",
+                },
+                handler: Handler::Db(string::handle_getrange::<KV, KE>),
+            },
+            Command {
+                name: "getset",
+                arity: 3,
+                flags: &[Flag::Write, Flag::DenyOom, Flag::Fast],
+                acl_categories: &[AclCategory::Write, AclCategory::String, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "ACCESS", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Returns the previous string value of a key after setting it to a new value.",
+                    since: "1.0.0",
+                    group: "string",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(string::handle_getset::<KV, KE>),
+            },
+            Command {
+                name: "hello",
+                arity: -1,
+                flags: &[
+                    Flag::NoScript,
+                    Flag::Loading,
+                    Flag::Stale,
+                    Flag::Fast,
+                    Flag::NoAuth,
+                ],
+                acl_categories: &[AclCategory::Fast, AclCategory::Connection],
+                key_specs: &[],
+                tips: &[],
+                docs: Docs {
+                    summary: "Handshakes with the Redis server.",
+                    since: "6.0.0",
+                    group: "connection",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(connection::handle_hello::<KV, KE>),
+            },
+            Command {
+                name: "hscan",
+                arity: -3,
+                flags: &[Flag::ReadOnly],
+                acl_categories: &[AclCategory::Read, AclCategory::Hash, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &["nondeterministic_output"],
+                docs: Docs {
+                    summary: "Iterates over fields and values of a hash.",
+                    since: "2.8.0",
+                    group: "hash",
+                    complexity: "O(1) for every call. O(N) for a complete iteration, including enough command calls for the cursor to return back to 0. N is the number of elements inside the collection.",
+                },
+                handler: Handler::Db(hash::handle_hscan::<KV, KE>),
+            },
+            Command {
+                name: "incr",
+                arity: 2,
+                flags: &[Flag::Write, Flag::DenyOom, Flag::Fast],
+                acl_categories: &[AclCategory::Write, AclCategory::String, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "ACCESS", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Increments the integer value of a key by one. Uses 0 as initial value if the key doesn't exist.",
+                    since: "1.0.0",
+                    group: "string",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(string::handle_incr::<KV, KE>),
+            },
+            Command {
+                name: "incrby",
+                arity: 3,
+                flags: &[Flag::Write, Flag::DenyOom, Flag::Fast],
+                acl_categories: &[AclCategory::Write, AclCategory::String, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "ACCESS", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Increments the integer value of a key by a number. Uses 0 as initial value if the key doesn't exist.",
+                    since: "1.0.0",
+                    group: "string",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(string::handle_incrby::<KV, KE>),
+            },
+            Command {
+                name: "incrbyfloat",
+                arity: 3,
+                flags: &[Flag::Write, Flag::DenyOom, Flag::Fast],
+                acl_categories: &[AclCategory::Write, AclCategory::String, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "ACCESS", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Increment the floating point value of a key by a number. Uses 0 as initial value if the key doesn't exist.",
+                    since: "2.6.0",
+                    group: "string",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(string::handle_incrbyfloat::<KV, KE>),
+            },
+            Command {
+                name: "keys",
+                arity: 2,
+                flags: &[Flag::ReadOnly],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Read, AclCategory::Slow, AclCategory::Dangerous],
+                key_specs: &[],
+                tips: &["request_policy:all_shards", "nondeterministic_output_order"],
+                docs: Docs {
+                    summary: "Returns all key names that match a pattern.",
+                    since: "1.0.0",
+                    group: "generic",
+                    complexity: "O(N) with N being the number of keys in the database, under the assumption that the key names in the database and the given pattern have limited length.",
+                },
+                handler: Handler::Db(keyspace::handle_keys::<KV, KE>),
+            },
+            Command {
+                name: "lcs",
+                arity: -3,
+                flags: &[Flag::ReadOnly],
+                acl_categories: &[AclCategory::Read, AclCategory::String, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: 1,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Finds the longest common substring.",
+                    since: "7.0.0",
+                    group: "string",
+                    complexity: "O(N*M) where N and M are the lengths of s1 and s2, respectively",
+                },
+                handler: Handler::Db(string::handle_lcs::<KV, KE>),
+            },
+            Command {
+                name: "mget",
+                arity: -2,
+                flags: &[Flag::ReadOnly, Flag::Fast],
+                acl_categories: &[AclCategory::Read, AclCategory::String, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: -1,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &["request_policy:multi_shard"],
+                docs: Docs {
+                    summary: "Atomically returns the string values of one or more keys.",
+                    since: "1.0.0",
+                    group: "string",
+                    complexity: "O(N) where N is the number of keys to retrieve.",
+                },
+                handler: Handler::Db(string::handle_mget::<KV, KE>),
+            },
+            Command {
+                name: "move",
+                arity: 3,
+                flags: &[Flag::Write, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Write, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "ACCESS", "DELETE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Moves a key to another database.",
+                    since: "1.0.0",
+                    group: "generic",
+                    complexity: "O(1)",
+                },
+                handler: Handler::AllDbs(keyspace::handle_move::<KV, KE>),
+            },
+            Command {
+                name: "mset",
+                arity: -3,
+                flags: &[Flag::Write, Flag::DenyOom],
+                acl_categories: &[AclCategory::Write, AclCategory::String, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["OW", "UPDATE"],
+                    begin_search: 1,
+                    last_key: -1,
+                    key_step: 2,
+                    limit: 0,
+                }],
+                tips: &["request_policy:multi_shard", "response_policy:all_succeeded"],
+                docs: Docs {
+                    summary: "Atomically creates or modifies the string values of one or more keys.",
+                    since: "1.0.1",
+                    group: "string",
+                    complexity: "O(N) where N is the number of keys to set.",
+                },
+                handler: Handler::Db(string::handle_mset::<KV, KE>),
+            },
+            Command {
+                name: "msetnx",
+                arity: -3,
+                flags: &[Flag::Write, Flag::DenyOom],
+                acl_categories: &[AclCategory::Write, AclCategory::String, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["OW", "INSERT"],
+                    begin_search: 1,
+                    last_key: -1,
+                    key_step: 2,
+                    limit: 0,
+                }],
+                tips: &["request_policy:multi_shard", "response_policy:agg_min"],
+                docs: Docs {
+                    summary: "Atomically modifies the string values of one or more keys only when all keys don't exist.",
+                    since: "1.0.1",
+                    group: "string",
+                    complexity: "O(N) where N is the number of keys to set.",
+                },
+                handler: Handler::Db(string::handle_msetnx::<KV, KE>),
+            },
+            Command {
+                name: "object",
+                arity: -2,
+                flags: &[],
+                acl_categories: &[AclCategory::Slow],
+                key_specs: &[],
+                tips: &[],
+                docs: Docs {
+                    summary: "A container for object introspection commands.",
+                    since: "2.2.3",
+                    group: "generic",
+                    complexity: "Depends on subcommand.",
+                },
+                handler: Handler::Db(keyspace::handle_object::<KV, KE>),
+            },
+            Command {
+                name: "persist",
+                arity: 2,
+                flags: &[Flag::Write, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Write, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Removes the expiration time of a key.",
+                    since: "2.2.0",
+                    group: "generic",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(keyspace::handle_persist::<KV, KE>),
+            },
+            Command {
+                name: "pexpire",
+                arity: -3,
+                flags: &[Flag::Write, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Write, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Sets the expiration time of a key in milliseconds.",
+                    since: "2.6.0",
+                    group: "generic",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(keyspace::handle_pexpire::<KV, KE>),
+            },
+            Command {
+                name: "pexpireat",
+                arity: -3,
+                flags: &[Flag::Write, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Write, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Sets the expiration time of a key to a Unix milliseconds timestamp.",
+                    since: "2.6.0",
+                    group: "generic",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(keyspace::handle_pexpireat::<KV, KE>),
+            },
+            Command {
+                name: "pexpiretime",
+                arity: 2,
+                flags: &[Flag::ReadOnly, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Read, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Returns the expiration time of a key as a Unix milliseconds timestamp.",
+                    since: "7.0.0",
+                    group: "generic",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(keyspace::handle_pexpiretime::<KV, KE>),
+            },
+            Command {
+                name: "pfadd",
+                arity: -2,
+                flags: &[Flag::Write, Flag::DenyOom, Flag::Fast],
+                acl_categories: &[AclCategory::Write, AclCategory::Hyperloglog, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "INSERT"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Adds elements to a HyperLogLog key. Creates the key if it doesn't exist.",
+                    since: "2.8.9",
+                    group: "hyperloglog",
+                    complexity: "O(1) to add every element.",
+                },
+                handler: Handler::Db(hyperloglog::handle_pfadd::<KV, KE>),
+            },
+            Command {
+                name: "pfcount",
+                arity: -2,
+                flags: &[Flag::ReadOnly],
+                acl_categories: &[AclCategory::Read, AclCategory::Hyperloglog, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "ACCESS"],
+                    begin_search: 1,
+                    last_key: -1,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Returns the approximated cardinality of the set(s) observed by the HyperLogLog key(s).",
+                    since: "2.8.9",
+                    group: "hyperloglog",
+                    complexity: "O(1) with a very small average constant time when called with a single key. O(N) with N being the number of keys, and much bigger constant times, when called with multiple keys.",
+                },
+                handler: Handler::Db(hyperloglog::handle_pfcount::<KV, KE>),
+            },
+            Command {
+                name: "pfdebug",
+                arity: 3,
+                flags: &[Flag::Write, Flag::DenyOom, Flag::Admin],
+                acl_categories: &[AclCategory::Write, AclCategory::Hyperloglog, AclCategory::Admin, AclCategory::Slow, AclCategory::Dangerous],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "ACCESS"],
+                    begin_search: 2,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Internal commands for debugging HyperLogLog values.",
+                    since: "2.8.9",
+                    group: "hyperloglog",
+                    complexity: "N/A",
+                },
+                handler: Handler::Db(hyperloglog::handle_pfdebug::<KV, KE>),
+            },
+            Command {
+                name: "pfmerge",
+                arity: -2,
+                flags: &[Flag::Write, Flag::DenyOom],
+                acl_categories: &[AclCategory::Write, AclCategory::Hyperloglog, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "ACCESS", "INSERT"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }, KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 2,
+                    last_key: -1,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Merges one or more HyperLogLog values into a single key.",
+                    since: "2.8.9",
+                    group: "hyperloglog",
+                    complexity: "O(N) to merge N HyperLogLogs, but with high constant times.",
+                },
+                handler: Handler::Db(hyperloglog::handle_pfmerge::<KV, KE>),
+            },
+            Command {
+                name: "pfselftest",
+                arity: 1,
+                flags: &[Flag::Admin],
+                acl_categories: &[AclCategory::Hyperloglog, AclCategory::Admin, AclCategory::Slow, AclCategory::Dangerous],
+                key_specs: &[],
+                tips: &[],
+                docs: Docs {
+                    summary: "An internal command for testing HyperLogLog values.",
+                    since: "2.8.9",
+                    group: "hyperloglog",
+                    complexity: "N/A",
+                },
+                handler: Handler::Db(hyperloglog::handle_pfselftest::<KV, KE>),
+            },
+            Command {
+                name: "ping",
+                arity: -1,
+                flags: &[Flag::Fast],
+                acl_categories: &[AclCategory::Fast, AclCategory::Connection],
+                key_specs: &[],
+                tips: &["request_policy:all_shards", "response_policy:all_succeeded"],
+                docs: Docs {
+                    summary: "Returns the server's liveliness response.",
+                    since: "1.0.0",
+                    group: "connection",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(connection::handle_ping::<KV, KE>),
+            },
+            Command {
+                name: "psetex",
+                arity: 4,
+                flags: &[Flag::Write, Flag::DenyOom],
+                acl_categories: &[AclCategory::Write, AclCategory::String, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["OW", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Sets both string value and expiration time in milliseconds of a key. The key is created if it doesn't exist.",
+                    since: "2.6.0",
+                    group: "string",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(string::handle_psetex::<KV, KE>),
+            },
+            Command {
+                name: "pttl",
+                arity: 2,
+                flags: &[Flag::ReadOnly, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Read, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &["nondeterministic_output"],
+                docs: Docs {
+                    summary: "Returns the expiration time in milliseconds of a key.",
+                    since: "2.6.0",
+                    group: "generic",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(keyspace::handle_pttl::<KV, KE>),
+            },
+            Command {
+                name: "randomkey",
+                arity: 1,
+                flags: &[Flag::ReadOnly],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Read, AclCategory::Slow],
+                key_specs: &[],
+                tips: &["request_policy:all_shards", "response_policy:special", "nondeterministic_output"],
+                docs: Docs {
+                    summary: "Returns a random key name from the database.",
+                    since: "1.0.0",
+                    group: "generic",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(keyspace::handle_randomkey::<KV, KE>),
+            },
+            Command {
+                name: "rename",
+                arity: 3,
+                flags: &[Flag::Write],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Write, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "ACCESS", "DELETE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }, KeySpec {
+                    flags: &["OW", "UPDATE"],
+                    begin_search: 2,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Renames a key and overwrites the destination.",
+                    since: "1.0.0",
+                    group: "generic",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(keyspace::handle_rename::<KV, KE>),
+            },
+            Command {
+                name: "renamenx",
+                arity: 3,
+                flags: &[Flag::Write, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Write, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "ACCESS", "DELETE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }, KeySpec {
+                    flags: &["OW", "INSERT"],
+                    begin_search: 2,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Renames a key only when the target key name doesn't exist.",
+                    since: "1.0.0",
+                    group: "generic",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(keyspace::handle_renamenx::<KV, KE>),
+            },
+            Command {
+                name: "restore",
+                arity: -4,
+                flags: &[Flag::Write, Flag::DenyOom],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Write, AclCategory::Slow, AclCategory::Dangerous],
+                key_specs: &[KeySpec {
+                    flags: &["OW", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Creates a key from the serialized representation of a value.",
+                    since: "2.6.0",
+                    group: "generic",
+                    complexity: "O(1) to create the new key and additional O(N*M) to reconstruct the serialized value, where N is the number of Redis objects composing the value and M their average size. For small string values the time complexity is thus O(1)+O(1*M) where M is small, so simply O(1). However for sorted set values the complexity is O(N*M*log(N)) because inserting values into sorted sets is O(log(N)).",
+                },
+                handler: Handler::Db(keyspace::handle_restore::<KV, KE>),
+            },
+            Command {
+                name: "scan",
+                arity: -2,
+                flags: &[Flag::ReadOnly],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Read, AclCategory::Slow],
+                key_specs: &[],
+                tips: &["nondeterministic_output", "request_policy:special", "response_policy:special"],
+                docs: Docs {
+                    summary: "Iterates over the key names in the database.",
+                    since: "2.8.0",
+                    group: "generic",
+                    complexity: "O(1) for every call. O(N) for a complete iteration, including enough command calls for the cursor to return back to 0. N is the number of elements inside the collection.",
+                },
+                handler: Handler::Db(keyspace::handle_scan::<KV, KE>),
+            },
+            Command {
+                name: "select",
+                arity: 2,
+                flags: &[Flag::Loading, Flag::Stale, Flag::Fast],
+                acl_categories: &[AclCategory::Fast, AclCategory::Connection],
+                key_specs: &[],
+                tips: &[],
+                docs: Docs {
+                    summary: "Changes the selected database.",
+                    since: "1.0.0",
+                    group: "connection",
+                    complexity: "O(1)",
+                },
+                handler: Handler::AllDbs(connection::handle_select::<KV, KE>),
+            },
+            Command {
+                name: "set",
+                arity: -3,
+                flags: &[Flag::Write, Flag::DenyOom],
+                acl_categories: &[AclCategory::Write, AclCategory::String, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "ACCESS", "UPDATE", "VARIABLE_FLAGS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Sets the string value of a key, ignoring its type. The key is created if it doesn't exist.",
+                    since: "1.0.0",
+                    group: "string",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(string::handle_set::<KV, KE>),
+            },
+            Command {
+                name: "setbit",
+                arity: 4,
+                flags: &[Flag::Write, Flag::DenyOom],
+                acl_categories: &[AclCategory::Write, AclCategory::Bitmap, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "ACCESS", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Sets or clears the bit at offset of the string value. Creates the key if it doesn't exist.",
+                    since: "2.2.0",
+                    group: "bitmap",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(bitmap::handle_setbit::<KV, KE>),
+            },
+            Command {
+                name: "setex",
+                arity: 4,
+                flags: &[Flag::Write, Flag::DenyOom],
+                acl_categories: &[AclCategory::Write, AclCategory::String, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["OW", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Sets the string value and expiration time of a key. Creates the key if it doesn't exist.",
+                    since: "2.0.0",
+                    group: "string",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(string::handle_setex::<KV, KE>),
+            },
+            Command {
+                name: "setnx",
+                arity: 3,
+                flags: &[Flag::Write, Flag::DenyOom, Flag::Fast],
+                acl_categories: &[AclCategory::Write, AclCategory::String, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["OW", "INSERT"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Set the string value of a key only when the key doesn't exist.",
+                    since: "1.0.0",
+                    group: "string",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(string::handle_setnx::<KV, KE>),
+            },
+            Command {
+                name: "setrange",
+                arity: 4,
+                flags: &[Flag::Write, Flag::DenyOom],
+                acl_categories: &[AclCategory::Write, AclCategory::String, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RW", "UPDATE"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Overwrites a part of a string value with another by an offset. Creates the key if it doesn't exist.",
+                    since: "2.2.0",
+                    group: "string",
+                    complexity: "O(1), not counting the time taken to copy the new string in place. Usually, this string is very small so the amortized complexity is O(1). Otherwise, complexity is O(M) with M being the length of the value argument.",
+                },
+                handler: Handler::Db(string::handle_setrange::<KV, KE>),
+            },
+            Command {
+                name: "sscan",
+                arity: -3,
+                flags: &[Flag::ReadOnly],
+                acl_categories: &[AclCategory::Read, AclCategory::Set, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &["nondeterministic_output"],
+                docs: Docs {
+                    summary: "Iterates over members of a set.",
+                    since: "2.8.0",
+                    group: "set",
+                    complexity: "O(1) for every call. O(N) for a complete iteration, including enough command calls for the cursor to return back to 0. N is the number of elements inside the collection.",
+                },
+                handler: Handler::Db(set::handle_sscan::<KV, KE>),
+            },
+            Command {
+                name: "strlen",
+                arity: 2,
+                flags: &[Flag::ReadOnly, Flag::Fast],
+                acl_categories: &[AclCategory::Read, AclCategory::String, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RO"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Returns the length of a string value.",
+                    since: "2.2.0",
+                    group: "string",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(string::handle_strlen::<KV, KE>),
+            },
+            Command {
+                name: "swapdb",
+                arity: 3,
+                flags: &[Flag::Write, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Write, AclCategory::Fast, AclCategory::Dangerous],
+                key_specs: &[],
+                tips: &[],
+                docs: Docs {
+                    summary: "Swaps two Redis databases.",
+                    since: "4.0.0",
+                    group: "server",
+                    complexity: "O(N) where N is the count of clients watching or blocking on keys from both databases.",
+                },
+                handler: Handler::AllDbs(server::handle_swapdb::<KV, KE>),
+            },
+            Command {
+                name: "touch",
+                arity: -2,
+                flags: &[Flag::ReadOnly, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Read, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RO"],
+                    begin_search: 1,
+                    last_key: -1,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &["request_policy:multi_shard", "response_policy:agg_sum"],
+                docs: Docs {
+                    summary: "Returns the number of existing keys out of those specified after updating the time they were last accessed.",
+                    since: "3.2.1",
+                    group: "generic",
+                    complexity: "O(N) where N is the number of keys that will be touched.",
+                },
+                handler: Handler::Db(keyspace::handle_touch::<KV, KE>),
+            },
+            Command {
+                name: "ttl",
+                arity: 2,
+                flags: &[Flag::ReadOnly, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Read, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &["nondeterministic_output"],
+                docs: Docs {
+                    summary: "Returns the expiration time in seconds of a key.",
+                    since: "1.0.0",
+                    group: "generic",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(keyspace::handle_ttl::<KV, KE>),
+            },
+            Command {
+                name: "type",
+                arity: 2,
+                flags: &[Flag::ReadOnly, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Read, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RO"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &[],
+                docs: Docs {
+                    summary: "Determines the type of value stored at a key.",
+                    since: "1.0.0",
+                    group: "generic",
+                    complexity: "O(1)",
+                },
+                handler: Handler::Db(keyspace::handle_type::<KV, KE>),
+            },
+            Command {
+                name: "unlink",
+                arity: -2,
+                flags: &[Flag::Write, Flag::Fast],
+                acl_categories: &[AclCategory::Keyspace, AclCategory::Write, AclCategory::Fast],
+                key_specs: &[KeySpec {
+                    flags: &["RM", "DELETE"],
+                    begin_search: 1,
+                    last_key: -1,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &["request_policy:multi_shard", "response_policy:agg_sum"],
+                docs: Docs {
+                    summary: "Asynchronously deletes one or more keys.",
+                    since: "4.0.0",
+                    group: "generic",
+                    complexity: "O(1) for each key removed regardless of its size. Then the command does O(N) work in a different thread in order to reclaim memory, where N is the number of allocations the deleted objects where composed of.",
+                },
+                handler: Handler::Db(keyspace::handle_unlink::<KV, KE>),
+            },
+            Command {
+                name: "zscan",
+                arity: -3,
+                flags: &[Flag::ReadOnly],
+                acl_categories: &[AclCategory::Read, AclCategory::SortedSet, AclCategory::Slow],
+                key_specs: &[KeySpec {
+                    flags: &["RO", "ACCESS"],
+                    begin_search: 1,
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0,
+                }],
+                tips: &["nondeterministic_output"],
+                docs: Docs {
+                    summary: "Iterates over members and scores of a sorted set.",
+                    since: "2.8.0",
+                    group: "sorted-set",
+                    complexity: "O(1) for every call. O(N) for a complete iteration, including enough command calls for the cursor to return back to 0. N is the number of elements inside the collection.",
+                },
+                handler: Handler::Db(sorted_set::handle_zscan::<KV, KE>),
+            },
+        ]
+    }
+}
+
+/// Looks a command up by its case-insensitive name.
+///
+/// The table is sorted by lowercase name, so this is a binary search.
+pub(crate) fn lookup<KV: Crud + 'static, KE: Crud + 'static>(
+    name: &[u8],
+) -> Option<&'static Command<KV, KE>> {
+    let commands = commands::<KV, KE>();
+    let lowercase = name.iter().map(u8::to_ascii_lowercase);
+    commands
+        .binary_search_by(|cmd| cmd.name.bytes().cmp(lowercase.clone()))
+        .ok()
+        .map(|index| &commands[index])
+}
+
+impl<KV, KE> Command<KV, KE> {
+    /// Checks the number of words in a command, including its name, against the command's arity.
+    pub(crate) fn check_arity(&self, num_words: usize) -> bool {
+        let num_words = num_words as i64;
+        if self.arity >= 0 {
+            num_words == self.arity
+        } else {
+            num_words >= -self.arity
+        }
+    }
+
+    /// Extracts the keys from a command's words, based on the command's key specifications.
+    ///
+    /// Returns `None` if the words don't match the key specifications.
+    pub(crate) fn keys(&self, args: &[Bytes]) -> Option<Vec<Bytes>> {
+        let argc = args.len() as i64;
+        let mut keys = vec![];
+        for spec in self.key_specs {
+            let first = spec.begin_search as i64;
+            if first >= argc {
+                continue;
+            }
+            let last = if spec.last_key >= 0 {
+                first + spec.last_key
+            } else if spec.limit <= 1 {
+                argc + spec.last_key
+            } else {
+                first + ((argc - first) / spec.limit as i64 + spec.last_key)
+            };
+            if last >= argc || last < first {
+                return None;
+            }
+            keys.extend(
+                args[first as usize..=last as usize]
+                    .iter()
+                    .step_by(spec.key_step)
+                    .cloned(),
+            );
+        }
+        Some(keys)
+    }
+
+    /// The legacy `(first key, last key, step)` triplet, derived from the key specifications
+    ///
+    /// It is `(0, 0, 0)` for commands that don't take keys.
+    fn legacy_key_range(&self) -> (i64, i64, i64) {
+        let Some(spec) = self.key_specs.first() else {
+            return (0, 0, 0);
+        };
+        let first = self
+            .key_specs
+            .iter()
+            .map(|spec| spec.begin_search as i64)
+            .min()
+            .unwrap_or_default();
+        let last = if self.key_specs.iter().any(|spec| spec.last_key < 0) {
+            -1
+        } else {
+            self.key_specs
+                .iter()
+                .map(|spec| spec.begin_search as i64 + spec.last_key)
+                .max()
+                .unwrap_or_default()
+        };
+        (first, last, spec.key_step as i64)
+    }
+
+    /// The reply to `COMMAND INFO` for this command
+    pub(crate) fn info(&self) -> Value {
+        let (first_key, last_key, key_step) = self.legacy_key_range();
+        Value::Array(vec![
+            bulk(self.name),
+            Value::Integer(self.arity),
+            Value::Set(
+                self.flags
+                    .iter()
+                    .map(|flag| simple(flag.as_str()))
+                    .collect(),
+            ),
+            Value::Integer(first_key),
+            Value::Integer(last_key),
+            Value::Integer(key_step),
+            Value::Set(
+                self.acl_categories
+                    .iter()
+                    .map(|category| {
+                        Value::SimpleString(Bytes::from(format!("@{}", category.as_str())))
+                    })
+                    .collect(),
+            ),
+            Value::Set(self.tips.iter().map(|tip| bulk(tip)).collect()),
+            Value::Array(self.key_specs.iter().map(KeySpec::info).collect()),
+            Value::Array(vec![]),
+        ])
+    }
+
+    /// The reply to `COMMAND DOCS` for this command, without the command's name
+    pub(crate) fn docs(&self) -> Value {
+        Value::Map(vec![
+            (bulk("summary"), bulk(self.docs.summary)),
+            (bulk("since"), bulk(self.docs.since)),
+            (bulk("group"), bulk(self.docs.group)),
+            (bulk("complexity"), bulk(self.docs.complexity)),
+        ])
+    }
+
+    /// Whether the command belongs to the given ACL category, which is matched case-insensitively
+    pub(crate) fn has_acl_category(&self, category: &[u8]) -> bool {
+        self.acl_categories
+            .iter()
+            .any(|cat| category.eq_ignore_ascii_case(cat.as_str().as_bytes()))
+    }
+}
+
+impl KeySpec {
+    /// The key specification as a part of the reply to `COMMAND INFO`
+    fn info(&self) -> Value {
+        Value::Map(vec![
+            (
+                bulk("flags"),
+                Value::Set(self.flags.iter().map(|flag| simple(flag)).collect()),
+            ),
+            (
+                bulk("begin_search"),
+                Value::Map(vec![
+                    (bulk("type"), bulk("index")),
+                    (
+                        bulk("spec"),
+                        Value::Map(vec![(
+                            bulk("index"),
+                            Value::Integer(self.begin_search as i64),
+                        )]),
+                    ),
+                ]),
+            ),
+            (
+                bulk("find_keys"),
+                Value::Map(vec![
+                    (bulk("type"), bulk("range")),
+                    (
+                        bulk("spec"),
+                        Value::Map(vec![
+                            (bulk("lastkey"), Value::Integer(self.last_key)),
+                            (bulk("keystep"), Value::Integer(self.key_step as i64)),
+                            (bulk("limit"), Value::Integer(self.limit as i64)),
+                        ]),
+                    ),
+                ]),
+            ),
+        ])
+    }
+}
+
+fn bulk(s: &'static str) -> Value {
+    Value::BulkString(Bytes::from_static(s.as_bytes()))
+}
+
+fn simple(s: &'static str) -> Value {
+    Value::SimpleString(Bytes::from_static(s.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{InMemoryExpiryTimeHashMap, InMemoryStorageHashMap};
+
+    type Cmd = Command<InMemoryStorageHashMap, InMemoryExpiryTimeHashMap>;
+
+    fn lookup(name: &str) -> &'static Cmd {
+        super::lookup(name.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn commands_sorted_and_lowercase() {
+        let names = commands::<InMemoryStorageHashMap, InMemoryExpiryTimeHashMap>()
+            .iter()
+            .map(|cmd| cmd.name)
+            .collect::<Vec<_>>();
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(sorted, names);
+        assert!(names.iter().all(|name| name.to_lowercase() == *name));
+    }
+
+    #[test]
+    fn lookup_case_insensitive() {
+        assert_eq!("get", lookup("GeT").name);
+        for cmd in commands::<InMemoryStorageHashMap, InMemoryExpiryTimeHashMap>() {
+            assert_eq!(cmd.name, lookup(&cmd.name.to_uppercase()).name);
+        }
+        assert!(
+            super::lookup::<InMemoryStorageHashMap, InMemoryExpiryTimeHashMap>(b"nope").is_none()
+        );
+    }
+
+    #[test]
+    fn check_arity() {
+        let get = lookup("get");
+        assert!(!get.check_arity(1));
+        assert!(get.check_arity(2));
+        assert!(!get.check_arity(3));
+
+        let set = lookup("set");
+        assert!(!set.check_arity(2));
+        assert!(set.check_arity(3));
+        assert!(set.check_arity(7));
+    }
+
+    #[test]
+    fn keys() {
+        let args = ["SET", "k", "v", "EX", "10"].map(Bytes::from);
+        assert_eq!(Some(vec![Bytes::from("k")]), lookup("set").keys(&args));
+
+        let args = ["PING", "k"].map(Bytes::from);
+        assert_eq!(Some(vec![]), lookup("ping").keys(&args));
+    }
+
+    #[test]
+    fn info_get() {
+        let info = lookup("get").info();
+        let Value::Array(info) = info else {
+            panic!("Expected an array");
+        };
+        assert_eq!(10, info.len());
+        assert_eq!(Value::BulkString(Bytes::from("get")), info[0]);
+        assert_eq!(Value::Integer(2), info[1]);
+        assert_eq!(
+            Value::Set(vec![
+                Value::SimpleString(Bytes::from("readonly")),
+                Value::SimpleString(Bytes::from("fast"))
+            ]),
+            info[2]
+        );
+        assert_eq!(
+            [Value::Integer(1), Value::Integer(1), Value::Integer(1)],
+            info[3..6]
+        );
+        assert_eq!(
+            Value::Set(vec![
+                Value::SimpleString(Bytes::from("@read")),
+                Value::SimpleString(Bytes::from("@string")),
+                Value::SimpleString(Bytes::from("@fast"))
+            ]),
+            info[6]
+        );
+    }
+}
@@ -1,16 +1,49 @@
 //! # Connection Handler
 
-use crate::cmd::handle_request;
-use crate::errors::ConnectionError;
+use crate::cmd::{error_reply, handle_request};
+use crate::constants::BUFFER_LEN;
+use crate::errors::{CmdError, ConnectionError};
+use crate::resp::{FrameDecoder, Protocol};
 use crate::storage::generic::Crud;
-use crate::types::ConcurrentStorageType;
+use crate::types::ConcurrentDatabases;
 use crate::{debug_and_stderr, log_and_stderr};
 use anyhow::Result;
-use bytes::BytesMut;
-use log::warn;
+use bytes::{BufMut, Bytes, BytesMut};
+use log::{debug, warn};
+use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 
+/// Per-connection state of a client
+#[derive(Debug)]
+pub(crate) struct Client {
+    /// Unique ID of the connection
+    pub(crate) id: u64,
+    /// RESP protocol version, which can be switched with `HELLO`
+    pub(crate) protocol: Protocol,
+    /// Connection name, which can be set with `HELLO ... SETNAME`
+    pub(crate) name: Option<Bytes>,
+    /// Index of the database that commands work with, which can be switched with `SELECT`
+    pub(crate) db: usize,
+    /// Whether reading keys leaves their last access time and LFU counter alone, which can be switched
+    /// with `CLIENT NO-TOUCH`
+    pub(crate) no_touch: bool,
+}
+
+impl Client {
+    /// Creates the state of a new connection, with a new unique ID, in RESP2 mode, with database 0 selected
+    pub(crate) fn new() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            protocol: Protocol::default(),
+            name: None,
+            db: 0,
+            no_touch: false,
+        }
+    }
+}
+
 /// Handles multiple successive requests from the same connection.
 ///
 /// Since a single request is always an array, it can contain multiple commands. This is called
@@ -21,31 +54,62 @@ use tokio::net::TcpStream;
 /// The client can skip reading replies and continue to send the commands one after the other.
 /// All the replies can be read at the end.
 /// For more information, see [Pipelining](https://redis.io/docs/latest/develop/use/pipelining/).
-pub async fn handle_connection<KV: Crud, KE: Crud>(
-    storage: ConcurrentStorageType<KV, KE>,
+pub async fn handle_connection<KV: Crud + 'static, KE: Crud + 'static>(
+    databases: ConcurrentDatabases<KV, KE>,
     socket: &mut TcpStream,
 ) -> Result<(), ConnectionError> {
     let peer_addr = socket.peer_addr()?;
     log_and_stderr!(debug, "Start handling requests from", peer_addr);
 
+    // The buffer lives as long as the connection, because a request can be split across multiple reads,
+    // and a single read can contain multiple requests.
+    let mut decoder = FrameDecoder::with_capacity(BUFFER_LEN);
+    let mut client = Client::new();
+
     loop {
-        let mut buf = BytesMut::new();
-        let _n = match socket.read_buf(&mut buf).await {
+        let _n = match socket.read_buf(decoder.buffer_mut()).await {
             Ok(0) => break,
-            Ok(n) => {
-                assert!(0 < n && n <= buf.len());
-                n
-            }
+            Ok(n) => n,
             Err(err) => {
                 warn!("{}", err);
                 return Err(ConnectionError::from(err));
             }
         };
-        // [`cmd::handle_request`] will forward the buffer to [`resp::deserialize`] which **depends**
-        // on the byte stream **ending in CRLF**.
-        let response = handle_request(&storage, &buf.freeze()).await?;
-        socket.write_all(&response).await?;
-        socket.flush().await?;
+
+        // Handle all complete frames that have arrived so far, in order, and keep the rest for the next read.
+        // [`cmd::handle_request`] will forward each frame to [`resp::deserialize`] which **depends**
+        // on the byte stream **ending in CRLF**, and a complete frame always does.
+        //
+        // An error in a command is sent back to the client as an error reply, and the connection stays open.
+        // A protocol error is sent back as well, but then the connection is closed, the same as Redis does it,
+        // because the rest of the byte stream can't be trusted anymore.
+        let mut response = BytesMut::new();
+        let mut fatal = None;
+        loop {
+            let result = match decoder.decode() {
+                Ok(Some(frame)) => handle_request(&databases, &mut client, &frame).await,
+                Ok(None) => break,
+                Err(err) => Err(CmdError::from(err)),
+            };
+            match result {
+                Ok(reply) => response.put(reply),
+                Err(err) => {
+                    debug!("{peer_addr}: {err}");
+                    error_reply(&err).write_to(&mut response, client.protocol);
+                    if err.is_protocol_error() {
+                        fatal = Some(err);
+                        break;
+                    }
+                }
+            }
+        }
+        if !response.is_empty() {
+            socket.write_all(&response).await?;
+            socket.flush().await?;
+        }
+        if let Some(err) = fatal {
+            return Err(ConnectionError::from(err));
+        }
     }
 
     debug_and_stderr!("Stop handling requests from", peer_addr);
@@ -16,18 +16,45 @@ pub const LOCAL_SOCKET_ADDR_STR_TEST: &str = "127.0.0.1:0";
 
 /// Default maximum number of allowed concurrent connections from clients
 pub const DEFAULT_MAX_CONNECTIONS: usize = 100;
+/// Default number of logical databases, which clients switch between with `SELECT`
+pub const DEFAULT_DATABASES: usize = 16;
 /// Connection permit timeout in milliseconds
 pub const CONNECTION_PERMIT_TIMEOUT_MS: u64 = 5000;
 
-/// Supported Redis commands
-pub const COMMANDS: [&[u8]; 4] = [b"ECHO", b"GET", b"PING", b"SET"];
+/// Redis version that the server reports to clients
+pub const REDIS_VERSION: &str = "7.4.0";
 
 /// Time period in milliseconds for checking of expired keys
 pub const HZ_MS: ExpirationTimeType = 100;
 
+/// Number of keys with an expiration time that the eviction loop checks at once, the same as Redis's
+pub const ACTIVE_EXPIRE_CYCLE_KEYS_PER_LOOP: usize = 20;
+/// Percentage of expired keys among the checked ones, above which the eviction loop goes on to check more keys
+pub const ACTIVE_EXPIRE_CYCLE_ACCEPTABLE_STALE: usize = 10;
+/// Percentage of every [`HZ_MS`] period that the eviction loop may spend checking keys
+pub const ACTIVE_EXPIRE_CYCLE_SLOW_TIME_PERC: ExpirationTimeType = 25;
+
+/// Number of allocations above which `UNLINK` frees a value in the background
+pub const LAZYFREE_THRESHOLD: usize = 64;
+
 /// Length of buffer for handling connections, 512 bytes
 pub const BUFFER_LEN: usize = 512;
 
+/// Maximum length of a single bulk string in a request, 512 MB
+pub const PROTO_MAX_BULK_LEN: usize = 512 * 1024 * 1024;
+
+/// Maximum length of an inline command, 64 kB
+pub const PROTO_INLINE_MAX_SIZE: usize = 64 * 1024;
+
+/// Maximum number of arguments of a single request, the same as Redis's
+pub const PROTO_MAX_MULTIBULK_LEN: usize = i32::MAX as usize;
+
+/// Maximum size of a request that is still being received, `client-query-buffer-limit`, 1 GB
+pub const PROTO_MAX_QUERYBUF_LEN: usize = 1024 * 1024 * 1024;
+
+/// Maximum nesting depth of aggregate values, such as arrays of arrays, that are deserialized
+pub const PROTO_MAX_NESTING_DEPTH: usize = 64;
+
 /// Time to wait during a graceful shutdown in milliseconds
 pub const SHUTDOWN_TIME_MS: u64 = 500;
 
@@ -42,6 +42,9 @@ pub enum ConnectionError {
     #[error(transparent)]
     CmdError(#[from] CmdError),
 
+    #[error(transparent)]
+    RESPError(#[from] RESPError),
+
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
@@ -52,11 +55,140 @@ pub enum CmdError {
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
 
-    #[error(transparent)]
-    FromUtf8Error(#[from] std::string::FromUtf8Error),
+    #[error("value is not an integer or out of range")]
+    NotAnInteger,
 
-    #[error(transparent)]
-    ParseIntError(#[from] std::num::ParseIntError),
+    #[error("value is not a valid float")]
+    NotAFloat,
+
+    #[error("increment or decrement would overflow")]
+    Overflow,
+
+    #[error("decrement would overflow")]
+    DecrementOverflow,
+
+    #[error("increment would produce NaN or Infinity")]
+    NanOrInfinity,
+
+    #[error("string exceeds maximum allowed size (proto-max-bulk-len)")]
+    StringTooLong,
+
+    #[error("offset is out of range")]
+    OffsetOutOfRange,
+
+    #[error("The specified keys must contain string values")]
+    NotStringValues,
+
+    #[error("If you want both the length and indexes, please just use IDX.")]
+    LcsLenAndIdx,
+
+    #[error("Insufficient memory, transient memory for LCS exceeds proto-max-bulk-len")]
+    LcsTooLong,
+
+    #[error("bit offset is not an integer or out of range")]
+    BitOffsetOutOfRange,
+
+    #[error("bit is not an integer or out of range")]
+    BitOutOfRange,
+
+    #[error("The bit argument must be 1 or 0.")]
+    BitArgument,
+
+    #[error("BITOP NOT must be called with a single source key.")]
+    BitopNotSingleKey,
+
+    #[error("BITOP {0} must be called with at least two source keys.")]
+    BitopTwoKeys(String),
+
+    #[error("Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is.")]
+    InvalidBitfieldType,
+
+    #[error("Invalid OVERFLOW type specified")]
+    InvalidOverflowType,
+
+    #[error("BITFIELD_RO only supports the GET subcommand")]
+    BitfieldReadOnly,
+
+    #[error("Unsupported option {0}")]
+    UnsupportedOption(String),
+
+    #[error("NX and XX, GT or LT options at the same time are not compatible")]
+    NxAndXxGtLt,
+
+    #[error("GT and LT options at the same time are not compatible")]
+    GtAndLt,
+
+    #[error("no such key")]
+    NoSuchKey,
+
+    #[error("source and destination objects are the same")]
+    SameObject,
+
+    #[error("DB index is out of range")]
+    DbIndexOutOfRange,
+
+    #[error("invalid {0} DB index")]
+    InvalidDbIndex(&'static str),
+
+    #[error("Client names cannot contain spaces, newlines or special characters.")]
+    InvalidClientName,
+
+    #[error("unsupported protocol version")]
+    NoProto,
+
+    #[error("Protocol version is not an integer or out of range")]
+    InvalidProtocolVersion,
+
+    #[error("invalid username-password pair or user is disabled.")]
+    WrongPass,
+
+    #[error("Syntax error in HELLO option '{0}'")]
+    HelloSyntaxError(String),
+
+    #[error("Target key name already exists.")]
+    BusyKey,
+
+    #[error("Invalid TTL value, must be >= 0")]
+    NegativeTtl,
+
+    #[error("Invalid IDLETIME value, must be >= 0")]
+    InvalidIdleTime,
+
+    #[error("Invalid FREQ value, must be >= 0 and <= 255")]
+    InvalidFrequency,
+
+    #[error("DUMP payload version or checksum are wrong")]
+    DumpPayloadInvalid,
+
+    #[error("Bad data format")]
+    BadDataFormat,
+
+    #[error("values of type '{0}' can't be dumped yet")]
+    UnsupportedDumpType(&'static str),
+
+    #[error("invalid cursor")]
+    InvalidCursor,
+
+    #[error("unknown type name '{0}'")]
+    UnknownTypeName(String),
+
+    #[error("Key is not a valid HyperLogLog string value.")]
+    NotHyperLogLog,
+
+    #[error("Corrupted HLL object detected")]
+    CorruptedHll,
+
+    #[error("HLL encoding is not sparse")]
+    HllNotSparse,
+
+    #[error("Unknown PFDEBUG subcommand '{0}'")]
+    UnknownPfdebugSubcommand(String),
+
+    #[error("The specified key does not exist")]
+    KeyDoesNotExist,
+
+    #[error("{0}")]
+    SelfTestFailed(String),
 
     #[error("Clock may have gone backwards: {0}")]
     TimeError(#[from] std::time::SystemTimeError),
@@ -70,31 +202,76 @@ pub enum CmdError {
     #[error("CMD: CRLF (\\r\\n) characters not present at end")]
     CRLFNotAtEnd,
 
-    #[error("Null Array")]
-    NullArray,
-
     #[error("Command is not Array")]
     CmdNotArray,
 
-    #[error("Empty Array")]
-    EmptyArray,
-
     #[error("Not all words are Bulk Strings")]
     NotAllBulk,
 
-    #[error("Command missing argument")]
-    MissingArg,
+    #[error("unknown command '{0}', with args beginning with: {1}")]
+    UnrecognizedCmd(String, String),
+
+    #[error("wrong number of arguments for '{0}' command")]
+    WrongNumberOfArgs(String),
+
+    #[error("syntax error")]
+    SyntaxError,
 
-    #[error("Unrecognized command: {0}")]
-    UnrecognizedCmd(String),
+    #[error("Operation against a key holding the wrong kind of value")]
+    WrongType,
 
-    #[error("Wrong argument: {0}")]
-    WrongArg(String),
+    #[error("invalid expire time in '{0}' command")]
+    InvalidExpireTime(String),
+
+    #[error("unknown subcommand '{1}'. Try {0} HELP.")]
+    UnknownSubcommand(String, String),
+
+    #[error("Invalid command specified")]
+    InvalidCommand,
+
+    #[error("Invalid number of arguments specified for command")]
+    InvalidNumberOfArgs,
+
+    #[error("Invalid arguments specified for command")]
+    InvalidKeyArgs,
+
+    #[error("The command has no key arguments")]
+    NoKeyArgs,
 
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
 
+impl CmdError {
+    /// The error code that prefixes the error message in an error reply
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::WrongType | Self::NotHyperLogLog => "WRONGTYPE",
+            Self::CorruptedHll => "INVALIDOBJ",
+            Self::BusyKey => "BUSYKEY",
+            Self::NoProto => "NOPROTO",
+            Self::WrongPass => "WRONGPASS",
+            Self::SelfTestFailed(_) => "TESTFAILED",
+            _ => "ERR",
+        }
+    }
+
+    /// Whether the error is caused by a malformed request, rather than by a command or its arguments
+    ///
+    /// The server can't trust the rest of the byte stream after a protocol error, so it closes the connection,
+    /// while the connection stays open after any other error.
+    pub fn is_protocol_error(&self) -> bool {
+        matches!(
+            self,
+            Self::RESPError(_)
+                | Self::InputTooShort(_)
+                | Self::CRLFNotAtEnd
+                | Self::CmdNotArray
+                | Self::NotAllBulk
+        )
+    }
+}
+
 /// Errors related to working with [`crate::resp`]
 #[derive(Debug, Error)]
 pub enum RESPError {
@@ -131,6 +308,36 @@ pub enum RESPError {
     #[error("Couldn't parse {0} to integer")]
     IntegerParseError(String),
 
+    #[error("Integer {0} doesn't fit in 64 bits")]
+    IntegerOverflow(String),
+
+    #[error("invalid bulk length: {0}")]
+    InvalidBulkLength(usize),
+
+    #[error("invalid multibulk length")]
+    InvalidMultibulkLength,
+
+    #[error("expected '$', got '{0}'")]
+    ExpectedBulk(char),
+
+    #[error("query buffer limit exceeded")]
+    QueryBufferLimit,
+
+    #[error("too many nested aggregates")]
+    NestingTooDeep,
+
+    #[error("Incomplete RESP frame; more bytes are needed")]
+    Incomplete,
+
+    #[error("Invalid RESP value: {0}")]
+    InvalidValue(String),
+
+    #[error("unbalanced quotes in request")]
+    UnbalancedQuotes,
+
+    #[error("too big inline request")]
+    InlineTooBig,
+
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
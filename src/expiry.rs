@@ -1,47 +1,137 @@
 //! Eviction Facility
 //!
 //! Implementation of a background thread for eviction of expired keys.
+//!
+//! - [How Redis expires keys](https://redis.io/docs/latest/commands/expire/#how-redis-expires-keys)
+//!
+//! Keys are expired actively in the same way Redis does it: a small sample of keys with an expiration time is
+//! checked at a time, and more keys are checked right away only while many of the checked ones turn out
+//! to be expired, so that the databases are never locked for long.
 
-use crate::constants::HZ_MS;
+use crate::cmd::write_lock;
+use crate::constants::{
+    ACTIVE_EXPIRE_CYCLE_ACCEPTABLE_STALE, ACTIVE_EXPIRE_CYCLE_KEYS_PER_LOOP,
+    ACTIVE_EXPIRE_CYCLE_SLOW_TIME_PERC, HZ_MS,
+};
 use crate::errors::CmdError;
 use crate::storage::generic::Crud;
-use crate::types::{ConcurrentStorageType, ExpirationTime, StorageKey};
+use crate::types::{
+    ConcurrentDatabases, ConcurrentStorageType, ExpirationTime, ExpirationTimeType,
+};
 use anyhow::Result;
 use log::{debug, trace};
 use std::fmt::Debug;
 use std::ops::DerefMut;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Returns the current Unix time in milliseconds.
+pub(crate) fn now_ms() -> Result<ExpirationTimeType, CmdError> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis())
+}
+
+/// Checks whether a key with the given expiration time has expired at `now` milliseconds.
+///
+/// Keys without an expiration time never expire.
+pub(crate) fn is_expired(expiry: ExpirationTime, now: ExpirationTimeType) -> bool {
+    expiry.is_some_and(|expiry| now > expiry)
+}
 
-/// Removes expired keys from the storage
+/// Removes expired keys from all databases
 ///
 /// Meant to be run in a background thread as it loops infinitely.
 ///
-/// It sleeps for [`Hz`](HZ_MS) milliseconds and then removes expired keys from the storage in a loop.
-pub fn eviction_loop<
-    KV: Crud + Debug,
-    KE: Clone + Crud + Debug + IntoIterator<Item = (StorageKey, ExpirationTime)>,
->(
-    storage: ConcurrentStorageType<KV, KE>,
+/// It sleeps for [`Hz`](HZ_MS) milliseconds and then removes expired keys from the databases in a loop.
+/// Every database is sampled where the previous cycle left off, so all keys with an expiration time get
+/// checked eventually. A database is sampled again as long as more than
+/// [`ACTIVE_EXPIRE_CYCLE_ACCEPTABLE_STALE`] percent of its checked keys were expired, within the cycle's time limit.
+///
+/// The databases are locked one at a time, and only for a single sample, so that commands can proceed in the meantime.
+pub fn eviction_loop<KV: Crud + Debug, KE: Crud + Debug>(
+    databases: ConcurrentDatabases<KV, KE>,
 ) -> Result<(), CmdError> {
     debug!("Starting the eviction loop...");
+    let time_limit =
+        Duration::from_millis((HZ_MS * ACTIVE_EXPIRE_CYCLE_SLOW_TIME_PERC / 100) as u64);
+    let mut cursors = vec![0; databases.len()];
     loop {
-        let time_now_ms = match SystemTime::now().duration_since(UNIX_EPOCH) {
-            Ok(since) => since,
-            Err(err) => return Err(CmdError::TimeError(err)),
-        }
-        .as_millis();
-        trace!("time_now_ms = {time_now_ms}");
-        let mut s = storage.write().expect("RwLockWriteGuard");
-        let (kv, ke) = s.deref_mut();
-        for (key, expiry) in ke.clone().into_iter() {
-            if time_now_ms > expiry.expect("Expected Some(expiry)") {
-                kv.delete(&key);
-                ke.delete(&key);
+        let start = Instant::now();
+        for (index, storage) in databases.iter().enumerate() {
+            loop {
+                let (checked, expired) = expire_sample(storage, &mut cursors[index], now_ms()?);
+                trace!("DB {index}: {expired} of {checked} checked keys expired");
+                if cursors[index] == 0
+                    || expired * 100 <= checked * ACTIVE_EXPIRE_CYCLE_ACCEPTABLE_STALE
+                    || start.elapsed() > time_limit
+                {
+                    break;
+                }
             }
         }
-        trace!("KV: {kv:?}");
-        trace!("KE: {ke:?}");
-        drop(s);
         std::thread::sleep(Duration::from_millis(HZ_MS as u64));
     }
 }
+
+/// Checks up to [`ACTIVE_EXPIRE_CYCLE_KEYS_PER_LOOP`] keys with an expiration time, starting at `cursor`,
+/// and deletes the ones that have expired at `now`.
+///
+/// Advances the cursor, and returns the number of checked keys and the number of deleted keys.
+fn expire_sample<KV: Crud, KE: Crud>(
+    storage: &ConcurrentStorageType<KV, KE>,
+    cursor: &mut u64,
+    now: ExpirationTimeType,
+) -> (usize, usize) {
+    let mut s = write_lock(storage);
+    let (kv, ke) = s.deref_mut();
+    let (next, keys) = ke.scan(*cursor, ACTIVE_EXPIRE_CYCLE_KEYS_PER_LOOP);
+    *cursor = next;
+
+    let mut expired = 0;
+    for key in &keys {
+        if is_expired(ke.expiry(key).flatten(), now) {
+            kv.delete(key);
+            ke.delete(key);
+            expired += 1;
+        }
+    }
+    (keys.len(), expired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::read_lock;
+    use crate::cmd::tests::new_databases;
+    use crate::storage::object::Object;
+    use bytes::Bytes;
+
+    #[test]
+    fn expire_sample_deletes_expired_keys() {
+        let storage = new_databases().swap_remove(0);
+        let now = now_ms().unwrap();
+        {
+            let mut s = write_lock(&storage);
+            for i in 0..50 {
+                let expiry = if i % 2 == 0 { now - 1 } else { now + 60_000 };
+                s.create(
+                    &Bytes::from(format!("key:{i}")),
+                    Object::default(),
+                    Some(expiry),
+                );
+            }
+            s.create(&Bytes::from("persistent"), Object::default(), None);
+        }
+
+        let mut cursor = 0;
+        let (checked, expired) = expire_sample(&storage, &mut cursor, now);
+        assert_eq!(ACTIVE_EXPIRE_CYCLE_KEYS_PER_LOOP, checked);
+        assert_ne!(0, cursor);
+        let mut total = expired;
+        while cursor != 0 {
+            let (checked, expired) = expire_sample(&storage, &mut cursor, now);
+            assert!(checked <= ACTIVE_EXPIRE_CYCLE_KEYS_PER_LOOP);
+            total += expired;
+        }
+        assert_eq!(25, total);
+        assert_eq!(26, read_lock(&storage).size());
+    }
+}
@@ -0,0 +1,210 @@
+//! Glob-Style Pattern Matching
+//!
+//! A port of Redis's `stringmatchlen`, which is used wherever Redis matches keys or channels against patterns,
+//! such as in `KEYS`, `SCAN ... MATCH`, `PSUBSCRIBE` and ACL key patterns.
+//!
+//! Supported patterns:
+//! - `h?llo` matches `hello`, `hallo` and `hxllo`
+//! - `h*llo` matches `hllo` and `heeeello`
+//! - `h[ae]llo` matches `hello` and `hallo`, but not `hillo`
+//! - `h[^e]llo` matches `hallo`, `hbllo`, ..., but not `hello`
+//! - `h[a-b]llo` matches `hallo` and `hbllo`
+//!
+//! Use `\` to escape special characters, if you want to match them verbatim.
+//!
+//! Patterns and strings are binary-safe.
+
+/// The maximum depth of recursion, which protects against abusive patterns such as many `*` in a row
+const MAX_NESTING: usize = 1000;
+
+/// Checks whether `string` matches the glob-style `pattern`, the same way Redis does it.
+///
+/// With `nocase`, letters are compared case-insensitively.
+pub fn string_match(pattern: &[u8], string: &[u8], nocase: bool) -> bool {
+    let mut skip_longer_matches = false;
+    string_match_impl(pattern, string, nocase, &mut skip_longer_matches, 0)
+}
+
+fn string_match_impl(
+    pattern: &[u8],
+    string: &[u8],
+    nocase: bool,
+    skip_longer_matches: &mut bool,
+    nesting: usize,
+) -> bool {
+    if nesting > MAX_NESTING {
+        return false;
+    }
+
+    let eq = |a: u8, b: u8| {
+        if nocase {
+            a.eq_ignore_ascii_case(&b)
+        } else {
+            a == b
+        }
+    };
+    // Reading past the end of the pattern yields 0, like the terminating byte of a C string.
+    let at = |i: usize| pattern.get(i).copied().unwrap_or_default();
+
+    let (mut p, mut s) = (0, 0);
+    while p < pattern.len() && s < string.len() {
+        match pattern[p] {
+            b'*' => {
+                while at(p + 1) == b'*' && p + 1 < pattern.len() {
+                    p += 1;
+                }
+                if p + 1 == pattern.len() {
+                    return true;
+                }
+                while s < string.len() {
+                    if string_match_impl(
+                        &pattern[p + 1..],
+                        &string[s..],
+                        nocase,
+                        skip_longer_matches,
+                        nesting + 1,
+                    ) {
+                        return true;
+                    }
+                    if *skip_longer_matches {
+                        return false;
+                    }
+                    s += 1;
+                }
+                // The rest of the pattern doesn't match anywhere in the rest of the string, so a longer match
+                // of any earlier `*` can't help either, and the whole search can stop.
+                *skip_longer_matches = true;
+                return false;
+            }
+            b'?' => s += 1,
+            b'[' => {
+                p += 1;
+                let not = at(p) == b'^';
+                if not {
+                    p += 1;
+                }
+                let mut matched = false;
+                loop {
+                    let remaining = pattern.len() - p;
+                    if at(p) == b'\\' && remaining >= 2 {
+                        p += 1;
+                        if pattern[p] == string[s] {
+                            matched = true;
+                        }
+                    } else if at(p) == b']' {
+                        break;
+                    } else if remaining == 0 {
+                        // An unterminated class; step back, so the pattern ends after the outer increment.
+                        p -= 1;
+                        break;
+                    } else if remaining >= 3 && pattern[p + 1] == b'-' {
+                        let (mut start, mut end) = (pattern[p], pattern[p + 2]);
+                        let mut c = string[s];
+                        if start > end {
+                            std::mem::swap(&mut start, &mut end);
+                        }
+                        if nocase {
+                            start = start.to_ascii_lowercase();
+                            end = end.to_ascii_lowercase();
+                            c = c.to_ascii_lowercase();
+                        }
+                        p += 2;
+                        if (start..=end).contains(&c) {
+                            matched = true;
+                        }
+                    } else if eq(pattern[p], string[s]) {
+                        matched = true;
+                    }
+                    p += 1;
+                }
+                if not {
+                    matched = !matched;
+                }
+                if !matched {
+                    return false;
+                }
+                s += 1;
+            }
+            c => {
+                if c == b'\\' && pattern.len() - p >= 2 {
+                    p += 1;
+                }
+                if !eq(pattern[p], string[s]) {
+                    return false;
+                }
+                s += 1;
+            }
+        }
+        p += 1;
+        if s == string.len() {
+            while p < pattern.len() && pattern[p] == b'*' {
+                p += 1;
+            }
+            break;
+        }
+    }
+
+    p == pattern.len() && s == string.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, string: &str) -> bool {
+        string_match(pattern.as_bytes(), string.as_bytes(), false)
+    }
+
+    #[test]
+    fn string_match_wildcards() {
+        // Same as in Redis, an empty string doesn't match anything but an empty pattern.
+        assert!(!matches("*", ""));
+        assert!(matches("*", "anything"));
+        assert!(matches("h?llo", "hello"));
+        assert!(!matches("h?llo", "hllo"));
+        assert!(matches("h*llo", "hllo"));
+        assert!(matches("h*llo", "heeeello"));
+        assert!(!matches("h*llo", "heeeellx"));
+        assert!(matches("a*b*c", "aXXbYYc"));
+        assert!(matches("a**", "a"));
+        assert!(!matches("", "a"));
+        assert!(matches("", ""));
+        assert!(!matches("a", ""));
+    }
+
+    #[test]
+    fn string_match_classes() {
+        assert!(matches("h[ae]llo", "hello"));
+        assert!(matches("h[ae]llo", "hallo"));
+        assert!(!matches("h[ae]llo", "hillo"));
+        assert!(matches("h[^e]llo", "hallo"));
+        assert!(!matches("h[^e]llo", "hello"));
+        assert!(matches("h[a-b]llo", "hbllo"));
+        assert!(matches("h[b-a]llo", "hallo"));
+        assert!(!matches("h[a-b]llo", "hcllo"));
+        assert!(matches("[\\]]", "]"));
+        assert!(matches("[a", "a"));
+        assert!(!matches("[", "a"));
+        assert!(matches("[^", "a"));
+        assert!(matches("[^]", "a"));
+    }
+
+    #[test]
+    fn string_match_escapes_and_case() {
+        assert!(matches("h\\*llo", "h*llo"));
+        assert!(!matches("h\\*llo", "hello"));
+        assert!(matches("\\?", "?"));
+        assert!(matches("a\\", "a\\"));
+        assert!(!matches("HELLO", "hello"));
+        assert!(string_match(b"HELLO", b"hello", true));
+        assert!(string_match(b"h[A-Z]llo", b"hello", true));
+        assert!(string_match(b"key:\xff*", b"key:\xff\x00", false));
+    }
+
+    #[test]
+    fn string_match_abusive_pattern() {
+        let pattern = "a*".repeat(50) + "b";
+        let string = "a".repeat(100);
+        assert!(!matches(&pattern, &string));
+    }
+}
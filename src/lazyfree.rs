@@ -0,0 +1,55 @@
+//! Lazy Freeing Facility
+//!
+//! Implementation of a background thread for freeing of large values.
+//!
+//! Dropping a value with many elements, such as a list with millions of items, takes time, and it would block
+//! the command that removed it. Such values are instead sent to a background thread, which drops them.
+//! The same goes for whole databases that are flushed with the `ASYNC` option.
+
+use crate::constants::LAZYFREE_THRESHOLD;
+use crate::storage::object::Object;
+use crate::types::StorageValue;
+use log::debug;
+use std::sync::mpsc::{self, Sender};
+use std::sync::OnceLock;
+
+/// The sending side of the channel to the background thread, which is started on first use
+static LAZYFREE: OnceLock<Sender<Box<dyn Send>>> = OnceLock::new();
+
+/// Returns the amount of work needed to free a value, which is roughly the number of its allocations.
+pub(crate) fn free_effort(value: &Object) -> usize {
+    match value {
+        Object::String(_) => 1,
+        Object::List(list) => list.len(),
+        Object::Hash(hash) => hash.len(),
+        Object::Set(set) => set.len(),
+        Object::SortedSet(zset) => zset.len(),
+        Object::Stream(stream) => stream.len(),
+    }
+}
+
+/// Frees a value, in the background if it's large, and in place otherwise.
+pub(crate) fn free(value: StorageValue) {
+    if free_effort(&value) > LAZYFREE_THRESHOLD {
+        free_async(value);
+    }
+}
+
+/// Frees anything, such as a whole database, in the background.
+pub(crate) fn free_async<T: Send + 'static>(garbage: T) {
+    let sender = LAZYFREE.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<Box<dyn Send>>();
+        std::thread::Builder::new()
+            .name("lazyfree-thread".to_string())
+            .spawn(move || {
+                debug!("Starting the lazy free thread...");
+                for garbage in receiver {
+                    drop(garbage);
+                }
+            })
+            .expect("Failed to spawn the lazy free thread");
+        sender
+    });
+    // The garbage is dropped in place if the background thread is gone.
+    let _ = sender.send(Box::new(garbage));
+}
@@ -6,8 +6,11 @@ pub mod conn;
 pub mod constants;
 pub mod errors;
 pub mod expiry;
+pub mod glob;
+pub mod lazyfree;
 #[macro_use]
 pub mod macros;
+pub mod random;
 pub mod resp;
 pub mod server;
 pub mod storage;
@@ -8,7 +8,9 @@ use redis_server::errors::ApplicationError;
 use redis_server::expiry::eviction_loop;
 use redis_server::server::Server;
 use redis_server::storage::Storage;
-use redis_server::types::{InMemoryExpiryTimeHashMap, InMemoryStorageHashMap, StorageType};
+use redis_server::types::{
+    ConcurrentDatabases, InMemoryExpiryTimeHashMap, InMemoryStorageHashMap, StorageType,
+};
 use std::sync::{Arc, RwLock};
 
 #[tokio::main]
@@ -18,20 +20,22 @@ async fn main() -> Result<(), ApplicationError> {
 
     let args = Args::parse();
 
-    let storage = Storage::<
-        StorageType<InMemoryStorageHashMap, InMemoryExpiryTimeHashMap>,
-        InMemoryStorageHashMap,
-        InMemoryExpiryTimeHashMap,
-    >::new();
-
-    let storage = Arc::new(RwLock::new(storage));
-
-    let evictor_store = Arc::clone(&storage);
+    let databases: ConcurrentDatabases<_, _> = (0..args.databases)
+        .map(|_| {
+            Arc::new(RwLock::new(Storage::<
+                StorageType<InMemoryStorageHashMap, InMemoryExpiryTimeHashMap>,
+                InMemoryStorageHashMap,
+                InMemoryExpiryTimeHashMap,
+            >::new()))
+        })
+        .collect();
+
+    let evictor_store = Arc::clone(&databases);
     std::thread::Builder::new()
         .name("evictor-thread".to_string())
         .spawn(move || eviction_loop(evictor_store))?;
 
-    let core_store = Arc::clone(&storage);
+    let core_store = Arc::clone(&databases);
     let server = Server::new(args, core_store).await?;
     server.start().await?;
 
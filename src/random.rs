@@ -0,0 +1,52 @@
+//! Pseudo-Random Numbers
+//!
+//! A fast, non-cryptographic pseudo-random number generator for the places where Redis uses `rand()`,
+//! such as picking a random key, or deciding whether to increment an LFU counter.
+//!
+//! Every thread has its own xorshift64* generator, which is seeded once from the standard library's
+//! randomly-keyed hasher, so generating a number doesn't need any synchronization between threads.
+
+use std::cell::Cell;
+use std::hash::{BuildHasher, RandomState};
+
+thread_local! {
+    /// The state of the generator of the current thread, which is never 0
+    static STATE: Cell<u64> = Cell::new(RandomState::new().hash_one(()) | 1);
+}
+
+/// Returns a pseudo-random 64-bit number.
+pub fn random_u64() -> u64 {
+    STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    })
+}
+
+/// Returns a pseudo-random number between 0 (inclusive) and 1 (exclusive).
+pub fn random_f64() -> f64 {
+    // The upper 53 bits fit exactly in the mantissa.
+    (random_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_numbers_vary() {
+        let numbers = (0..1000).map(|_| random_u64()).collect::<Vec<_>>();
+        let mut distinct = numbers.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+        assert_eq!(numbers.len(), distinct.len());
+
+        let fractions = (0..1000).map(|_| random_f64()).collect::<Vec<_>>();
+        assert!(fractions.iter().all(|r| (0.0..1.0).contains(r)));
+        let mean = fractions.iter().sum::<f64>() / fractions.len() as f64;
+        assert!((0.4..0.6).contains(&mean), "{mean}");
+    }
+}
@@ -45,12 +45,15 @@
 //!
 //! [Official documentation](https://redis.io/docs/latest/develop/reference/protocol-spec/)
 
+use crate::constants::{
+    PROTO_INLINE_MAX_SIZE, PROTO_MAX_BULK_LEN, PROTO_MAX_MULTIBULK_LEN, PROTO_MAX_NESTING_DEPTH,
+    PROTO_MAX_QUERYBUF_LEN,
+};
 use crate::errors::RESPError;
 use anyhow::Result;
-use bytes::Bytes;
-use memchr::memmem;
-use std::fmt::{Display, Formatter};
-use std::ops::Neg;
+use bytes::{BufMut, Bytes, BytesMut};
+use memchr::memchr;
+use std::fmt::{Display, Formatter, Write};
 
 /// A RESP message
 ///
@@ -66,21 +69,46 @@ pub(crate) struct Message {
 impl Message {
     /// Deserializes (parses) a received byte stream into a [`Message`].
     ///
-    /// The byte stream buffer should not contain excess bytes, i.e., it is expected to end with the appropriate `CRLF`.
+    /// The byte stream buffer may contain excess bytes after the message; they are left unread.
     ///
     /// This is an associated function that can be used to create a new instance of a [`Message`].
     ///
     /// Returns a tuple of ([`Message`], the length of the complete raw value in bytes).
+    ///
+    /// # Errors
+    /// - [`RESPError::Incomplete`] in case the byte stream ends before the message does
+    /// - Other [`RESPError`]s in case the message is malformed
     pub(crate) fn deserialize(bytes: &Bytes) -> Result<(Message, usize), RESPError> {
-        let resp_type = bytes[0].try_into()?;
+        Self::deserialize_nested(bytes, 0)
+    }
+
+    /// Deserializes a message that is nested `depth` levels deep in aggregates.
+    ///
+    /// # Errors
+    /// - [`RESPError::NestingTooDeep`] in case the message is nested deeper than [`PROTO_MAX_NESTING_DEPTH`],
+    ///   so that a malicious message can't overflow the stack
+    /// - The same errors as [`Message::deserialize`]
+    fn deserialize_nested(bytes: &Bytes, depth: usize) -> Result<(Message, usize), RESPError> {
+        if depth > PROTO_MAX_NESTING_DEPTH {
+            return Err(RESPError::NestingTooDeep);
+        }
+        let resp_type = Cursor::new(bytes).peek()?.try_into()?;
         let (value, length) = match resp_type {
             RESPType::SimpleString => Self::deserialize_simple_string(bytes)?,
             RESPType::BulkString => Self::deserialize_bulk_string(bytes)?,
             RESPType::Integer => Self::deserialize_integer(bytes)?,
-            RESPType::Array => Self::deserialize_array(bytes)?,
+            RESPType::Array => Self::deserialize_array(bytes, depth)?,
             RESPType::Error => Self::deserialize_error(bytes)?,
-            #[allow(unreachable_patterns)]
-            t => return Err(RESPError::UnsupportedRESPType(u8::from(t))),
+            RESPType::Null => Self::deserialize_null(bytes)?,
+            RESPType::Boolean => Self::deserialize_boolean(bytes)?,
+            RESPType::Double => Self::deserialize_double(bytes)?,
+            RESPType::BigNumber => Self::deserialize_big_number(bytes)?,
+            RESPType::BulkError => Self::deserialize_bulk_error(bytes)?,
+            RESPType::VerbatimString => Self::deserialize_verbatim_string(bytes)?,
+            RESPType::Map => Self::deserialize_map(bytes, depth)?,
+            RESPType::Attribute => Self::deserialize_attribute(bytes, depth)?,
+            RESPType::Set => Self::deserialize_set(bytes, depth)?,
+            RESPType::Push => Self::deserialize_push(bytes, depth)?,
         };
 
         Ok((
@@ -92,6 +120,143 @@ impl Message {
         ))
     }
 
+    /// Deserializes (parses) an [inline command](https://redis.io/docs/latest/develop/reference/protocol-spec/#inline-commands)
+    /// into a [`Message`].
+    ///
+    /// Inline commands are meant for humans that talk to the server directly, e.g., through `telnet` or `netcat`,
+    /// without a RESP client. An inline command is a single line of space-separated arguments,
+    /// terminated by `LF`, or by `CRLF`.
+    ///
+    /// The arguments are split the same way Redis does it:
+    /// - An argument can be enclosed in double quotes, in which case it can contain spaces, and the escape
+    ///   sequences `\n`, `\r`, `\t`, `\b`, `\a`, `\\`, `\"` and `\xHH` (a hexadecimal byte) are recognized.
+    /// - An argument can be enclosed in single quotes, in which case it can contain spaces, and only the
+    ///   escape sequence `\'` is recognized.
+    /// - A closing quote must be followed by a space or by the end of the line.
+    ///
+    /// The result is the same as for the equivalent RESP request: an array of bulk strings.
+    /// An empty line results in an empty array.
+    ///
+    /// Returns a tuple of ([`Message`], the length of the line in bytes, including the line terminator).
+    ///
+    /// Examples:
+    /// - `PING\r\n` => `["PING"]`
+    /// - `SET foo "bar baz"\n` => `["SET", "foo", "bar baz"]`
+    /// - `ECHO 'it\'s' "\x41\n"\r\n` => `["ECHO", "it's", "A\n"]`
+    ///
+    /// # Errors
+    /// - [`RESPError::Incomplete`] in case the line isn't terminated
+    /// - [`RESPError::UnbalancedQuotes`] in case a quote isn't closed or a closing quote isn't followed by a space
+    pub(crate) fn deserialize_inline(bytes: &Bytes) -> Result<(Message, usize), RESPError> {
+        let Some(lf_pos) = memchr(b'\n', bytes) else {
+            return Err(RESPError::Incomplete);
+        };
+        let line = &bytes[..lf_pos];
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+        let mut args = Vec::new();
+        let mut i = 0;
+        loop {
+            while i < line.len() && Self::is_space(line[i]) {
+                i += 1;
+            }
+            if i == line.len() {
+                break;
+            }
+
+            let mut arg = Vec::new();
+            let mut quote = None;
+            loop {
+                let Some(&c) = line.get(i) else {
+                    if quote.is_some() {
+                        return Err(RESPError::UnbalancedQuotes);
+                    }
+                    break;
+                };
+                match quote {
+                    Some(b'"') if c == b'\\' => {
+                        let hex = (line.get(i + 1) == Some(&b'x'))
+                            .then(|| line.get(i + 2..i + 4))
+                            .flatten()
+                            .and_then(Self::hex_byte);
+                        if let Some(byte) = hex {
+                            arg.push(byte);
+                            i += 3;
+                        } else if let Some(&escaped) = line.get(i + 1) {
+                            arg.push(match escaped {
+                                b'n' => b'\n',
+                                b'r' => b'\r',
+                                b't' => b'\t',
+                                b'b' => 0x08,
+                                b'a' => 0x07,
+                                other => other,
+                            });
+                            i += 1;
+                        } else {
+                            arg.push(c);
+                        }
+                    }
+                    Some(b'\'') if c == b'\\' && line.get(i + 1) == Some(&b'\'') => {
+                        i += 1;
+                        arg.push(b'\'');
+                    }
+                    Some(q) if c == q => {
+                        // The closing quote must be followed by a space or nothing at all.
+                        if line.get(i + 1).is_some_and(|&c| !Self::is_space(c)) {
+                            return Err(RESPError::UnbalancedQuotes);
+                        }
+                        i += 1;
+                        break;
+                    }
+                    Some(_) => arg.push(c),
+                    None if Self::is_space(c) => break,
+                    None if c == b'"' || c == b'\'' => quote = Some(c),
+                    None => arg.push(c),
+                }
+                i += 1;
+            }
+            args.push(Value::BulkString(Bytes::from(arg)));
+        }
+
+        Ok((
+            Self {
+                resp_type: RESPType::Array,
+                data: Value::Array(args),
+            },
+            lf_pos + 1,
+        ))
+    }
+
+    /// Whether a byte separates the arguments of an inline command, the same as C's `isspace`,
+    /// which, unlike [`u8::is_ascii_whitespace`], includes the vertical tab
+    fn is_space(c: u8) -> bool {
+        matches!(c, b' ' | b'\t' | b'\n' | b'\x0b' | b'\x0c' | b'\r')
+    }
+
+    /// Converts two ASCII hexadecimal digits to the byte they represent
+    fn hex_byte(digits: &[u8]) -> Option<u8> {
+        if !digits.iter().all(u8::is_ascii_hexdigit) {
+            return None;
+        }
+        std::str::from_utf8(digits)
+            .ok()
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+    }
+
+    /// Returns the length of the inline command at the beginning of the byte stream, including the line terminator,
+    /// or `None` in case the line isn't terminated yet.
+    ///
+    /// # Errors
+    /// - [`RESPError::InlineTooBig`] in case the line is longer than [`PROTO_INLINE_MAX_SIZE`]
+    ///   and still isn't terminated
+    pub(crate) fn inline_frame_len(bytes: &[u8]) -> Result<Option<usize>, RESPError> {
+        match memchr(b'\n', bytes) {
+            Some(lf_pos) => Ok(Some(lf_pos + 1)),
+            None if bytes.len() > PROTO_INLINE_MAX_SIZE => Err(RESPError::InlineTooBig),
+            None => Ok(None),
+        }
+    }
+
     /// Gets the length of aggregate types (for example, arrays or bulk strings).
     ///
     /// Returns a tuple of the parsed length and the number of bytes read to extract the length.
@@ -111,62 +276,19 @@ impl Message {
     /// - `b"*0\r\n"` => `(Some(0), 4)`
     /// - `b"$123456\r\n"` => `(Some(123456), 9)`
     ///
-    /// Reading the length of aggregate types (for example, bulk strings or arrays) can be processed with code that
-    /// performs a single operation per character while at the same time scanning for the CR character.
-    ///
-    /// https://redis.io/docs/latest/develop/reference/protocol-spec/#high-performance-parser-for-the-redis-protocol
+    /// See [`Cursor::read_len`] for details.
     ///
     /// # Errors
-    /// - Returns an error in case `LF` is missing after `CR`, but `CR` is assumed to be present, as it's required
-    ///   by the algorithm which is designed to be fast, so it doesn't check for it beforehand.
-    /// - Length can't be negative, so returns an error if that's the case.
-    /// - Characters composing length must be decimal digits `0` through `9`; returns an error if that isn't the case.
+    /// - [`RESPError::Incomplete`] in case the byte stream ends before `CRLF`
+    /// - [`RESPError::NegativeLength`] in case the length is negative, but not `-1`
+    /// - [`RESPError::IntegerParseError`] in case the length contains anything but decimal digits
+    /// - [`RESPError::IntegerOverflow`] in case the length doesn't fit in an [`i64`]
     pub(crate) fn parse_len(bytes: &[u8]) -> Result<(Option<usize>, usize), RESPError> {
-        let mut ptr: *const u8 = bytes.as_ptr();
-        let mut bytes_read: usize = 0;
-        let mut len: usize = 0;
-
-        unsafe {
-            // Skip the first byte which denotes a RESP type.
-            ptr = ptr.add(1);
-            bytes_read += 1;
-
-            if (*ptr).eq(&b'-') {
-                if (*ptr.add(1)).eq(&b'1') {
-                    if (*ptr.add(2)).eq(&b'\r') {
-                        if (*ptr.add(3)).eq(&b'\n') {
-                            bytes_read += 4;
-                            return Ok((None, bytes_read));
-                        } else {
-                            return Err(RESPError::LFMissing);
-                        }
-                    } else {
-                        return Err(RESPError::NegativeLength);
-                    }
-                } else {
-                    return Err(RESPError::NegativeLength);
-                }
-            }
-
-            while (*ptr).ne(&b'\r') {
-                bytes_read += 1;
-                if *ptr < b'0' || *ptr - b'0' > 9 {
-                    return Err(RESPError::IntegerParseError(String::from_utf8(
-                        bytes[1..bytes_read].to_vec(),
-                    )?));
-                }
-                len = (len * 10) + (*ptr - b'0') as usize;
-                ptr = ptr.add(1);
-            }
-
-            ptr = ptr.add(1);
-            if (*ptr).ne(&b'\n') {
-                return Err(RESPError::LFMissing);
-            }
-        }
-
-        // `CRLF` account for the `+2`:
-        Ok((Some(len), bytes_read + 2))
+        let mut cursor = Cursor::new(bytes);
+        // Skip the first byte which denotes a RESP type.
+        cursor.read_u8()?;
+        let len = cursor.read_len()?;
+        Ok((len, cursor.position()))
     }
 
     /// Returns a tuple of deserialized simple string contents and length of the complete raw simple string in bytes.
@@ -179,26 +301,19 @@ impl Message {
         // Simple strings can't contain CR or LF unless in the terminator and in that order.
         // But, we are providing `Bytes` and work on slices of it in entire library, so `bytes`
         // may contain multiple CRLF.
-        let mut cr_it = memmem::find_iter(bytes, b"\r");
-        let cr_pos = match cr_it.next() {
-            Some(pos) => pos,
-            None => return Err(RESPError::CRMissing),
-        };
-        let mut lf_it = memmem::find_iter(bytes, b"\n");
-        let lf_pos = match lf_it.next() {
-            Some(pos) => pos,
-            None => return Err(RESPError::LFMissing),
-        };
-        if lf_pos != cr_pos + 1 {
-            return Err(RESPError::CRLFNotAtEnd);
-        }
-
-        Ok((Value::SimpleString(bytes.slice(1..cr_pos)), lf_pos + 1))
+        let mut cursor = Cursor::new(bytes);
+        cursor.read_u8()?;
+        let line = cursor.read_line()?;
+        Ok((
+            Value::SimpleString(bytes.slice_ref(line)),
+            cursor.position(),
+        ))
     }
 
     /// Returns a tuple of deserialized bulk string contents and the length of the complete raw bulk string in bytes.
     ///
     /// The string contents are returned as `Value::BulkString(contents)`, where contents are [`Bytes`].
+    /// They aren't copied; they are a slice of the received byte stream.
     ///
     /// In case `-1` is received as length, returns `(Value::NullBulkString, 5)`.
     ///
@@ -208,11 +323,16 @@ impl Message {
     /// - A Null Bulk String: `$-1\r\n` => `(Value::NullBulkString, 5)`
     fn deserialize_bulk_string(bytes: &Bytes) -> Result<(Value, usize), RESPError> {
         // Bulk strings can contain CR or LF or CRLF.
-        let (Some(len), start) = Self::parse_len(bytes)? else {
-            return Ok((Value::NullBulkString, 5));
+        let mut cursor = Cursor::new(bytes);
+        cursor.read_u8()?;
+        let Some(len) = cursor.read_len()? else {
+            return Ok((Value::NullBulkString, cursor.position()));
         };
-        let end = start + len;
-        Ok((Value::BulkString(bytes.slice(start..end)), end + 2))
+        let contents = cursor.read_bulk(len)?;
+        Ok((
+            Value::BulkString(bytes.slice_ref(contents)),
+            cursor.position(),
+        ))
     }
 
     /// Returns a tuple of deserialized integer contents and the length of the complete raw integer in bytes.
@@ -225,20 +345,10 @@ impl Message {
     /// - `:+1000\r\n` => `(1000, 8)`
     /// - `:-1000\r\n` => `(-1000, 8)`
     fn deserialize_integer(bytes: &Bytes) -> Result<(Value, usize), RESPError> {
-        // We use Self::parse_len(). It skips the first byte, considering it a RESP type.
-        if bytes[1].eq(&b'+') {
-            let (value, bytes_read) = Self::parse_len(&bytes.slice(1..))?;
-            let value = value.expect("Expected some length; got None (-1).");
-            Ok((Value::Integer(value as i64), 1 + bytes_read))
-        } else if bytes[1].eq(&b'-') {
-            let (value, bytes_read) = Self::parse_len(&bytes.slice(1..))?;
-            let value = value.expect("Expected some length; got None (-1).");
-            Ok((Value::Integer((value as i64).neg()), 1 + bytes_read))
-        } else {
-            let (value, bytes_read) = Self::parse_len(bytes)?;
-            let value = value.expect("Expected some length; got None (-1).");
-            Ok((Value::Integer(value as i64), bytes_read))
-        }
+        let mut cursor = Cursor::new(bytes);
+        cursor.read_u8()?;
+        let value = cursor.read_integer()?;
+        Ok((Value::Integer(value), cursor.position()))
     }
 
     /// Returns a tuple of deserialized array contents and the length of the complete raw array in bytes.
@@ -266,18 +376,51 @@ impl Message {
     /// - `None`: A Null Array: `*-1\r\n` => `(Value::NullArray, 5)`
     /// - An array with null elements:
     ///   `*3\r\n$5\r\nhello\r\n$-1\r\n$5\r\nworld\r\n` => `["hello", None, "world"]`
-    fn deserialize_array(bytes: &Bytes) -> Result<(Value, usize), RESPError> {
-        let (Some(num_elts), mut offset) = Self::parse_len(bytes)? else {
+    fn deserialize_array(bytes: &Bytes, depth: usize) -> Result<(Value, usize), RESPError> {
+        let (Some(num_elts), offset) = Self::parse_len(bytes)? else {
             return Ok((Value::NullArray, 5));
         };
-        let mut result = Vec::with_capacity(num_elts);
+        let (result, offset) = Self::deserialize_elements(bytes, offset, num_elts, depth)?;
+        Ok((Value::Array(result), offset))
+    }
+
+    /// Deserializes `num_elts` consecutive values, the first one of which starts at `offset`.
+    ///
+    /// Returns a tuple of the values and the offset right after the last one.
+    fn deserialize_elements(
+        bytes: &Bytes,
+        mut offset: usize,
+        num_elts: usize,
+        depth: usize,
+    ) -> Result<(Vec<Value>, usize), RESPError> {
+        // Every element takes at least three bytes, so don't trust the received length for pre-allocation.
+        let mut result = Vec::with_capacity(num_elts.min(bytes.len() / 3));
         for _ in 0..num_elts {
-            let (msg, bytes_read) = Message::deserialize(&bytes.slice(offset..))?;
+            let (msg, bytes_read) = Message::deserialize_nested(&bytes.slice(offset..), depth + 1)?;
             let value = msg.data;
             result.push(value);
             offset += bytes_read;
         }
-        Ok((Value::Array(result), offset))
+        Ok((result, offset))
+    }
+
+    /// Deserializes the key-value pairs of a map or an attribute.
+    ///
+    /// Returns a tuple of the pairs and the offset right after the last one.
+    fn deserialize_pairs(
+        bytes: &Bytes,
+        depth: usize,
+    ) -> Result<(Vec<(Value, Value)>, usize), RESPError> {
+        let (Some(num_pairs), offset) = Self::parse_len(bytes)? else {
+            return Err(RESPError::NegativeLength);
+        };
+        let (elements, offset) = Self::deserialize_elements(bytes, offset, 2 * num_pairs, depth)?;
+        let mut elements = elements.into_iter();
+        let mut pairs = Vec::with_capacity(num_pairs);
+        while let (Some(key), Some(value)) = (elements.next(), elements.next()) {
+            pairs.push((key, value));
+        }
+        Ok((pairs, offset))
     }
 
     /// Returns a tuple of deserialized error string contents and length of the complete raw error string in bytes.
@@ -294,6 +437,524 @@ impl Message {
         };
         Ok((Value::Error(value), bytes_read))
     }
+
+    /// Returns a tuple of deserialized RESP3 null and the length of the complete raw null in bytes.
+    ///
+    /// Example: `_\r\n` => `(Value::Null, 3)`
+    fn deserialize_null(bytes: &Bytes) -> Result<(Value, usize), RESPError> {
+        let mut cursor = Cursor::new(bytes);
+        cursor.read_u8()?;
+        match cursor.read_line()? {
+            b"" => Ok((Value::Null, cursor.position())),
+            line => Err(RESPError::InvalidValue(Self::lossy(line))),
+        }
+    }
+
+    /// Returns a tuple of deserialized RESP3 boolean and the length of the complete raw boolean in bytes.
+    ///
+    /// Examples:
+    /// - `#t\r\n` => `(true, 4)`
+    /// - `#f\r\n` => `(false, 4)`
+    fn deserialize_boolean(bytes: &Bytes) -> Result<(Value, usize), RESPError> {
+        let mut cursor = Cursor::new(bytes);
+        cursor.read_u8()?;
+        match cursor.read_line()? {
+            b"t" => Ok((Value::Boolean(true), cursor.position())),
+            b"f" => Ok((Value::Boolean(false), cursor.position())),
+            line => Err(RESPError::InvalidValue(Self::lossy(line))),
+        }
+    }
+
+    /// Returns a tuple of deserialized RESP3 double and the length of the complete raw double in bytes.
+    ///
+    /// Examples:
+    /// - `,1.23\r\n` => `(1.23, 7)`
+    /// - `,-inf\r\n` => `(f64::NEG_INFINITY, 7)`
+    fn deserialize_double(bytes: &Bytes) -> Result<(Value, usize), RESPError> {
+        let mut cursor = Cursor::new(bytes);
+        cursor.read_u8()?;
+        let line = cursor.read_line()?;
+        let value = std::str::from_utf8(line)
+            .ok()
+            .and_then(|line| line.parse::<f64>().ok())
+            .ok_or_else(|| RESPError::InvalidValue(Self::lossy(line)))?;
+        Ok((Value::Double(value), cursor.position()))
+    }
+
+    /// Returns a tuple of deserialized RESP3 big number and the length of the complete raw big number in bytes.
+    ///
+    /// Example: `(-123456789012345678901234567890\r\n` => `("-123456789012345678901234567890", 34)`
+    fn deserialize_big_number(bytes: &Bytes) -> Result<(Value, usize), RESPError> {
+        let mut cursor = Cursor::new(bytes);
+        cursor.read_u8()?;
+        let line = cursor.read_line()?;
+        let digits = line
+            .strip_prefix(b"-")
+            .or(line.strip_prefix(b"+"))
+            .unwrap_or(line);
+        if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+            return Err(RESPError::IntegerParseError(Self::lossy(line)));
+        }
+        Ok((Value::BigNumber(bytes.slice_ref(line)), cursor.position()))
+    }
+
+    /// Returns a tuple of deserialized RESP3 bulk error and the length of the complete raw bulk error in bytes.
+    ///
+    /// Example: `!21\r\nSYNTAX invalid syntax\r\n` => `("SYNTAX invalid syntax", 28)`
+    fn deserialize_bulk_error(bytes: &Bytes) -> Result<(Value, usize), RESPError> {
+        match Self::deserialize_bulk_string(bytes)? {
+            (Value::BulkString(error), bytes_read) => Ok((Value::BulkError(error), bytes_read)),
+            _ => Err(RESPError::NegativeLength),
+        }
+    }
+
+    /// Returns a tuple of deserialized RESP3 verbatim string and the length of the complete raw string in bytes.
+    ///
+    /// Example: `=15\r\ntxt:Some string\r\n` => `(("txt", "Some string"), 22)`
+    fn deserialize_verbatim_string(bytes: &Bytes) -> Result<(Value, usize), RESPError> {
+        let (Value::BulkString(contents), bytes_read) = Self::deserialize_bulk_string(bytes)?
+        else {
+            return Err(RESPError::NegativeLength);
+        };
+        if contents.len() < 4 || contents[3] != b':' {
+            return Err(RESPError::InvalidValue(Self::lossy(&contents)));
+        }
+        let encoding = [contents[0], contents[1], contents[2]];
+        Ok((
+            Value::VerbatimString(encoding, contents.slice(4..)),
+            bytes_read,
+        ))
+    }
+
+    /// Returns a tuple of deserialized RESP3 map and the length of the complete raw map in bytes.
+    ///
+    /// Example: `%2\r\n+first\r\n:1\r\n+second\r\n:2\r\n` => `({"first": 1, "second": 2}, 34)`
+    fn deserialize_map(bytes: &Bytes, depth: usize) -> Result<(Value, usize), RESPError> {
+        let (pairs, offset) = Self::deserialize_pairs(bytes, depth)?;
+        Ok((Value::Map(pairs), offset))
+    }
+
+    /// Returns a tuple of deserialized RESP3 attribute, together with the value that follows it,
+    /// and the length of both of them in bytes.
+    ///
+    /// Example: `|1\r\n+ttl\r\n:3600\r\n+OK\r\n` => `(({"ttl": 3600}, "OK"), 24)`
+    fn deserialize_attribute(bytes: &Bytes, depth: usize) -> Result<(Value, usize), RESPError> {
+        let (pairs, offset) = Self::deserialize_pairs(bytes, depth)?;
+        let (msg, bytes_read) = Message::deserialize_nested(&bytes.slice(offset..), depth + 1)?;
+        Ok((
+            Value::Attribute(pairs, Box::new(msg.data)),
+            offset + bytes_read,
+        ))
+    }
+
+    /// Returns a tuple of deserialized RESP3 set and the length of the complete raw set in bytes.
+    ///
+    /// Example: `~2\r\n:1\r\n:2\r\n` => `([1, 2], 12)`
+    fn deserialize_set(bytes: &Bytes, depth: usize) -> Result<(Value, usize), RESPError> {
+        let (Some(num_elts), offset) = Self::parse_len(bytes)? else {
+            return Err(RESPError::NegativeLength);
+        };
+        let (result, offset) = Self::deserialize_elements(bytes, offset, num_elts, depth)?;
+        Ok((Value::Set(result), offset))
+    }
+
+    /// Returns a tuple of deserialized RESP3 push and the length of the complete raw push in bytes.
+    ///
+    /// Example: `>2\r\n+message\r\n+hello\r\n` => `(["message", "hello"], 24)`
+    fn deserialize_push(bytes: &Bytes, depth: usize) -> Result<(Value, usize), RESPError> {
+        let (Some(num_elts), offset) = Self::parse_len(bytes)? else {
+            return Err(RESPError::NegativeLength);
+        };
+        let (result, offset) = Self::deserialize_elements(bytes, offset, num_elts, depth)?;
+        Ok((Value::Push(result), offset))
+    }
+
+    /// Returns the bytes as a string, for error messages
+    fn lossy(bytes: &[u8]) -> String {
+        String::from_utf8_lossy(bytes).to_string()
+    }
+
+    /// Checks whether the byte stream starts with a complete request frame.
+    ///
+    /// A request frame is a flat RESP array of bulk strings, the same as Redis accepts it; any other
+    /// element type is rejected as soon as it's seen, so nested aggregates are never traversed.
+    ///
+    /// Returns `Some(length of the frame in bytes)` in case it does, or `None` in case more bytes are needed
+    /// for the frame to be complete.
+    ///
+    /// Unlike [`Message::deserialize`], this doesn't allocate.
+    ///
+    /// The check resumes from `progress`, which was left by the previous check of the same, then incomplete, frame,
+    /// and `progress` is updated as the frame is checked. A frame that arrives over many reads is thus checked
+    /// in time linear in its length, rather than from its beginning on every read.
+    ///
+    /// Examples:
+    /// - `b"*1\r\n$4\r\nPING\r\n*1\r\n$4\r\nPI"` => `Some(14)`
+    /// - `b"*2\r\n$3\r\nGET\r\n"` => `None`
+    ///
+    /// # Errors
+    /// - [`RESPError::QueryBufferLimit`] in case the frame is still incomplete after [`PROTO_MAX_QUERYBUF_LEN`] bytes
+    /// - Returns an error in case the frame is malformed, regardless of whether it is complete or not.
+    pub(crate) fn frame_len(
+        bytes: &[u8],
+        progress: &mut FrameProgress,
+    ) -> Result<Option<usize>, RESPError> {
+        let mut cursor = Cursor::new(bytes);
+        match cursor.skip_request(progress) {
+            Ok(()) => Ok(Some(cursor.position())),
+            Err(RESPError::Incomplete) if bytes.len() > PROTO_MAX_QUERYBUF_LEN => {
+                Err(RESPError::QueryBufferLimit)
+            }
+            Err(RESPError::Incomplete) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Incremental decoder of RESP frames
+///
+/// A single read from a socket doesn't necessarily correspond to a single request.
+/// A large request can be split across multiple TCP segments, and multiple pipelined requests
+/// can arrive in a single segment.
+///
+/// A request is either a RESP array or an inline command, which is a single line of text.
+///
+/// The decoder keeps a per-connection buffer which the incoming bytes are appended to.
+/// It hands back complete frames one by one, in the order in which they were received,
+/// and keeps any trailing partial frame in the buffer until the rest of it arrives.
+#[derive(Debug, Default)]
+pub(crate) struct FrameDecoder {
+    buf: BytesMut,
+    /// How far the frame at the beginning of the buffer has been checked, while it's incomplete
+    progress: FrameProgress,
+}
+
+/// How far an incomplete request frame has been checked, so that the check can resume from there once more bytes
+/// arrive, the same as Redis keeps `multibulklen` and `bulklen` for a partial request
+#[derive(Debug, Default)]
+pub(crate) struct FrameProgress {
+    /// The number of bytes that have been checked: the array header, the complete elements and, with
+    /// [`FrameProgress::bulk_len`], the header of the next element
+    checked: usize,
+    /// The number of elements that are still expected, once the array header has been read
+    remaining: Option<usize>,
+    /// The length of the next element, once its header has been read
+    bulk_len: Option<usize>,
+}
+
+impl FrameDecoder {
+    /// Creates a new decoder with an empty buffer of the given initial capacity
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: BytesMut::with_capacity(capacity),
+            progress: FrameProgress::default(),
+        }
+    }
+
+    /// Returns the internal buffer, so that the incoming bytes can be appended to it.
+    pub(crate) fn buffer_mut(&mut self) -> &mut BytesMut {
+        &mut self.buf
+    }
+
+    /// Takes the next complete frame out of the buffer.
+    ///
+    /// Returns `None` in case more bytes are needed, in which case the buffer is left intact.
+    ///
+    /// # Errors
+    /// - Returns an error in case the buffered frame is malformed.
+    ///   The buffer's state is then unspecified and the connection should be closed.
+    pub(crate) fn decode(&mut self) -> Result<Option<Bytes>, RESPError> {
+        // Requests are RESP arrays; anything else is an inline command.
+        let len = match self.buf.first() {
+            None => None,
+            Some(b'*') => Message::frame_len(&self.buf, &mut self.progress)?,
+            Some(_) => Message::inline_frame_len(&self.buf)?,
+        };
+        match len {
+            Some(len) => {
+                self.progress = FrameProgress::default();
+                Ok(Some(self.buf.split_to(len).freeze()))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// A bounds-checked cursor over a byte stream
+///
+/// Every read checks the remaining length first, so a truncated or malformed frame results in a typed
+/// [`RESPError`] rather than in reading past the end of the buffer.
+///
+/// The cursor never allocates. Strings are returned as sub-slices of the underlying byte stream,
+/// which [`Bytes::slice_ref`] then turns into zero-copy [`Bytes`].
+#[derive(Debug)]
+pub(crate) struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// Creates a cursor that points to the beginning of the byte stream
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    /// Returns the number of bytes read so far
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the current byte without advancing the cursor.
+    ///
+    /// # Errors
+    /// - [`RESPError::Incomplete`] in case there are no more bytes
+    pub(crate) fn peek(&self) -> Result<u8, RESPError> {
+        self.bytes
+            .get(self.pos)
+            .copied()
+            .ok_or(RESPError::Incomplete)
+    }
+
+    /// Returns the current byte and advances the cursor past it.
+    ///
+    /// # Errors
+    /// - [`RESPError::Incomplete`] in case there are no more bytes
+    pub(crate) fn read_u8(&mut self) -> Result<u8, RESPError> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    /// Returns the bytes up to the next `CRLF`, and advances the cursor past the `CRLF`.
+    ///
+    /// Meant for simple strings and errors, which can't contain `CR` or `LF`.
+    ///
+    /// # Errors
+    /// - [`RESPError::Incomplete`] in case `CRLF` hasn't been received yet
+    /// - [`RESPError::LFExcess`] in case there is an `LF` before the `CR`
+    /// - [`RESPError::LFMissing`] in case the `CR` isn't followed by an `LF`
+    pub(crate) fn read_line(&mut self) -> Result<&'a [u8], RESPError> {
+        let rest = &self.bytes[self.pos..];
+        let Some(cr_pos) = memchr(b'\r', rest) else {
+            return match memchr(b'\n', rest) {
+                Some(_) => Err(RESPError::LFExcess),
+                None => Err(RESPError::Incomplete),
+            };
+        };
+        let line = &rest[..cr_pos];
+        if memchr(b'\n', line).is_some() {
+            return Err(RESPError::LFExcess);
+        }
+        self.pos += cr_pos + 1;
+        match self.read_u8()? {
+            b'\n' => Ok(line),
+            _ => Err(RESPError::LFMissing),
+        }
+    }
+
+    /// Parses a `CRLF`-terminated signed, base-10, 64-bit integer, and advances the cursor past the `CRLF`.
+    ///
+    /// An optional `+` or `-` sign is accepted.
+    ///
+    /// The integer is parsed with a single operation per character while at the same time scanning for the
+    /// `CR` character, but unlike the reference algorithm, every character is bounds-checked and so is the value.
+    ///
+    /// https://redis.io/docs/latest/develop/reference/protocol-spec/#high-performance-parser-for-the-redis-protocol
+    ///
+    /// # Errors
+    /// - [`RESPError::Incomplete`] in case the byte stream ends before `CRLF`
+    /// - [`RESPError::IntegerParseError`] in case there are no digits or a non-digit is found
+    /// - [`RESPError::IntegerOverflow`] in case the value doesn't fit in an [`i64`]
+    /// - [`RESPError::LFMissing`] in case the `CR` isn't followed by an `LF`
+    pub(crate) fn read_integer(&mut self) -> Result<i64, RESPError> {
+        let start = self.pos;
+        let negative = match self.peek()? {
+            b'-' => {
+                self.pos += 1;
+                true
+            }
+            b'+' => {
+                self.pos += 1;
+                false
+            }
+            _ => false,
+        };
+        let digits_start = self.pos;
+
+        let mut value: i64 = 0;
+        loop {
+            let byte = self.read_u8()?;
+            match byte {
+                b'0'..=b'9' => {
+                    let digit = (byte - b'0') as i64;
+                    // Accumulating negative numbers as negative allows for `i64::MIN`.
+                    value = value
+                        .checked_mul(10)
+                        .and_then(|value| {
+                            if negative {
+                                value.checked_sub(digit)
+                            } else {
+                                value.checked_add(digit)
+                            }
+                        })
+                        .ok_or_else(|| RESPError::IntegerOverflow(self.lossy_from(start)))?;
+                }
+                b'\r' if self.pos - 1 > digits_start => break,
+                _ => return Err(RESPError::IntegerParseError(self.lossy_from(start))),
+            }
+        }
+
+        match self.read_u8()? {
+            b'\n' => Ok(value),
+            _ => Err(RESPError::LFMissing),
+        }
+    }
+
+    /// Parses the length of an aggregate type (for example, a bulk string or an array),
+    /// and advances the cursor past the `CRLF`.
+    ///
+    /// The length is parsed as an [`i64`], and it is either `None` for `-1` or some non-negative integer.
+    ///
+    /// Length of `-1` carries a special meaning in RESP, to represent null bulk strings or arrays.
+    /// It is the only allowed negative length, so any other negative length is rejected as soon as it's seen,
+    /// without waiting for the rest of the header.
+    ///
+    /// # Errors
+    /// - [`RESPError::NegativeLength`] in case the length is negative, but not `-1`
+    /// - The same errors as [`Cursor::read_integer`]
+    pub(crate) fn read_len(&mut self) -> Result<Option<usize>, RESPError> {
+        if self.peek()? == b'-' {
+            for expected in [b'-', b'1', b'\r'] {
+                if self.read_u8()? != expected {
+                    return Err(RESPError::NegativeLength);
+                }
+            }
+            return match self.read_u8()? {
+                b'\n' => Ok(None),
+                _ => Err(RESPError::LFMissing),
+            };
+        }
+
+        let len = self.read_integer()?;
+        usize::try_from(len)
+            .map(Some)
+            .map_err(|_| RESPError::NegativeLength)
+    }
+
+    /// Returns the next `len` bytes, and advances the cursor past them and past the `CRLF` that must follow them.
+    ///
+    /// Meant for bulk strings, which can contain any bytes, including `CR` and `LF`.
+    ///
+    /// # Errors
+    /// - [`RESPError::InvalidBulkLength`] in case `len` is larger than [`PROTO_MAX_BULK_LEN`]
+    /// - [`RESPError::Incomplete`] in case the byte stream ends before the contents and the `CRLF` do
+    /// - [`RESPError::CRLFNotAtEnd`] in case the contents aren't followed by `CRLF`
+    pub(crate) fn read_bulk(&mut self, len: usize) -> Result<&'a [u8], RESPError> {
+        if len > PROTO_MAX_BULK_LEN {
+            return Err(RESPError::InvalidBulkLength(len));
+        }
+        let end = self.pos + len;
+        if self.bytes.len() < end + 2 {
+            return Err(RESPError::Incomplete);
+        }
+        if self.bytes[end..end + 2].ne(b"\r\n") {
+            return Err(RESPError::CRLFNotAtEnd);
+        }
+        let contents = &self.bytes[self.pos..end];
+        self.pos = end + 2;
+        Ok(contents)
+    }
+
+    /// Advances the cursor past the next complete request, an array of bulk strings,
+    /// without parsing it into a [`Value`].
+    ///
+    /// The check starts where `progress` says the previous check of the same request stopped,
+    /// and `progress` is updated after every complete part of the request.
+    ///
+    /// # Errors
+    /// - [`RESPError::NotArray`] in case the request isn't an array
+    /// - [`RESPError::InvalidMultibulkLength`] in case the array is longer than [`PROTO_MAX_MULTIBULK_LEN`]
+    /// - [`RESPError::ExpectedBulk`] in case an element isn't a bulk string
+    /// - [`RESPError::NegativeLength`] in case an element is a null bulk string
+    /// - The same errors as the other read methods
+    pub(crate) fn skip_request(&mut self, progress: &mut FrameProgress) -> Result<(), RESPError> {
+        self.pos = progress.checked;
+        let remaining = match progress.remaining {
+            Some(remaining) => remaining,
+            None => {
+                if self.read_u8()? != b'*' {
+                    return Err(RESPError::NotArray);
+                }
+                // A null array is a complete request without elements.
+                let num_elts = self.read_len()?.unwrap_or(0);
+                if num_elts > PROTO_MAX_MULTIBULK_LEN {
+                    return Err(RESPError::InvalidMultibulkLength);
+                }
+                num_elts
+            }
+        };
+        progress.remaining = Some(remaining);
+        progress.checked = self.pos;
+
+        for remaining in (0..remaining).rev() {
+            let len = match progress.bulk_len {
+                Some(len) => len,
+                None => {
+                    match self.read_u8()? {
+                        b'$' => {}
+                        other => return Err(RESPError::ExpectedBulk(other as char)),
+                    }
+                    let Some(len) = self.read_len()? else {
+                        return Err(RESPError::NegativeLength);
+                    };
+                    progress.bulk_len = Some(len);
+                    progress.checked = self.pos;
+                    len
+                }
+            };
+            self.read_bulk(len)?;
+            progress.bulk_len = None;
+            progress.remaining = Some(remaining);
+            progress.checked = self.pos;
+        }
+        Ok(())
+    }
+
+    /// Returns the bytes read since `start` as a string, for error messages
+    fn lossy_from(&self, start: usize) -> String {
+        String::from_utf8_lossy(&self.bytes[start..self.pos]).to_string()
+    }
+}
+
+/// RESP protocol version of a connection
+///
+/// New connections start in RESP2 mode, and can switch to RESP3 and back with the `HELLO` command.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Protocol {
+    #[default]
+    RESP2 = 2,
+    RESP3 = 3,
+}
+
+/// Formats a double the way Redis does in its replies.
+///
+/// Infinities are `inf` and `-inf`, and not-a-number is `nan`. Very large and very small numbers use
+/// the exponent notation, e.g., `1e+20`, and all other numbers use the shortest representation that
+/// round-trips, e.g., `2.5`.
+pub(crate) fn format_double(d: f64) -> String {
+    if d.is_nan() {
+        "nan".to_string()
+    } else if d.is_infinite() {
+        if d > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else if d != 0.0 && (d.abs() >= 1e17 || d.abs() < 1e-4) {
+        let formatted = format!("{d:e}");
+        match formatted.split_once('e') {
+            Some((mantissa, exp)) if !exp.starts_with('-') => format!("{mantissa}e+{exp}"),
+            _ => formatted,
+        }
+    } else {
+        format!("{d}")
+    }
 }
 
 /// Denotes a RESP type of a Redis message.
@@ -407,6 +1068,72 @@ pub(crate) enum RESPType {
     ///
     /// The client should raise an exception when it receives an Error reply.
     Error = b'-',
+
+    /// The null data type represents non-existent values. It was introduced in RESP3.
+    ///
+    /// It replaces the RESP2 null bulk string and null array, which it is downgraded to for RESP2 clients.
+    ///
+    /// Example: `_\r\n`
+    Null = b'_',
+
+    /// RESP3 boolean
+    ///
+    /// `#<t|f>\r\n`
+    ///
+    /// Examples: `#t\r\n`, `#f\r\n`
+    Boolean = b'#',
+
+    /// RESP3 double; a `CRLF`-terminated base-10 floating point number
+    ///
+    /// `,[<+|->]<integral>[.<fractional>][<E|e>[sign]<exponent>]\r\n`
+    ///
+    /// Positive and negative infinity are `inf` and `-inf`, while not-a-number is `nan`.
+    ///
+    /// Examples: `,1.23\r\n`, `,10\r\n`, `,inf\r\n`
+    Double = b',',
+
+    /// RESP3 big number; an integer that may exceed the range of 64-bit integers
+    ///
+    /// `([+|-]<number>\r\n`
+    ///
+    /// Example: `(3492890328409238509324850943850943825024385\r\n`
+    BigNumber = b'(',
+
+    /// RESP3 bulk error; a binary-safe error, encoded like a bulk string
+    ///
+    /// `!<length>\r\n<error>\r\n`
+    ///
+    /// Example: `!21\r\nSYNTAX invalid syntax\r\n`
+    BulkError = b'!',
+
+    /// RESP3 verbatim string; a bulk string whose contents start with a three-byte encoding and a colon
+    ///
+    /// `=<length>\r\n<encoding>:<data>\r\n`
+    ///
+    /// Example: `=15\r\ntxt:Some string\r\n`
+    VerbatimString = b'=',
+
+    /// RESP3 map; a sequence of key-value pairs, where the length is the number of pairs
+    ///
+    /// `%<number-of-entries>\r\n<key-1><value-1>...<key-n><value-n>`
+    ///
+    /// Example: `%2\r\n+first\r\n:1\r\n+second\r\n:2\r\n` <=> `{"first": 1, "second": 2}`
+    Map = b'%',
+
+    /// RESP3 attribute; like a map, but it carries auxiliary information about the value that follows it
+    ///
+    /// `|<number-of-entries>\r\n<key-1><value-1>...<key-n><value-n><value>`
+    Attribute = b'|',
+
+    /// RESP3 set; like an array, but its elements are unordered and unique
+    ///
+    /// `~<number-of-elements>\r\n<element-1>...<element-n>`
+    Set = b'~',
+
+    /// RESP3 push; like an array, but it's sent out-of-band, rather than as a reply to a command
+    ///
+    /// `><number-of-elements>\r\n<element-1>...<element-n>`
+    Push = b'>',
 }
 
 /// In case we'd like to print [`RESPType`] as raw byte, i.e., as [`u8`].
@@ -434,6 +1161,16 @@ impl TryFrom<u8> for RESPType {
             b':' => Ok(RESPType::Integer),
             b'*' => Ok(RESPType::Array),
             b'-' => Ok(RESPType::Error),
+            b'_' => Ok(RESPType::Null),
+            b'#' => Ok(RESPType::Boolean),
+            b',' => Ok(RESPType::Double),
+            b'(' => Ok(RESPType::BigNumber),
+            b'!' => Ok(RESPType::BulkError),
+            b'=' => Ok(RESPType::VerbatimString),
+            b'%' => Ok(RESPType::Map),
+            b'|' => Ok(RESPType::Attribute),
+            b'~' => Ok(RESPType::Set),
+            b'>' => Ok(RESPType::Push),
             v => Err(RESPError::UnsupportedRESPType(v)),
         }
     }
@@ -555,6 +1292,181 @@ pub(crate) enum Value {
     ///
     /// The client should raise an exception when it receives an Error reply.
     Error(Bytes),
+
+    /// RESP3 null. See [`RESPType::Null`].
+    Null,
+
+    /// RESP3 boolean. See [`RESPType::Boolean`].
+    Boolean(bool),
+
+    /// RESP3 double. See [`RESPType::Double`].
+    Double(f64),
+
+    /// RESP3 big number. See [`RESPType::BigNumber`].
+    ///
+    /// We only keep the digits, with an optional sign, as we don't need to calculate with big numbers.
+    BigNumber(Bytes),
+
+    /// RESP3 bulk error. See [`RESPType::BulkError`].
+    BulkError(Bytes),
+
+    /// RESP3 verbatim string. See [`RESPType::VerbatimString`].
+    ///
+    /// Holds the three-byte encoding, such as `txt` or `mkd`, and the data, without the colon.
+    VerbatimString([u8; 3], Bytes),
+
+    /// RESP3 map. See [`RESPType::Map`].
+    Map(Vec<(Value, Value)>),
+
+    /// RESP3 attribute and the value it describes. See [`RESPType::Attribute`].
+    Attribute(Vec<(Value, Value)>, Box<Value>),
+
+    /// RESP3 set. See [`RESPType::Set`].
+    Set(Vec<Value>),
+
+    /// RESP3 push. See [`RESPType::Push`].
+    Push(Vec<Value>),
+}
+
+impl Value {
+    /// The `OK` simple string, which is the reply of many commands on success
+    pub(crate) const OK: Value = Value::SimpleString(Bytes::from_static(b"OK"));
+
+    /// Serializes (encodes) the value and appends it to the byte buffer.
+    ///
+    /// This is the inverse of [`Message::deserialize`]. Bulk strings are written as-is, with their length
+    /// prefixed, so they are binary-safe.
+    ///
+    /// RESP3-only types are downgraded to their RESP2 shapes in case the client hasn't switched to RESP3:
+    /// - null => null bulk string
+    /// - boolean => integer `1` or `0`
+    /// - double, big number and verbatim string => bulk string
+    /// - bulk error => simple error
+    /// - map => flat array of keys and values
+    /// - set and push => array
+    /// - attribute => only the value it describes
+    ///
+    /// In RESP3, both RESP2 nulls, the null bulk string and the null array, are written as the null, as Redis does it.
+    ///
+    /// Examples:
+    /// - `Value::SimpleString("OK")` => `+OK\r\n`
+    /// - `Value::BulkString("hello")` => `$5\r\nhello\r\n`
+    /// - `Value::NullBulkString` => `$-1\r\n` in RESP2, `_\r\n` in RESP3
+    /// - `Value::Array([1, "hello"])` => `*2\r\n:1\r\n$5\r\nhello\r\n`
+    /// - `Value::Map({"proto": 3})` => `%1\r\n$5\r\nproto\r\n:3\r\n` in RESP3, and
+    ///   `*2\r\n$5\r\nproto\r\n:3\r\n` in RESP2
+    pub(crate) fn write_to(&self, buf: &mut BytesMut, protocol: Protocol) {
+        let resp2 = protocol == Protocol::RESP2;
+        match self {
+            Value::SimpleString(s) => Self::write_line(buf, RESPType::SimpleString, s),
+            Value::Error(e) => Self::write_line(buf, RESPType::Error, e),
+            Value::Integer(i) => Self::write_header(buf, RESPType::Integer, *i),
+            Value::BulkString(b) => Self::write_bulk(buf, RESPType::BulkString, b),
+            Value::NullBulkString if resp2 => Self::write_header(buf, RESPType::BulkString, -1),
+            Value::Array(array) => Self::write_aggregate(buf, RESPType::Array, array, protocol),
+            Value::NullArray if resp2 => Self::write_header(buf, RESPType::Array, -1),
+            Value::Null if resp2 => Self::write_header(buf, RESPType::BulkString, -1),
+            // RESP3 has a single null type, which replaces both RESP2 nulls.
+            Value::NullBulkString | Value::NullArray | Value::Null => {
+                Self::write_line(buf, RESPType::Null, b"")
+            }
+            Value::Boolean(b) if resp2 => Self::write_header(buf, RESPType::Integer, *b as i64),
+            Value::Boolean(b) => {
+                Self::write_line(buf, RESPType::Boolean, if *b { b"t" } else { b"f" })
+            }
+            Value::Double(d) if resp2 => {
+                Self::write_bulk(buf, RESPType::BulkString, format_double(*d).as_bytes())
+            }
+            Value::Double(d) => {
+                Self::write_line(buf, RESPType::Double, format_double(*d).as_bytes())
+            }
+            Value::BigNumber(n) if resp2 => Self::write_bulk(buf, RESPType::BulkString, n),
+            Value::BigNumber(n) => Self::write_line(buf, RESPType::BigNumber, n),
+            Value::BulkError(e) if resp2 => {
+                // Simple errors can't contain CR or LF.
+                let e: Vec<u8> = e
+                    .iter()
+                    .map(|&c| if c == b'\r' || c == b'\n' { b' ' } else { c })
+                    .collect();
+                Self::write_line(buf, RESPType::Error, &e)
+            }
+            Value::BulkError(e) => Self::write_bulk(buf, RESPType::BulkError, e),
+            Value::VerbatimString(_, text) if resp2 => {
+                Self::write_bulk(buf, RESPType::BulkString, text)
+            }
+            Value::VerbatimString(encoding, text) => {
+                Self::write_header(buf, RESPType::VerbatimString, 4 + text.len() as i64);
+                buf.put_slice(encoding);
+                buf.put_u8(b':');
+                buf.put_slice(text);
+                buf.put_slice(b"\r\n");
+            }
+            Value::Map(pairs) => {
+                if resp2 {
+                    Self::write_header(buf, RESPType::Array, 2 * pairs.len() as i64);
+                } else {
+                    Self::write_header(buf, RESPType::Map, pairs.len() as i64);
+                }
+                Self::write_pairs(buf, pairs, protocol);
+            }
+            Value::Attribute(pairs, value) => {
+                if !resp2 {
+                    Self::write_header(buf, RESPType::Attribute, pairs.len() as i64);
+                    Self::write_pairs(buf, pairs, protocol);
+                }
+                value.write_to(buf, protocol);
+            }
+            Value::Set(set) if resp2 => Self::write_aggregate(buf, RESPType::Array, set, protocol),
+            Value::Set(set) => Self::write_aggregate(buf, RESPType::Set, set, protocol),
+            Value::Push(push) if resp2 => {
+                Self::write_aggregate(buf, RESPType::Array, push, protocol)
+            }
+            Value::Push(push) => Self::write_aggregate(buf, RESPType::Push, push, protocol),
+        }
+    }
+
+    /// Writes a RESP type followed by the length, the contents and `CRLF`, which is used for bulk types.
+    fn write_bulk(buf: &mut BytesMut, resp_type: RESPType, contents: &[u8]) {
+        Self::write_header(buf, resp_type, contents.len() as i64);
+        buf.put_slice(contents);
+        buf.put_slice(b"\r\n");
+    }
+
+    /// Writes a RESP type followed by the number of elements and the elements, which is used for aggregate types.
+    fn write_aggregate(
+        buf: &mut BytesMut,
+        resp_type: RESPType,
+        elements: &[Value],
+        protocol: Protocol,
+    ) {
+        Self::write_header(buf, resp_type, elements.len() as i64);
+        for value in elements {
+            value.write_to(buf, protocol);
+        }
+    }
+
+    /// Writes the key-value pairs of a map or an attribute, without the header.
+    fn write_pairs(buf: &mut BytesMut, pairs: &[(Value, Value)], protocol: Protocol) {
+        for (key, value) in pairs {
+            key.write_to(buf, protocol);
+            value.write_to(buf, protocol);
+        }
+    }
+
+    /// Writes a RESP type followed by an integer and `CRLF`, which is used for integers and lengths.
+    fn write_header(buf: &mut BytesMut, resp_type: RESPType, n: i64) {
+        buf.put_u8(resp_type.into());
+        // Writing to a `BytesMut` can't fail, as it grows as needed.
+        let _ = write!(buf, "{n}");
+        buf.put_slice(b"\r\n");
+    }
+
+    /// Writes a RESP type followed by the contents and `CRLF`, which is used for simple strings and errors.
+    fn write_line(buf: &mut BytesMut, resp_type: RESPType, contents: &[u8]) {
+        buf.put_u8(resp_type.into());
+        buf.put_slice(contents);
+        buf.put_slice(b"\r\n");
+    }
 }
 
 #[cfg(test)]
@@ -625,6 +1537,76 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_len_truncated() {
+        for input in [&b"*12"[..], b"*12\r", b"$", b"$-1\r", b"*"] {
+            if let Err(RESPError::Incomplete) = Message::parse_len(input) {
+            } else {
+                assert_eq!(0, 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_len_overflow() {
+        let input = Bytes::copy_from_slice(b"*99999999999999999999\r\n");
+        if let Err(RESPError::IntegerOverflow(_)) = Message::parse_len(&input) {
+        } else {
+            assert_eq!(0, 1);
+        }
+    }
+
+    #[test]
+    fn test_parse_len_missing_digits() {
+        let input = Bytes::copy_from_slice(b"*\r\n");
+        if let Err(RESPError::IntegerParseError(_)) = Message::parse_len(&input) {
+        } else {
+            assert_eq!(0, 1);
+        }
+    }
+
+    #[test]
+    fn test_cursor_read_integer_extremes() {
+        let input = b":-9223372036854775808\r\n:9223372036854775807\r\n";
+        let mut cursor = Cursor::new(input);
+        cursor.read_u8().unwrap();
+        assert_eq!(i64::MIN, cursor.read_integer().unwrap());
+        cursor.read_u8().unwrap();
+        assert_eq!(i64::MAX, cursor.read_integer().unwrap());
+        assert_eq!(input.len(), cursor.position());
+    }
+
+    #[test]
+    fn test_cursor_read_bulk_too_large() {
+        let input = b"$1000000000000\r\n";
+        let mut cursor = Cursor::new(input);
+        cursor.read_u8().unwrap();
+        let len = cursor.read_len().unwrap().unwrap();
+        if let Err(RESPError::InvalidBulkLength(_)) = cursor.read_bulk(len) {
+        } else {
+            assert_eq!(0, 1);
+        }
+    }
+
+    #[test]
+    fn test_deserialize_truncated_bulk_string() {
+        let input = Bytes::copy_from_slice(b"$12\r\nHello");
+        if let Err(RESPError::Incomplete) = Message::deserialize(&input) {
+        } else {
+            assert_eq!(0, 1);
+        }
+    }
+
+    #[test]
+    fn test_deserialize_bulk_string_is_zero_copy() {
+        let input = Bytes::copy_from_slice(b"$5\r\nHello\r\n");
+        let (msg, _) = Message::deserialize(&input).unwrap();
+        let Value::BulkString(contents) = msg.data else {
+            panic!("Expected a bulk string");
+        };
+        assert_eq!(input[4..9].as_ptr(), contents.as_ptr());
+    }
+
     #[test]
     fn test_deserialize_simple_string_empty() {
         let input = Bytes::copy_from_slice(b"+\r\n");
@@ -700,7 +1682,7 @@ mod tests {
     #[test]
     fn test_deserialize_array_empty() {
         let input = Bytes::copy_from_slice(b"*0\r\n");
-        let result = Message::deserialize_array(&input).unwrap();
+        let result = Message::deserialize_array(&input, 0).unwrap();
         let v = vec![];
         let expected = (Value::Array(v), 4);
         assert_eq!(expected, result);
@@ -709,7 +1691,7 @@ mod tests {
     #[test]
     fn test_deserialize_array_ping() {
         let input = Bytes::copy_from_slice(b"*1\r\n$4\r\nPING\r\n");
-        let result = Message::deserialize_array(&input).unwrap();
+        let result = Message::deserialize_array(&input, 0).unwrap();
         let v = vec![Value::BulkString(Bytes::copy_from_slice(b"PING"))];
         let expected = (Value::Array(v), 14);
         assert_eq!(expected, result);
@@ -718,7 +1700,7 @@ mod tests {
     #[test]
     fn test_deserialize_array_ping_with_arg() {
         let input = Bytes::copy_from_slice(b"*2\r\n$4\r\nPING\r\n$5\r\nHello\r\n");
-        let result = Message::deserialize_array(&input).unwrap();
+        let result = Message::deserialize_array(&input, 0).unwrap();
         let v = vec![
             Value::BulkString(Bytes::copy_from_slice(b"PING")),
             Value::BulkString(Bytes::copy_from_slice(b"Hello")),
@@ -730,7 +1712,7 @@ mod tests {
     #[test]
     fn test_deserialize_array_echo() {
         let input = Bytes::copy_from_slice(b"*2\r\n$4\r\nECHO\r\n$5\r\nHello\r\n");
-        let result = Message::deserialize_array(&input).unwrap();
+        let result = Message::deserialize_array(&input, 0).unwrap();
         let v = vec![
             Value::BulkString(Bytes::copy_from_slice(b"ECHO")),
             Value::BulkString(Bytes::copy_from_slice(b"Hello")),
@@ -742,7 +1724,7 @@ mod tests {
     #[test]
     fn test_deserialize_array_two_elts() {
         let input = Bytes::copy_from_slice(b"*2\r\n$5\r\nhello\r\n$5\r\nworld\r\n");
-        let result = Message::deserialize_array(&input).unwrap();
+        let result = Message::deserialize_array(&input, 0).unwrap();
         let v = vec![
             Value::BulkString(Bytes::copy_from_slice(b"hello")),
             Value::BulkString(Bytes::copy_from_slice(b"world")),
@@ -754,7 +1736,7 @@ mod tests {
     #[test]
     fn test_deserialize_array_integers() {
         let input = Bytes::copy_from_slice(b"*3\r\n:1\r\n:-2\r\n:3\r\n");
-        let result = Message::deserialize_array(&input).unwrap();
+        let result = Message::deserialize_array(&input, 0).unwrap();
         let v = vec![Value::Integer(1), Value::Integer(-2), Value::Integer(3)];
         let expected = (Value::Array(v), 17);
         assert_eq!(expected, result);
@@ -763,7 +1745,7 @@ mod tests {
     #[test]
     fn test_deserialize_array_mixed_data_types() {
         let input = Bytes::copy_from_slice(b"*5\r\n:1\r\n:2\r\n:3\r\n:4\r\n$5\r\nhello\r\n");
-        let result = Message::deserialize_array(&input).unwrap();
+        let result = Message::deserialize_array(&input, 0).unwrap();
         let v = vec![
             Value::Integer(1),
             Value::Integer(2),
@@ -785,7 +1767,7 @@ mod tests {
     fn test_deserialize_array_nested() {
         let input =
             Bytes::copy_from_slice(b"*2\r\n*3\r\n:1\r\n:2\r\n:3\r\n*2\r\n+Hello\r\n-World\r\n");
-        let result = Message::deserialize_array(&input).unwrap();
+        let result = Message::deserialize_array(&input, 0).unwrap();
         let v = vec![
             Value::Array(vec![
                 Value::Integer(1),
@@ -804,7 +1786,7 @@ mod tests {
     #[test]
     fn test_deserialize_array_null() {
         let input = Bytes::copy_from_slice(b"*-1\r\n");
-        let result = Message::deserialize_array(&input).unwrap();
+        let result = Message::deserialize_array(&input, 0).unwrap();
         let expected = (Value::NullArray, 5);
         assert_eq!(expected, result);
     }
@@ -812,7 +1794,7 @@ mod tests {
     #[test]
     fn test_deserialize_array_with_null_elt() {
         let input = Bytes::copy_from_slice(b"*3\r\n$5\r\nhello\r\n$-1\r\n$5\r\nworld\r\n");
-        let result = Message::deserialize_array(&input).unwrap();
+        let result = Message::deserialize_array(&input, 0).unwrap();
         let v = vec![
             Value::BulkString(Bytes::copy_from_slice(b"hello")),
             Value::NullBulkString,
@@ -849,4 +1831,357 @@ mod tests {
         let expected = (Value::Array(v), 40);
         assert_eq!(expected, result);
     }
+
+    #[test]
+    fn test_frame_len_complete() {
+        let input = b"*2\r\n$4\r\nECHO\r\n$3\r\nHey\r\n";
+        let result = frame_len(input).unwrap();
+        assert_eq!(Some(input.len()), result);
+    }
+
+    #[test]
+    fn test_frame_len_incomplete() {
+        for input in [
+            &b""[..],
+            b"*",
+            b"*2\r",
+            b"*2\r\n$4\r\nECHO\r\n",
+            b"*2\r\n$4\r\nECHO\r\n$3\r\nHe",
+            b"*2\r\n$4\r\nECHO\r\n$3\r\nHey\r",
+        ] {
+            assert_eq!(None, frame_len(input).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_frame_len_bulk_string_containing_crlf() {
+        let input = b"*1\r\n$4\r\n\r\n\r\n\r\n";
+        let result = frame_len(input).unwrap();
+        assert_eq!(Some(14), result);
+    }
+
+    #[test]
+    fn test_frame_len_bulk_string_wrong_terminator() {
+        let input = b"*1\r\n$3\r\nHeyyy\r\n";
+        if let Err(RESPError::CRLFNotAtEnd) = frame_len(input) {
+        } else {
+            assert_eq!(0, 1);
+        }
+    }
+
+    #[test]
+    fn test_frame_len_only_flat_arrays_of_bulk_strings() {
+        // Nested arrays are rejected right away, without traversing them.
+        let nested = b"*1\r\n".repeat(200_000);
+        assert!(matches!(
+            frame_len(&nested),
+            Err(RESPError::ExpectedBulk('*'))
+        ));
+        assert!(matches!(
+            frame_len(b"*2\r\n$3\r\nGET\r\n:1\r\n"),
+            Err(RESPError::ExpectedBulk(':'))
+        ));
+        assert!(matches!(
+            frame_len(b"*1\r\n$-1\r\n"),
+            Err(RESPError::NegativeLength)
+        ));
+        assert!(matches!(
+            frame_len(b"*4294967296\r\n"),
+            Err(RESPError::InvalidMultibulkLength)
+        ));
+        assert_eq!(Some(5), frame_len(b"*-1\r\n").unwrap());
+    }
+
+    #[test]
+    fn test_deserialize_nesting_too_deep() {
+        let nested = Bytes::from(b"*1\r\n".repeat(200_000));
+        assert!(matches!(
+            Message::deserialize(&nested),
+            Err(RESPError::NestingTooDeep)
+        ));
+        let mut input = b"*1\r\n".repeat(PROTO_MAX_NESTING_DEPTH);
+        input.extend_from_slice(b":1\r\n");
+        assert!(Message::deserialize(&Bytes::from(input)).is_ok());
+    }
+
+    #[test]
+    fn test_frame_decoder_split_across_reads() {
+        let mut decoder = FrameDecoder::default();
+        decoder
+            .buffer_mut()
+            .extend_from_slice(b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$11\r\nval");
+        assert_eq!(None, decoder.decode().unwrap());
+        decoder.buffer_mut().extend_from_slice(b"ue_01234\r\n");
+        let expected = Bytes::from("*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$11\r\nvalue_01234\r\n");
+        assert_eq!(Some(expected), decoder.decode().unwrap());
+        assert_eq!(None, decoder.decode().unwrap());
+    }
+
+    #[test]
+    fn test_frame_decoder_coalesced_frames() {
+        let mut decoder = FrameDecoder::default();
+        decoder.buffer_mut().extend_from_slice(
+            b"*1\r\n$4\r\nPING\r\n*2\r\n$4\r\nECHO\r\n$3\r\nHey\r\n*1\r\n$4\r\nPI",
+        );
+        let first = Bytes::from("*1\r\n$4\r\nPING\r\n");
+        let second = Bytes::from("*2\r\n$4\r\nECHO\r\n$3\r\nHey\r\n");
+        assert_eq!(Some(first), decoder.decode().unwrap());
+        assert_eq!(Some(second), decoder.decode().unwrap());
+        assert_eq!(None, decoder.decode().unwrap());
+        decoder.buffer_mut().extend_from_slice(b"NG\r\n");
+        let third = Bytes::from("*1\r\n$4\r\nPING\r\n");
+        assert_eq!(Some(third), decoder.decode().unwrap());
+    }
+
+    #[test]
+    fn test_frame_decoder_resumes_partial_frames() {
+        let input = b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n*1\r\n$4\r\nPING\r\n";
+        let mut decoder = FrameDecoder::default();
+        let mut frames = vec![];
+        for &byte in input {
+            decoder.buffer_mut().extend_from_slice(&[byte]);
+            frames.extend(decoder.decode().unwrap());
+        }
+        assert_eq!(
+            vec![
+                Bytes::from_static(&input[..33]),
+                Bytes::from_static(&input[33..])
+            ],
+            frames
+        );
+
+        // The header, `SET` and the header of `key` aren't checked again.
+        let mut decoder = FrameDecoder::default();
+        decoder.buffer_mut().extend_from_slice(&input[..20]);
+        assert_eq!(None, decoder.decode().unwrap());
+        assert_eq!(17, decoder.progress.checked);
+        assert_eq!(Some(2), decoder.progress.remaining);
+        assert_eq!(Some(3), decoder.progress.bulk_len);
+        decoder.buffer_mut().extend_from_slice(&input[20..]);
+        assert_eq!(
+            Some(Bytes::from_static(&input[..33])),
+            decoder.decode().unwrap()
+        );
+        assert_eq!(0, decoder.progress.checked);
+    }
+
+    fn frame_len(bytes: &[u8]) -> Result<Option<usize>, RESPError> {
+        Message::frame_len(bytes, &mut FrameProgress::default())
+    }
+
+    fn serialize(value: &Value) -> Bytes {
+        serialize_as(value, Protocol::RESP3)
+    }
+
+    fn serialize_as(value: &Value, protocol: Protocol) -> Bytes {
+        let mut buf = BytesMut::new();
+        value.write_to(&mut buf, protocol);
+        buf.freeze()
+    }
+
+    #[test]
+    fn test_serialize_simple_types() {
+        assert_eq!(Bytes::from("+OK\r\n"), serialize(&Value::OK));
+        assert_eq!(
+            Bytes::from("-ERR oops\r\n"),
+            serialize(&Value::Error(Bytes::from("ERR oops")))
+        );
+        assert_eq!(Bytes::from(":-1000\r\n"), serialize(&Value::Integer(-1000)));
+    }
+
+    #[test]
+    fn test_serialize_nulls() {
+        for (value, resp2) in [
+            (Value::NullBulkString, "$-1\r\n"),
+            (Value::NullArray, "*-1\r\n"),
+            (Value::Null, "$-1\r\n"),
+        ] {
+            assert_eq!(Bytes::from(resp2), serialize_as(&value, Protocol::RESP2));
+            assert_eq!(Bytes::from("_\r\n"), serialize_as(&value, Protocol::RESP3));
+        }
+    }
+
+    #[test]
+    fn test_serialize_bulk_string_binary() {
+        let value = Value::BulkString(Bytes::from_static(b"\x00\r\n\xff"));
+        assert_eq!(
+            Bytes::from_static(b"$4\r\n\x00\r\n\xff\r\n"),
+            serialize(&value)
+        );
+    }
+
+    #[test]
+    fn test_serialize_deserialize_round_trip() {
+        let input = Bytes::copy_from_slice(
+            b"*3\r\n*3\r\n:1\r\n:2\r\n:3\r\n*2\r\n+Hello\r\n-World\r\n$0\r\n\r\n",
+        );
+        let (msg, _) = Message::deserialize(&input).unwrap();
+        assert_eq!(input, serialize(&msg.data));
+    }
+
+    #[test]
+    fn test_deserialize_resp3_simple_types() {
+        let cases = [
+            (&b"_\r\n"[..], Value::Null),
+            (b"#t\r\n", Value::Boolean(true)),
+            (b"#f\r\n", Value::Boolean(false)),
+            (b",1.23\r\n", Value::Double(1.23)),
+            (b",-inf\r\n", Value::Double(f64::NEG_INFINITY)),
+            (
+                b"(-3492890328409238509324850943850943825024385\r\n",
+                Value::BigNumber(Bytes::from("-3492890328409238509324850943850943825024385")),
+            ),
+            (
+                b"!21\r\nSYNTAX invalid syntax\r\n",
+                Value::BulkError(Bytes::from("SYNTAX invalid syntax")),
+            ),
+            (
+                b"=15\r\ntxt:Some string\r\n",
+                Value::VerbatimString(*b"txt", Bytes::from("Some string")),
+            ),
+        ];
+        for (input, expected) in cases {
+            let input = Bytes::copy_from_slice(input);
+            let (msg, bytes_read) = Message::deserialize(&input).unwrap();
+            assert_eq!(expected, msg.data);
+            assert_eq!(input.len(), bytes_read);
+            assert_eq!(input, serialize(&msg.data));
+        }
+    }
+
+    #[test]
+    fn test_deserialize_resp3_invalid_boolean() {
+        let input = Bytes::copy_from_slice(b"#x\r\n");
+        if let Err(RESPError::InvalidValue(_)) = Message::deserialize(&input) {
+        } else {
+            assert_eq!(0, 1);
+        }
+    }
+
+    #[test]
+    fn test_deserialize_resp3_aggregates() {
+        let input = Bytes::copy_from_slice(
+            b"%2\r\n+first\r\n~2\r\n:1\r\n:2\r\n+second\r\n|1\r\n+ttl\r\n:3600\r\n>1\r\n#t\r\n",
+        );
+        let (msg, bytes_read) = Message::deserialize(&input).unwrap();
+        let expected = Value::Map(vec![
+            (
+                Value::SimpleString(Bytes::from("first")),
+                Value::Set(vec![Value::Integer(1), Value::Integer(2)]),
+            ),
+            (
+                Value::SimpleString(Bytes::from("second")),
+                Value::Attribute(
+                    vec![(
+                        Value::SimpleString(Bytes::from("ttl")),
+                        Value::Integer(3600),
+                    )],
+                    Box::new(Value::Push(vec![Value::Boolean(true)])),
+                ),
+            ),
+        ]);
+        assert_eq!(expected, msg.data);
+        assert_eq!(input.len(), bytes_read);
+        assert_eq!(input, serialize(&msg.data));
+    }
+
+    #[test]
+    fn test_serialize_resp3_downgraded_to_resp2() {
+        let value = Value::Map(vec![
+            (Value::BulkString(Bytes::from("null")), Value::Null),
+            (Value::BulkString(Bytes::from("bool")), Value::Boolean(true)),
+            (Value::BulkString(Bytes::from("double")), Value::Double(1.5)),
+            (
+                Value::BulkString(Bytes::from("set")),
+                Value::Set(vec![Value::VerbatimString(*b"txt", Bytes::from("hi"))]),
+            ),
+            (
+                Value::BulkString(Bytes::from("error")),
+                Value::BulkError(Bytes::from("ERR a\r\nb")),
+            ),
+        ]);
+        let expected = Bytes::from(
+            "*10\r\n$4\r\nnull\r\n$-1\r\n$4\r\nbool\r\n:1\r\n$6\r\ndouble\r\n$3\r\n1.5\r\n\
+             $3\r\nset\r\n*1\r\n$2\r\nhi\r\n$5\r\nerror\r\n-ERR a  b\r\n",
+        );
+        assert_eq!(expected, serialize_as(&value, Protocol::RESP2));
+    }
+
+    #[test]
+    fn test_format_double() {
+        assert_eq!("2.5", format_double(2.5));
+        assert_eq!("10", format_double(10.0));
+        assert_eq!("1e+20", format_double(1e20));
+        assert_eq!("1.5e-5", format_double(1.5e-5));
+        assert_eq!("-inf", format_double(f64::NEG_INFINITY));
+        assert_eq!("nan", format_double(f64::NAN));
+    }
+
+    fn inline_args(input: &str) -> Vec<Value> {
+        let (msg, bytes_read) =
+            Message::deserialize_inline(&Bytes::from(input.to_string())).unwrap();
+        assert_eq!(input.len(), bytes_read);
+        let Value::Array(args) = msg.data else {
+            panic!("Expected an array");
+        };
+        args
+    }
+
+    #[test]
+    fn test_deserialize_inline_same_as_multibulk() {
+        let inline = inline_args("SET foo \"bar baz\"\r\n");
+        let input = Bytes::from("*3\r\n$3\r\nSET\r\n$3\r\nfoo\r\n$7\r\nbar baz\r\n");
+        let (msg, _) = Message::deserialize(&input).unwrap();
+        assert_eq!(Value::Array(inline), msg.data);
+    }
+
+    #[test]
+    fn test_deserialize_inline_escapes() {
+        let args = inline_args("ECHO 'it\\'s' \"\\x41\\n\\\"\" \"\\xZZ\" a\"b c\"\t\n");
+        let expected = ["ECHO", "it's", "A\n\"", "xZZ", "ab c"]
+            .into_iter()
+            .map(|arg| Value::BulkString(Bytes::from(arg)))
+            .collect::<Vec<_>>();
+        assert_eq!(expected, args);
+    }
+
+    #[test]
+    fn test_deserialize_inline_vertical_tab() {
+        let args = inline_args("SET\x0bfoo \x0c\"bar\"\x0b\r\n");
+        let expected = ["SET", "foo", "bar"]
+            .into_iter()
+            .map(|arg| Value::BulkString(Bytes::from(arg)))
+            .collect::<Vec<_>>();
+        assert_eq!(expected, args);
+    }
+
+    #[test]
+    fn test_deserialize_inline_empty_line() {
+        assert!(inline_args("\r\n").is_empty());
+    }
+
+    #[test]
+    fn test_deserialize_inline_unbalanced_quotes() {
+        for input in ["SET foo \"bar\r\n", "SET foo 'bar'baz\n"] {
+            let input = Bytes::from(input);
+            if let Err(RESPError::UnbalancedQuotes) = Message::deserialize_inline(&input) {
+            } else {
+                assert_eq!(0, 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_frame_decoder_inline() {
+        let mut decoder = FrameDecoder::default();
+        decoder
+            .buffer_mut()
+            .extend_from_slice(b"PING\r\n*1\r\n$4\r\nPING\r\nECHO hey");
+        assert_eq!(Some(Bytes::from("PING\r\n")), decoder.decode().unwrap());
+        let expected = Bytes::from("*1\r\n$4\r\nPING\r\n");
+        assert_eq!(Some(expected), decoder.decode().unwrap());
+        assert_eq!(None, decoder.decode().unwrap());
+        decoder.buffer_mut().extend_from_slice(b"\n");
+        assert_eq!(Some(Bytes::from("ECHO hey\n")), decoder.decode().unwrap());
+    }
 }
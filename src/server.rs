@@ -5,9 +5,8 @@ use crate::conn::handle_connection;
 use crate::constants::{ExitCode, CONNECTION_PERMIT_TIMEOUT_MS};
 use crate::constants::{LOCAL_SOCKET_ADDR_STR, SHUTDOWN_TIME_MS};
 use crate::errors::ServerError;
-use crate::log_and_stderr;
 use crate::storage::generic::Crud;
-use crate::types::{ConcurrentStorageType, ExpirationTime, StorageKey};
+use crate::types::ConcurrentDatabases;
 use anyhow::Result;
 use log::{debug, error, info, warn};
 use std::fmt::Debug;
@@ -23,24 +22,16 @@ use tokio::time::timeout;
 pub struct Server<KV, KE> {
     listener: TcpListener,
     max_conn: Arc<Semaphore>,
-    storage: ConcurrentStorageType<KV, KE>,
+    databases: ConcurrentDatabases<KV, KE>,
 }
 
-impl<
-        KV: 'static + Crud + Send + Sync + Debug,
-        KE: 'static
-            + Clone
-            + Crud
-            + Send
-            + Sync
-            + Debug
-            + IntoIterator<Item = (StorageKey, ExpirationTime)>,
-    > Server<KV, KE>
+impl<KV: 'static + Crud + Send + Sync + Debug, KE: 'static + Crud + Send + Sync + Debug>
+    Server<KV, KE>
 {
     /// Create an instance of the Redis server
     pub async fn new(
         args: Args,
-        storage: ConcurrentStorageType<KV, KE>,
+        databases: ConcurrentDatabases<KV, KE>,
     ) -> Result<Self, ServerError> {
         let port = args.port;
         let max_conn = args.max_conn;
@@ -54,7 +45,7 @@ impl<
         Ok(Self {
             listener,
             max_conn,
-            storage,
+            databases,
         })
     }
 
@@ -71,25 +62,24 @@ impl<
     async fn core_loop(&self) -> Result<(), ServerError> {
         debug!("Starting the core loop...");
         info!("Waiting for requests...");
-        let storage = &self.storage;
+        let databases = &self.databases;
 
         Self::shutdown(SHUTDOWN_TIME_MS).await;
 
         loop {
             match self.acquire_socket_permit().await {
                 Ok((mut socket, permit)) => {
-                    let storage = Arc::clone(storage);
+                    let databases = Arc::clone(databases);
 
                     // A new task is spawned for each inbound socket. The socket is moved to the new task and processed there.
                     tokio::spawn(async move {
                         // Process each socket (stream) concurrently.
                         // Each connection can process multiple successive requests (commands) from the same client.
-                        handle_connection(storage, &mut socket)
-                            .await
-                            .map_err(|e| {
-                                warn!("{e}");
-                            })
-                            .expect("Failed to handle request");
+                        // An error only ends this connection, and it has already been reported to the client
+                        // if it could be, so it's just logged here.
+                        if let Err(e) = handle_connection(databases, &mut socket).await {
+                            warn!("{e}");
+                        }
                         // Drop socket while the permit is still alive.
                         drop(socket);
                         // Drop the permit so more tasks can be created.
@@ -0,0 +1,338 @@
+//! Cursor-based iteration, for the `SCAN` family of commands
+//!
+//! - [SCAN](https://redis.io/docs/latest/commands/scan/)
+//!
+//! Redis guarantees that a full iteration returns every element that is present from its start to its end,
+//! even if the collection grows, shrinks or is rehashed in the meantime. Our collections don't expose a stable
+//! iteration position, so elements are instead ordered by a stable 64-bit hash of their keys.
+//! A cursor is a position in that order: each call returns the elements with the lowest hashes at or after
+//! the cursor, and the next cursor follows the highest hash returned.
+//!
+//! An element's hash never changes, so an element that is present for the whole iteration is returned exactly once.
+//! Elements with equal hashes are always returned together, so they can't be split between two calls.
+//!
+//! [`ScanMap`] and [`ScanSet`] keep their keys ordered by position in an index, which is updated on every
+//! insertion and removal, so that a call takes O(count + log n) time, instead of visiting all elements.
+
+use bytes::Bytes;
+use std::collections::hash_map::{self, DefaultHasher};
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+
+/// Returns the position of a key in the cursor order.
+///
+/// The hasher is created with fixed keys, so the position is the same for the lifetime of the server.
+pub fn position(key: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Selects about `count` of the `items`, which are paired with their positions, starting at `cursor`.
+///
+/// Returns the next cursor, which is 0 when the iteration is complete, and the selected items.
+/// More than `count` items are returned if several share the position of the last one.
+///
+/// All items are visited, so this is meant for collections that don't keep a position index.
+pub fn scan<T>(
+    items: impl IntoIterator<Item = (u64, T)>,
+    cursor: u64,
+    count: usize,
+) -> (u64, Vec<T>) {
+    let count = count.max(1);
+    let mut candidates = items
+        .into_iter()
+        .filter(|(position, _)| *position >= cursor)
+        .collect::<Vec<_>>();
+    if candidates.len() <= count {
+        return (0, candidates.into_iter().map(|(_, item)| item).collect());
+    }
+
+    candidates.select_nth_unstable_by_key(count - 1, |(position, _)| *position);
+    let last = candidates[count - 1].0;
+    let selected = candidates
+        .into_iter()
+        .filter(|(position, _)| *position <= last)
+        .map(|(_, item)| item)
+        .collect();
+
+    // Wrapping around to 0 after the highest possible position also means that the iteration is complete.
+    (last.wrapping_add(1), selected)
+}
+
+/// A hash map with byte string keys, which also keeps its keys ordered by their positions,
+/// so that it can be scanned without visiting all of its elements
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScanMap<V> {
+    entries: HashMap<Bytes, V>,
+    positions: BTreeSet<(u64, Bytes)>,
+}
+
+impl<V> ScanMap<V> {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            positions: BTreeSet::new(),
+        }
+    }
+
+    /// Creates an empty map with space for at least `capacity` elements in its hash table.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::with_capacity(capacity),
+            positions: BTreeSet::new(),
+        }
+    }
+
+    /// Returns the number of elements.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the map has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns whether the map has an element with the given key.
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Returns a reference to the value of the given key.
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        self.entries.get(key)
+    }
+
+    /// Returns a mutable reference to the value of the given key.
+    pub fn get_mut(&mut self, key: &[u8]) -> Option<&mut V> {
+        self.entries.get_mut(key)
+    }
+
+    /// Inserts a key-value pair, and returns the previous value of the key, if any.
+    pub fn insert(&mut self, key: Bytes, value: V) -> Option<V> {
+        match self.entries.entry(key) {
+            hash_map::Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            hash_map::Entry::Vacant(entry) => {
+                self.positions
+                    .insert((position(entry.key()), entry.key().clone()));
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    /// Removes a key, and returns its value, if it existed.
+    pub fn remove(&mut self, key: &[u8]) -> Option<V> {
+        let (key, value) = self.entries.remove_entry(key)?;
+        self.positions.remove(&(position(&key), key));
+        Some(value)
+    }
+
+    /// Iterates over the key-value pairs, in no particular order.
+    pub fn iter(&self) -> hash_map::Iter<'_, Bytes, V> {
+        self.entries.iter()
+    }
+
+    /// Iterates over the keys, in no particular order.
+    pub fn keys(&self) -> hash_map::Keys<'_, Bytes, V> {
+        self.entries.keys()
+    }
+
+    /// Selects about `count` key-value pairs, starting at `cursor`, the same as [`scan`] does it.
+    ///
+    /// Only the selected keys are visited, plus the first one after them, if any.
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<(&Bytes, &V)>) {
+        let count = count.max(1);
+        let mut selected = vec![];
+        let mut last = cursor;
+        // The empty key is the lowest one, so the range starts with the first key at the cursor's position.
+        for (position, key) in self.positions.range((cursor, Bytes::new())..) {
+            if selected.len() >= count && *position != last {
+                // There's a higher position left, so `last` can't be the highest possible one.
+                return (last + 1, selected);
+            }
+            last = *position;
+            selected.extend(self.entries.get_key_value(key));
+        }
+        (0, selected)
+    }
+}
+
+impl<V> Default for ScanMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> FromIterator<(Bytes, V)> for ScanMap<V> {
+    fn from_iter<I: IntoIterator<Item = (Bytes, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<'a, V> IntoIterator for &'a ScanMap<V> {
+    type Item = (&'a Bytes, &'a V);
+    type IntoIter = hash_map::Iter<'a, Bytes, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A hash set of byte strings, which also keeps its members ordered by their positions,
+/// so that it can be scanned without visiting all of its members
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScanSet(ScanMap<()>);
+
+impl ScanSet {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Self(ScanMap::new())
+    }
+
+    /// Creates an empty set with space for at least `capacity` members in its hash table.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(ScanMap::with_capacity(capacity))
+    }
+
+    /// Returns the number of members.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether the set has no members.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns whether the given member is in the set.
+    pub fn contains(&self, member: &[u8]) -> bool {
+        self.0.contains_key(member)
+    }
+
+    /// Adds a member, and returns whether it was newly added.
+    pub fn insert(&mut self, member: Bytes) -> bool {
+        self.0.insert(member, ()).is_none()
+    }
+
+    /// Removes a member, and returns whether it was in the set.
+    pub fn remove(&mut self, member: &[u8]) -> bool {
+        self.0.remove(member).is_some()
+    }
+
+    /// Iterates over the members, in no particular order.
+    pub fn iter(&self) -> hash_map::Keys<'_, Bytes, ()> {
+        self.0.keys()
+    }
+
+    /// Selects about `count` members, starting at `cursor`, the same as [`ScanMap::scan`] does it.
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<&Bytes>) {
+        let (cursor, members) = self.0.scan(cursor, count);
+        (
+            cursor,
+            members.into_iter().map(|(member, _)| member).collect(),
+        )
+    }
+}
+
+impl FromIterator<Bytes> for ScanSet {
+    fn from_iter<I: IntoIterator<Item = Bytes>>(iter: I) -> Self {
+        Self(iter.into_iter().map(|member| (member, ())).collect())
+    }
+}
+
+impl<'a> IntoIterator for &'a ScanSet {
+    type Item = &'a Bytes;
+    type IntoIter = hash_map::Keys<'a, Bytes, ()>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scans the whole map with the given count, and returns the keys in the order they were returned.
+    fn scan_all<V>(map: &ScanMap<V>, count: usize) -> Vec<Bytes> {
+        let mut keys = vec![];
+        let mut cursor = 0;
+        loop {
+            let (next, selected) = map.scan(cursor, count);
+            keys.extend(selected.into_iter().map(|(key, _)| key.clone()));
+            if next == 0 {
+                return keys;
+            }
+            assert!(next > cursor);
+            cursor = next;
+        }
+    }
+
+    #[test]
+    fn scan_map_matches_unindexed_scan() {
+        let map = (0..100)
+            .map(|i| (Bytes::from(format!("key:{i}")), i))
+            .collect::<ScanMap<_>>();
+        for count in [1, 7, 100, 1000] {
+            let mut cursor = 0;
+            loop {
+                let (next, mut selected) = map.scan(cursor, count);
+                let (expected_next, mut expected) = scan(
+                    map.iter().map(|(key, value)| (position(key), (key, value))),
+                    cursor,
+                    count,
+                );
+                selected.sort();
+                expected.sort();
+                assert_eq!((expected_next, expected), (next, selected));
+                if next == 0 {
+                    break;
+                }
+                cursor = next;
+            }
+        }
+
+        let mut keys = scan_all(&map, 3);
+        keys.sort();
+        let mut expected = map.keys().cloned().collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(expected, keys);
+    }
+
+    #[test]
+    fn scan_map_keeps_the_index_up_to_date() {
+        let mut map = ScanMap::new();
+        assert_eq!(None, map.insert(Bytes::from("a"), 1));
+        assert_eq!(Some(1), map.insert(Bytes::from("a"), 2));
+        assert_eq!(None, map.insert(Bytes::from("b"), 3));
+        assert_eq!(Some(3), map.remove(b"b".as_slice()));
+        assert_eq!(None, map.remove(b"b".as_slice()));
+        assert_eq!(1, map.len());
+        assert_eq!(vec![Bytes::from("a")], scan_all(&map, 10));
+
+        let mut set = ScanSet::new();
+        assert!(set.insert(Bytes::from("m")));
+        assert!(!set.insert(Bytes::from("m")));
+        assert!(set.remove(b"m"));
+        assert!(set.is_empty());
+        assert_eq!((0, vec![]), set.scan(0, 10));
+    }
+
+    #[test]
+    fn scan_map_after_the_last_position() {
+        let map = [(Bytes::from("a"), ())].into_iter().collect::<ScanMap<_>>();
+        let position = position(b"a");
+        assert_eq!(1, map.scan(position, 1).1.len());
+        assert_eq!(0, map.scan(position, 1).0);
+        if position < u64::MAX {
+            assert_eq!((0, vec![]), map.scan(position + 1, 1));
+        }
+    }
+}
@@ -18,8 +18,10 @@
 //! for which they are `SET`, and not for all keys in the form of `None` or similar.
 //!
 //! - From [EXPIRE](https://redis.io/docs/latest/commands/expire/):
-//!     "Normally, Redis keys are created without an associated time to live."
+//!   "Normally, Redis keys are created without an associated time to live."
 
+use crate::storage::cursor;
+use crate::storage::metadata::Metadata;
 use crate::types::{ExpirationTime, StorageKey, StorageValue};
 
 /// Trait: Generic storage - Data Abstraction Layer (DAL)
@@ -50,12 +52,19 @@ where
 }
 
 /// Trait CRUD: Create, Read, Update, Delete
-pub trait Crud {
+///
+/// Stores are [`Send`], so that a whole store can be freed in a background thread.
+pub trait Crud: Send {
     /// Create an element
     fn create(&mut self, key: &StorageKey, value: StorageValue, expiry: ExpirationTime);
 
+    /// Read an element by reference, without copying its value
+    fn get(&self, key: &StorageKey) -> Option<(&StorageValue, ExpirationTime)>;
+
     /// Read an element
-    fn read(&self, key: &StorageKey) -> Option<(StorageValue, ExpirationTime)>;
+    fn read(&self, key: &StorageKey) -> Option<(StorageValue, ExpirationTime)> {
+        self.get(key).map(|(value, expiry)| (value.clone(), expiry))
+    }
 
     /// Update an element
     ///
@@ -64,6 +73,88 @@ pub trait Crud {
         self.create(key, value, expiry);
     }
 
+    /// Modify the value of an existing element in place, and return the result of `f`
+    ///
+    /// The expiration time is left as it is. Returns `None` if the element doesn't exist.
+    /// Stores that can't modify values in place read the value, and then write it back.
+    fn modify<T>(&mut self, key: &StorageKey, f: impl FnOnce(&mut StorageValue) -> T) -> Option<T>
+    where
+        Self: Sized,
+    {
+        let (mut value, expiry) = self.read(key)?;
+        let result = f(&mut value);
+        self.update(key, value, expiry);
+        Some(result)
+    }
+
     /// Delete an element
     fn delete(&mut self, key: &StorageKey);
+
+    /// List the keys of all elements, in no particular order
+    fn keys(&self) -> Vec<StorageKey>;
+
+    /// Count all elements
+    fn size(&self) -> usize {
+        self.keys().len()
+    }
+
+    /// Delete all elements, and return them in a store of their own
+    fn take(&mut self) -> Self
+    where
+        Self: Sized;
+
+    /// List about `count` keys, starting at `cursor`, and return the cursor to continue from,
+    /// which is 0 when all keys have been listed
+    ///
+    /// See [`cursor`] for the guarantees.
+    fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<StorageKey>) {
+        cursor::scan(
+            self.keys()
+                .into_iter()
+                .map(|key| (cursor::position(&key), key)),
+            cursor,
+            count,
+        )
+    }
+
+    /// Delete an element, and return it
+    fn remove(&mut self, key: &StorageKey) -> Option<(StorageValue, ExpirationTime)> {
+        let entry = self.read(key);
+        self.delete(key);
+        entry
+    }
+
+    /// Set or clear the expiration time of an existing element, without rewriting its value
+    ///
+    /// Does nothing if the element doesn't exist.
+    fn set_expiry(&mut self, key: &StorageKey, expiry: ExpirationTime) {
+        if let Some((value, _)) = self.read(key) {
+            self.update(key, value, expiry);
+        }
+    }
+
+    /// Record an access of an element in its metadata, such as its last access time
+    ///
+    /// Stores that don't keep metadata ignore it.
+    fn touch(&self, _key: &StorageKey) {}
+
+    /// Read the metadata of an element, without reading its value
+    ///
+    /// The access statistics in the metadata can be updated through the returned reference.
+    /// Returns `None` if the element doesn't exist, or if the store doesn't keep metadata.
+    fn metadata(&self, _key: &StorageKey) -> Option<&Metadata> {
+        None
+    }
+
+    /// Replace the metadata of an existing element, such as with the metadata it had under another key
+    ///
+    /// Stores that don't keep metadata ignore it.
+    fn set_metadata(&mut self, _key: &StorageKey, _metadata: Metadata) {}
+
+    /// Read the expiration time of an element, without reading its value
+    ///
+    /// Returns `None` if the element doesn't exist, and `Some(None)` if it exists but doesn't expire.
+    fn expiry(&self, key: &StorageKey) -> Option<ExpirationTime> {
+        self.read(key).map(|(_, expiry)| expiry)
+    }
 }
@@ -0,0 +1,675 @@
+//! HyperLogLog, stored as a string value
+//!
+//! - [HyperLogLog](https://redis.io/docs/latest/develop/data-types/probabilistic/hyperloglogs/)
+//!
+//! A HyperLogLog is a probabilistic data structure that estimates the cardinality of a set.
+//! It uses 16384 registers of 6 bits each, and has a standard error of 0.81%.
+//!
+//! The representation is byte-compatible with Redis's, so that values can be exchanged with Redis
+//! with `GET`, `SET`, `DUMP` and `RESTORE`. It starts with a 16-byte header:
+//! - the `HYLL` magic string,
+//! - the encoding: 0 for dense and 1 for sparse,
+//! - three unused bytes,
+//! - the cached cardinality, as a 64-bit little-endian integer, where the most significant bit of the last byte
+//!   being set means that the cache is invalid.
+//!
+//! The dense encoding packs the 6-bit registers one after another, starting from the least significant bits
+//! of each byte, in 12288 bytes.
+//!
+//! The sparse encoding is a run-length encoding of the registers, made of three opcodes:
+//! - `ZERO`: `00xxxxxx` - a run of 1 to 64 registers set to 0
+//! - `XZERO`: `01xxxxxx yyyyyyyy` - a run of 1 to 16384 registers set to 0, with a 14-bit length
+//! - `VAL`: `1vvvvvxx` - a run of 1 to 4 registers set to the value 1 to 32
+//!
+//! New HyperLogLogs are sparse. They are converted to dense when a register needs a value larger than 32,
+//! or when the sparse representation grows larger than [`SPARSE_MAX_BYTES`].
+
+use crate::errors::CmdError;
+
+/// Number of bits of the hash that select the register
+const P: u32 = 14;
+/// Number of registers
+pub const REGISTERS: usize = 1 << P;
+/// Number of bits of the hash that are used to count the leading zeros
+const Q: u32 = 64 - P;
+/// Width of a dense register in bits
+const BITS: usize = 6;
+/// The largest value of a dense register
+const REGISTER_MAX: u32 = (1 << BITS) - 1;
+/// Length of the header
+const HEADER_LEN: usize = 16;
+/// Length of a dense HyperLogLog, including the header
+const DENSE_LEN: usize = HEADER_LEN + (REGISTERS * BITS).div_ceil(8);
+/// The encoding byte of the dense representation
+const DENSE: u8 = 0;
+/// The encoding byte of the sparse representation
+const SPARSE: u8 = 1;
+/// The largest value of a `VAL` opcode
+const SPARSE_VAL_MAX_VALUE: u8 = 32;
+/// The longest run of a `VAL` opcode
+const SPARSE_VAL_MAX_LEN: usize = 4;
+/// The longest run of a `ZERO` opcode
+const SPARSE_ZERO_MAX_LEN: usize = 64;
+/// The longest run of an `XZERO` opcode
+const SPARSE_XZERO_MAX_LEN: usize = 16384;
+/// The largest size of a sparse HyperLogLog, including the header, above which it is converted to dense
+pub const SPARSE_MAX_BYTES: usize = 3000;
+/// The bias correction constant of the estimator
+const ALPHA_INF: f64 = 0.721_347_520_444_481_7;
+/// Seed of the hash function
+const HASH_SEED: u64 = 0xadc83b19;
+
+/// A HyperLogLog in the Redis format
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HyperLogLog(Vec<u8>);
+
+impl Default for HyperLogLog {
+    /// Creates an empty, sparse HyperLogLog.
+    fn default() -> Self {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + 2);
+        bytes.extend_from_slice(b"HYLL");
+        bytes.extend_from_slice(&[SPARSE, 0, 0, 0]);
+        bytes.extend_from_slice(&[0; 8]);
+        let mut remaining = REGISTERS;
+        while remaining > 0 {
+            let len = remaining.min(SPARSE_XZERO_MAX_LEN);
+            bytes.extend_from_slice(&xzero(len));
+            remaining -= len;
+        }
+        Self(bytes)
+    }
+}
+
+impl HyperLogLog {
+    /// Creates a HyperLogLog from a string value.
+    ///
+    /// The header is validated, and the length of the dense representation, which are reported as
+    /// [`CmdError::NotHyperLogLog`]. The sparse representation is validated as well, so that the other methods
+    /// can rely on it: its opcodes must be complete, and their runs must cover exactly all registers,
+    /// which is reported as [`CmdError::CorruptedHll`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CmdError> {
+        let valid = bytes.len() >= HEADER_LEN
+            && bytes.starts_with(b"HYLL")
+            && (bytes[4] == SPARSE || (bytes[4] == DENSE && bytes.len() == DENSE_LEN));
+        if !valid {
+            return Err(CmdError::NotHyperLogLog);
+        }
+        let hll = Self(bytes.to_vec());
+        if hll.is_sparse() {
+            let mut index = 0;
+            for (value, len) in hll.runs() {
+                value?;
+                index += len as usize;
+            }
+            if index != REGISTERS {
+                return Err(CmdError::CorruptedHll);
+            }
+        }
+        Ok(hll)
+    }
+
+    /// The string value
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+
+    /// Whether the HyperLogLog uses the sparse representation
+    pub fn is_sparse(&self) -> bool {
+        self.0[4] == SPARSE
+    }
+
+    /// Adds an element, and returns whether a register was updated.
+    ///
+    /// The cached cardinality is invalidated if a register was updated.
+    pub fn add(&mut self, element: &[u8]) -> Result<bool, CmdError> {
+        let (index, count) = pattern_len(element);
+        self.set(index, count)
+    }
+
+    /// Sets the register at `index` to `count`, if `count` is larger than the current value of the register,
+    /// and returns whether the register was updated.
+    fn set(&mut self, index: usize, count: u8) -> Result<bool, CmdError> {
+        let updated = if self.is_sparse() {
+            self.sparse_set(index, count)?
+        } else {
+            dense_set(&mut self.0[HEADER_LEN..], index, count)
+        };
+        if updated {
+            self.invalidate_cache();
+        }
+        Ok(updated)
+    }
+
+    /// Returns the cached cardinality, if it's valid.
+    pub fn cached_count(&self) -> Option<u64> {
+        let card = u64::from_le_bytes(self.0[8..HEADER_LEN].try_into().expect("8 bytes"));
+        (self.0[15] & 0x80 == 0).then_some(card)
+    }
+
+    /// Estimates the cardinality, and caches it.
+    pub fn count(&mut self) -> Result<u64, CmdError> {
+        if let Some(card) = self.cached_count() {
+            return Ok(card);
+        }
+        let card = estimate(&self.histogram()?);
+        self.0[8..HEADER_LEN].copy_from_slice(&card.to_le_bytes());
+        Ok(card)
+    }
+
+    /// Marks the cached cardinality as invalid.
+    pub fn invalidate_cache(&mut self) {
+        self.0[15] |= 0x80;
+    }
+
+    /// Merges the registers into `max`, which holds one register per byte, by keeping the larger values.
+    pub fn merge_into(&self, max: &mut [u8]) -> Result<(), CmdError> {
+        if self.is_sparse() {
+            let mut index = 0;
+            for (value, len) in self.runs() {
+                let (value, len) = (value?, len as usize);
+                if index + len > REGISTERS {
+                    return Err(CmdError::CorruptedHll);
+                }
+                if value > 0 {
+                    for register in &mut max[index..index + len] {
+                        *register = (*register).max(value);
+                    }
+                }
+                index += len;
+            }
+            if index != REGISTERS {
+                return Err(CmdError::CorruptedHll);
+            }
+        } else {
+            let registers = &self.0[HEADER_LEN..];
+            for (index, register) in max.iter_mut().enumerate() {
+                *register = (*register).max(dense_get(registers, index));
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets all registers to the values in `max`, which holds one register per byte, where larger than the current
+    /// values, and invalidates the cached cardinality.
+    ///
+    /// With `dense`, the HyperLogLog is converted to the dense representation first.
+    pub fn merge_from(&mut self, max: &[u8], dense: bool) -> Result<(), CmdError> {
+        if dense {
+            self.to_dense()?;
+        }
+        for (index, &value) in max.iter().enumerate() {
+            if value > 0 {
+                self.set(index, value)?;
+            }
+        }
+        self.invalidate_cache();
+        Ok(())
+    }
+
+    /// Converts the HyperLogLog to the dense representation, and returns whether it was sparse.
+    pub fn to_dense(&mut self) -> Result<bool, CmdError> {
+        if !self.is_sparse() {
+            return Ok(false);
+        }
+        let mut dense = vec![0; DENSE_LEN];
+        dense[..HEADER_LEN].copy_from_slice(&self.0[..HEADER_LEN]);
+        dense[4] = DENSE;
+        let mut index = 0;
+        for (value, len) in self.runs() {
+            let (value, len) = (value?, len as usize);
+            if value > 0 {
+                if index + len > REGISTERS {
+                    return Err(CmdError::CorruptedHll);
+                }
+                for i in index..index + len {
+                    dense_set(&mut dense[HEADER_LEN..], i, value);
+                }
+            }
+            index += len;
+        }
+        if index != REGISTERS {
+            return Err(CmdError::CorruptedHll);
+        }
+        self.0 = dense;
+        Ok(true)
+    }
+
+    /// Returns the values of all registers.
+    pub fn registers(&self) -> Result<Vec<u8>, CmdError> {
+        let mut registers = vec![0; REGISTERS];
+        self.merge_into(&mut registers)?;
+        Ok(registers)
+    }
+
+    /// Describes the opcodes of the sparse representation, such as `Z:100 v:3,1 z:5`, as `PFDEBUG DECODE` does it.
+    ///
+    /// Returns `None` for the dense representation.
+    pub fn decode_sparse(&self) -> Option<String> {
+        if !self.is_sparse() {
+            return None;
+        }
+        let mut decoded = vec![];
+        let mut p = HEADER_LEN;
+        while p < self.0.len() {
+            let op = self.0[p];
+            if is_zero(op) {
+                decoded.push(format!("z:{}", zero_len(op)));
+                p += 1;
+            } else if is_xzero(op) {
+                let next = self.0.get(p + 1).copied().unwrap_or_default();
+                decoded.push(format!("Z:{}", xzero_len(op, next)));
+                p += 2;
+            } else {
+                decoded.push(format!("v:{},{}", val_value(op), val_len(op)));
+                p += 1;
+            }
+        }
+        Some(decoded.join(" "))
+    }
+
+    /// Iterates over the runs of the sparse representation, as `(value, length)` pairs.
+    ///
+    /// A truncated `XZERO` opcode is reported as an error.
+    fn runs(&self) -> impl Iterator<Item = (Result<u8, CmdError>, u32)> + '_ {
+        let mut p = HEADER_LEN;
+        std::iter::from_fn(move || {
+            let op = *self.0.get(p)?;
+            Some(if is_zero(op) {
+                p += 1;
+                (Ok(0), zero_len(op) as u32)
+            } else if is_xzero(op) {
+                let next = self.0.get(p + 1).copied();
+                p += 2;
+                match next {
+                    Some(next) => (Ok(0), xzero_len(op, next) as u32),
+                    None => (Err(CmdError::CorruptedHll), 0),
+                }
+            } else {
+                p += 1;
+                (Ok(val_value(op)), val_len(op) as u32)
+            })
+        })
+    }
+
+    /// Counts the registers by their values.
+    fn histogram(&self) -> Result<[u32; 64], CmdError> {
+        let mut histogram = [0; 64];
+        if self.is_sparse() {
+            let mut index = 0;
+            for (value, len) in self.runs() {
+                histogram[value? as usize] += len;
+                index += len as usize;
+            }
+            if index != REGISTERS {
+                return Err(CmdError::CorruptedHll);
+            }
+        } else {
+            let registers = &self.0[HEADER_LEN..];
+            for index in 0..REGISTERS {
+                histogram[dense_get(registers, index) as usize] += 1;
+            }
+        }
+        Ok(histogram)
+    }
+
+    /// Sets a register of the sparse representation, the same way Redis does it, so that the resulting bytes
+    /// are the same.
+    ///
+    /// The opcode that covers the register is replaced with a sequence of up to five opcodes, and adjacent `VAL`
+    /// opcodes with the same value are then merged. The HyperLogLog is converted to the dense representation if
+    /// the value doesn't fit in a `VAL` opcode, or if the sparse representation would grow too large.
+    fn sparse_set(&mut self, index: usize, count: u8) -> Result<bool, CmdError> {
+        if count > SPARSE_VAL_MAX_VALUE {
+            return self.promote(index, count);
+        }
+
+        // Find the opcode that covers the register, and the opcode before it.
+        let mut p = HEADER_LEN;
+        let mut prev = None;
+        let mut first = 0;
+        let mut span = 0;
+        while p < self.0.len() {
+            let op = self.0[p];
+            let oplen = if is_xzero(op) { 2 } else { 1 };
+            span = if is_zero(op) {
+                zero_len(op)
+            } else if is_xzero(op) {
+                xzero_len(op, self.0.get(p + 1).copied().unwrap_or_default())
+            } else {
+                val_len(op)
+            };
+            if index < first + span {
+                break;
+            }
+            prev = Some(p);
+            p += oplen;
+            first += span;
+        }
+        if span == 0 || p >= self.0.len() {
+            return Err(CmdError::CorruptedHll);
+        }
+
+        let op = self.0[p];
+        if !is_zero(op) && !is_xzero(op) {
+            let old = val_value(op);
+            if old >= count {
+                return Ok(false);
+            }
+            if val_len(op) == 1 {
+                self.0[p] = val(count, 1);
+                self.merge_adjacent_vals(prev);
+                return Ok(true);
+            }
+        }
+        if is_zero(op) && zero_len(op) == 1 {
+            self.0[p] = val(count, 1);
+            self.merge_adjacent_vals(prev);
+            return Ok(true);
+        }
+
+        // Split the opcode into up to three runs: before the register, the register, and after the register.
+        let last = first + span - 1;
+        let mut seq = Vec::with_capacity(5);
+        let zero_run = |seq: &mut Vec<u8>, len: usize| {
+            if len > SPARSE_ZERO_MAX_LEN {
+                seq.extend_from_slice(&xzero(len));
+            } else {
+                seq.push(zero(len));
+            }
+        };
+        if is_zero(op) || is_xzero(op) {
+            if index != first {
+                zero_run(&mut seq, index - first);
+            }
+            seq.push(val(count, 1));
+            if index != last {
+                zero_run(&mut seq, last - index);
+            }
+        } else {
+            let old = val_value(op);
+            if index != first {
+                seq.push(val(old, index - first));
+            }
+            seq.push(val(count, 1));
+            if index != last {
+                seq.push(val(old, last - index));
+            }
+        }
+
+        let oldlen = if is_xzero(op) { 2 } else { 1 };
+        if p + oldlen > self.0.len() {
+            return Err(CmdError::CorruptedHll);
+        }
+        if seq.len() > oldlen && self.0.len() + seq.len() - oldlen > SPARSE_MAX_BYTES {
+            return self.promote(index, count);
+        }
+        self.0.splice(p..p + oldlen, seq);
+        self.merge_adjacent_vals(prev);
+        Ok(true)
+    }
+
+    /// Merges adjacent `VAL` opcodes with the same value, if their combined run fits in one opcode,
+    /// scanning up to five opcodes starting from `start`.
+    fn merge_adjacent_vals(&mut self, start: Option<usize>) {
+        let mut p = start.unwrap_or(HEADER_LEN);
+        let mut scan = 5;
+        while p < self.0.len() && scan > 0 {
+            scan -= 1;
+            let op = self.0[p];
+            if is_xzero(op) {
+                p += 2;
+                continue;
+            }
+            if is_zero(op) {
+                p += 1;
+                continue;
+            }
+            if let Some(&next) = self.0.get(p + 1) {
+                if !is_zero(next) && !is_xzero(next) && val_value(op) == val_value(next) {
+                    let len = val_len(op) + val_len(next);
+                    if len <= SPARSE_VAL_MAX_LEN {
+                        self.0[p + 1] = val(val_value(op), len);
+                        self.0.remove(p);
+                        // Try to merge the merged opcode with the one on its right.
+                        continue;
+                    }
+                }
+            }
+            p += 1;
+        }
+    }
+
+    /// Converts the HyperLogLog to the dense representation, and then sets the register.
+    fn promote(&mut self, index: usize, count: u8) -> Result<bool, CmdError> {
+        self.to_dense()?;
+        Ok(dense_set(&mut self.0[HEADER_LEN..], index, count))
+    }
+}
+
+/// Estimates the cardinality from a histogram of raw registers, which hold one register per byte.
+pub fn count_registers(registers: &[u8]) -> u64 {
+    let mut histogram = [0; 64];
+    for &register in registers {
+        histogram[register as usize] += 1;
+    }
+    estimate(&histogram)
+}
+
+/// Estimates the cardinality from the histogram of the registers, with the improved estimator from
+/// Otmar Ertl's "New cardinality estimation algorithms for HyperLogLog sketches", which Redis uses.
+fn estimate(histogram: &[u32; 64]) -> u64 {
+    let m = REGISTERS as f64;
+    let mut z = m * tau((m - histogram[Q as usize + 1] as f64) / m);
+    for j in (1..=Q as usize).rev() {
+        z += histogram[j] as f64;
+        z *= 0.5;
+    }
+    z += m * sigma(histogram[0] as f64 / m);
+    (ALPHA_INF * m * m / z).round() as u64
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let z_prime = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if z_prime == z {
+            return z / 3.0;
+        }
+    }
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let z_prime = z;
+        z += x * y;
+        y += y;
+        if z_prime == z {
+            return z;
+        }
+    }
+}
+
+/// Hashes an element, and returns the index of its register, and the length of the pattern `000..1` of the rest
+/// of the hash, which is the value to set the register to.
+fn pattern_len(element: &[u8]) -> (usize, u8) {
+    let hash = murmur_hash64a(element, HASH_SEED);
+    let index = (hash & (REGISTERS as u64 - 1)) as usize;
+    // Make sure that the count is at most Q + 1.
+    let hash = (hash >> P) | (1 << Q);
+    (index, hash.trailing_zeros() as u8 + 1)
+}
+
+/// MurmurHash2, 64-bit version, by Austin Appleby, as used by Redis
+///
+/// The data is read as little-endian on all platforms.
+pub fn murmur_hash64a(data: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4a7935bd1e995;
+    const R: u32 = 47;
+
+    let mut h = seed ^ (data.len() as u64).wrapping_mul(M);
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut k = u64::from_le_bytes(chunk.try_into().expect("8 bytes"));
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+    let rest = chunks.remainder();
+    if !rest.is_empty() {
+        for (i, &byte) in rest.iter().enumerate() {
+            h ^= (byte as u64) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
+
+/// Reads a register of the dense representation.
+fn dense_get(registers: &[u8], index: usize) -> u8 {
+    let byte = index * BITS / 8;
+    let fb = index * BITS % 8;
+    let b0 = registers[byte] as u32;
+    let b1 = registers.get(byte + 1).copied().unwrap_or_default() as u32;
+    (((b0 >> fb) | (b1 << (8 - fb))) & REGISTER_MAX) as u8
+}
+
+/// Sets a register of the dense representation, if `count` is larger than its current value,
+/// and returns whether it was updated.
+fn dense_set(registers: &mut [u8], index: usize, count: u8) -> bool {
+    if count <= dense_get(registers, index) {
+        return false;
+    }
+    dense_write(registers, index, count);
+    true
+}
+
+/// Overwrites a register of the dense representation.
+fn dense_write(registers: &mut [u8], index: usize, count: u8) {
+    let byte = index * BITS / 8;
+    let fb = index * BITS % 8;
+    let count = count as u32;
+    registers[byte] &= !(REGISTER_MAX << fb) as u8;
+    registers[byte] |= (count << fb) as u8;
+    if let Some(next) = registers.get_mut(byte + 1) {
+        *next &= !(REGISTER_MAX >> (8 - fb)) as u8;
+        *next |= (count >> (8 - fb)) as u8;
+    }
+}
+
+fn is_zero(op: u8) -> bool {
+    op & 0xc0 == 0
+}
+
+fn is_xzero(op: u8) -> bool {
+    op & 0xc0 == 0x40
+}
+
+fn zero_len(op: u8) -> usize {
+    (op & 0x3f) as usize + 1
+}
+
+fn xzero_len(op: u8, next: u8) -> usize {
+    (((op & 0x3f) as usize) << 8 | next as usize) + 1
+}
+
+fn val_value(op: u8) -> u8 {
+    ((op >> 2) & 0x1f) + 1
+}
+
+fn val_len(op: u8) -> usize {
+    (op & 0x3) as usize + 1
+}
+
+fn zero(len: usize) -> u8 {
+    (len - 1) as u8
+}
+
+fn xzero(len: usize) -> [u8; 2] {
+    let len = len - 1;
+    [(len >> 8) as u8 | 0x40, (len & 0xff) as u8]
+}
+
+fn val(value: u8, len: usize) -> u8 {
+    ((value - 1) << 2) | (len - 1) as u8 | 0x80
+}
+
+/// Checks the dense register encoding and the agreement between the sparse and dense representations,
+/// the same as Redis's `PFSELFTEST` does it.
+///
+/// Returns a description of the first failure.
+pub fn self_test(seed: u64) -> Result<(), String> {
+    // Test 1: setting and getting random values of all dense registers.
+    let mut rng = seed | 1;
+    let mut next = || {
+        // xorshift64
+        rng ^= rng << 13;
+        rng ^= rng >> 7;
+        rng ^= rng << 17;
+        rng
+    };
+    let mut registers = vec![0u8; DENSE_LEN - HEADER_LEN];
+    let mut expected = vec![0u8; REGISTERS];
+    for _ in 0..1000 {
+        for (index, value) in expected.iter_mut().enumerate() {
+            *value = (next() & REGISTER_MAX as u64) as u8;
+            dense_write(&mut registers, index, *value);
+        }
+        for (index, &value) in expected.iter().enumerate() {
+            if dense_get(&registers, index) != value {
+                return Err(format!("Register error at {index}"));
+            }
+        }
+    }
+
+    // Test 2: the approximation error, with the sparse and dense representations side by side.
+    let mut dense = HyperLogLog::default();
+    dense.to_dense().map_err(|err| err.to_string())?;
+    let mut sparse = HyperLogLog::default();
+    let max_error = 1.04 / (REGISTERS as f64).sqrt();
+    let mut checkpoint = 1u64;
+    for j in 1..=10_000_000u64 {
+        let element = (j ^ seed).to_le_bytes();
+        dense.add(&element).map_err(|err| err.to_string())?;
+        sparse.add(&element).map_err(|err| err.to_string())?;
+        if j != checkpoint {
+            continue;
+        }
+        if j < SPARSE_MAX_BYTES as u64 / 2 && !sparse.is_sparse() {
+            return Err("sparse encoding not used".to_string());
+        }
+        let card = dense.count().map_err(|err| err.to_string())?;
+        if card != sparse.count().map_err(|err| err.to_string())? {
+            return Err("dense/sparse disagree".to_string());
+        }
+        let abs_error = checkpoint.abs_diff(card);
+        let max = if j == 10 {
+            1
+        } else {
+            (max_error * 6.0 * checkpoint as f64).ceil() as u64
+        };
+        if abs_error > max {
+            return Err(format!("Too big error. card:{card} abserr:{abs_error}"));
+        }
+        checkpoint *= 10;
+    }
+
+    Ok(())
+}
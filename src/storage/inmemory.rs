@@ -1,11 +1,18 @@
 //! In-memory (not-persistent) representation of a CRUD storage
 
+use crate::storage::cursor::ScanMap;
 use crate::storage::generic::{Crud, SubStorage};
+use crate::storage::metadata::{Entry, Metadata};
 use crate::storage::Storage;
 use crate::types::{
     ExpirationTime, InMemoryExpiryTimeBTreeMap, InMemoryExpiryTimeHashMap, InMemoryStorage,
     InMemoryStorageHashMap, StorageKey, StorageValue,
 };
+use bytes::Bytes;
+use std::collections::BTreeMap;
+
+/// The dummy value that the expiry time stores report, as they don't keep values
+static NO_VALUE: StorageValue = StorageValue::String(Bytes::new());
 
 impl<S, KV, KE> Storage<S, KV, KE> for InMemoryStorage<KV, KE>
 where
@@ -20,32 +27,79 @@ where
 
 impl<KV: Crud, KE: Crud> Crud for InMemoryStorage<KV, KE> {
     fn create(&mut self, key: &StorageKey, value: StorageValue, expiry: ExpirationTime) {
-        self.0.create(key, value.clone(), expiry);
+        self.0.create(key, value, expiry);
         match expiry {
             // The `None` case exists to clear the expiry time in case it exists but wasn't `SET` this time around.
             None => self.1.delete(key),
             // In the `Some` case, we `SET` the new expiry time, whether it existed or not.
-            Some(_) => self.1.create(key, value, expiry),
+            // The expiry time store doesn't keep values, so it gets a cheap dummy value instead of a copy.
+            Some(_) => self.1.create(key, StorageValue::default(), expiry),
         }
     }
 
-    fn read(&self, key: &StorageKey) -> Option<(StorageValue, ExpirationTime)> {
-        match self.0.read(key) {
-            None => None,
-            Some((value, _dummy_expiry)) => {
-                let expiry = match self.1.read(key) {
-                    Some((_dummy_value, expiry)) => expiry,
-                    None => None,
-                };
-                Some((value, expiry))
-            }
-        }
+    fn get(&self, key: &StorageKey) -> Option<(&StorageValue, ExpirationTime)> {
+        let (value, _dummy_expiry) = self.0.get(key)?;
+        let expiry = self.1.expiry(key).flatten();
+        Some((value, expiry))
+    }
+
+    fn modify<T>(&mut self, key: &StorageKey, f: impl FnOnce(&mut StorageValue) -> T) -> Option<T> {
+        self.0.modify(key, f)
     }
 
     fn delete(&mut self, key: &StorageKey) {
         self.0.delete(key);
         self.1.delete(key);
     }
+
+    fn keys(&self) -> Vec<StorageKey> {
+        self.0.keys()
+    }
+
+    fn size(&self) -> usize {
+        self.0.size()
+    }
+
+    fn take(&mut self) -> Self {
+        (self.0.take(), self.1.take())
+    }
+
+    fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<StorageKey>) {
+        self.0.scan(cursor, count)
+    }
+
+    fn remove(&mut self, key: &StorageKey) -> Option<(StorageValue, ExpirationTime)> {
+        let (value, _dummy_expiry) = self.0.remove(key)?;
+        let expiry = self.1.remove(key).and_then(|(_dummy_value, expiry)| expiry);
+        Some((value, expiry))
+    }
+
+    fn set_expiry(&mut self, key: &StorageKey, expiry: ExpirationTime) {
+        if self.0.expiry(key).is_none() {
+            return;
+        }
+        match expiry {
+            None => self.1.delete(key),
+            Some(_) => self.1.create(key, StorageValue::default(), expiry),
+        }
+    }
+
+    fn touch(&self, key: &StorageKey) {
+        self.0.touch(key);
+    }
+
+    fn metadata(&self, key: &StorageKey) -> Option<&Metadata> {
+        self.0.metadata(key)
+    }
+
+    fn set_metadata(&mut self, key: &StorageKey, metadata: Metadata) {
+        self.0.set_metadata(key, metadata);
+    }
+
+    fn expiry(&self, key: &StorageKey) -> Option<ExpirationTime> {
+        self.0.expiry(key)?;
+        Some(self.1.expiry(key).flatten())
+    }
 }
 
 impl<S> SubStorage<S> for InMemoryStorageHashMap
@@ -59,15 +113,68 @@ where
 
 impl Crud for InMemoryStorageHashMap {
     fn create(&mut self, key: &StorageKey, value: StorageValue, _expiry: ExpirationTime) {
-        self.insert(key.clone(), value);
+        match self.get_mut(key) {
+            Some(entry) => entry.replace(value),
+            None => {
+                self.insert(key.clone(), Entry::new(value));
+            }
+        }
     }
 
-    fn read(&self, key: &StorageKey) -> Option<(StorageValue, ExpirationTime)> {
-        self.get(key).map(|value| (value.clone(), None))
+    fn get(&self, key: &StorageKey) -> Option<(&StorageValue, ExpirationTime)> {
+        ScanMap::get(self, key).map(|entry| (&entry.value, None))
+    }
+
+    fn modify<T>(&mut self, key: &StorageKey, f: impl FnOnce(&mut StorageValue) -> T) -> Option<T> {
+        ScanMap::get_mut(self, key).map(|entry| entry.modify(f))
     }
 
     fn delete(&mut self, key: &StorageKey) {
-        self.remove(key);
+        ScanMap::remove(self, key);
+    }
+
+    fn keys(&self) -> Vec<StorageKey> {
+        ScanMap::keys(self).cloned().collect()
+    }
+
+    fn size(&self) -> usize {
+        self.len()
+    }
+
+    fn take(&mut self) -> Self {
+        std::mem::take(self)
+    }
+
+    fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<StorageKey>) {
+        let (cursor, entries) = ScanMap::scan(self, cursor, count);
+        (
+            cursor,
+            entries.into_iter().map(|(key, _)| key.clone()).collect(),
+        )
+    }
+
+    fn remove(&mut self, key: &StorageKey) -> Option<(StorageValue, ExpirationTime)> {
+        ScanMap::remove(self, key).map(|entry| (entry.value, None))
+    }
+
+    fn touch(&self, key: &StorageKey) {
+        if let Some(entry) = ScanMap::get(self, key) {
+            entry.metadata.touch();
+        }
+    }
+
+    fn metadata(&self, key: &StorageKey) -> Option<&Metadata> {
+        ScanMap::get(self, key).map(|entry| &entry.metadata)
+    }
+
+    fn set_metadata(&mut self, key: &StorageKey, metadata: Metadata) {
+        if let Some(entry) = ScanMap::get_mut(self, key) {
+            entry.metadata = metadata;
+        }
+    }
+
+    fn expiry(&self, key: &StorageKey) -> Option<ExpirationTime> {
+        self.contains_key(key).then_some(None)
     }
 }
 
@@ -85,12 +192,36 @@ impl Crud for InMemoryExpiryTimeHashMap {
         self.insert(key.clone(), expiry);
     }
 
-    fn read(&self, key: &StorageKey) -> Option<(StorageValue, ExpirationTime)> {
-        self.get(key).map(|value| ("".to_string(), *value))
+    fn get(&self, key: &StorageKey) -> Option<(&StorageValue, ExpirationTime)> {
+        ScanMap::get(self, key).map(|expiry| (&NO_VALUE, *expiry))
     }
 
     fn delete(&mut self, key: &StorageKey) {
-        self.remove(key);
+        ScanMap::remove(self, key);
+    }
+
+    fn keys(&self) -> Vec<StorageKey> {
+        ScanMap::keys(self).cloned().collect()
+    }
+
+    fn size(&self) -> usize {
+        self.len()
+    }
+
+    fn take(&mut self) -> Self {
+        std::mem::take(self)
+    }
+
+    fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<StorageKey>) {
+        let (cursor, entries) = ScanMap::scan(self, cursor, count);
+        (
+            cursor,
+            entries.into_iter().map(|(key, _)| key.clone()).collect(),
+        )
+    }
+
+    fn expiry(&self, key: &StorageKey) -> Option<ExpirationTime> {
+        ScanMap::get(self, key).copied()
     }
 }
 
@@ -108,11 +239,27 @@ impl Crud for InMemoryExpiryTimeBTreeMap {
         self.insert(key.clone(), expiry);
     }
 
-    fn read(&self, key: &StorageKey) -> Option<(StorageValue, ExpirationTime)> {
-        self.get(key).map(|value| ("".to_string(), *value))
+    fn get(&self, key: &StorageKey) -> Option<(&StorageValue, ExpirationTime)> {
+        BTreeMap::get(self, key).map(|expiry| (&NO_VALUE, *expiry))
     }
 
     fn delete(&mut self, key: &StorageKey) {
-        self.remove(key);
+        BTreeMap::remove(self, key);
+    }
+
+    fn keys(&self) -> Vec<StorageKey> {
+        BTreeMap::keys(self).cloned().collect()
+    }
+
+    fn size(&self) -> usize {
+        self.len()
+    }
+
+    fn take(&mut self) -> Self {
+        std::mem::take(self)
+    }
+
+    fn expiry(&self, key: &StorageKey) -> Option<ExpirationTime> {
+        self.get(key).copied()
     }
 }
@@ -0,0 +1,429 @@
+//! Per-key metadata
+//!
+//! - [OBJECT](https://redis.io/docs/latest/commands/object/)
+//! - [Key eviction](https://redis.io/docs/latest/develop/reference/eviction/)
+//!
+//! Every key of the main store keeps some metadata next to its value, the same as a Redis object does:
+//! - the encoding, which is the internal representation that Redis would use for the value,
+//! - the time of the last access, which is what the LRU eviction policies go by,
+//! - the LFU counter, which is a logarithmic access frequency, and is what the LFU eviction policies go by.
+//!
+//! Our values always have the same representation, so the encoding is derived from the value when it's written,
+//! following the same rules and default limits as Redis. Aggregate values keep their encoding when they shrink,
+//! except lists, the same as in Redis.
+//!
+//! The access metadata is kept in atomics, so that commands which only read a key, such as `GET`,
+//! can update it while holding just the read lock of the storage.
+
+use crate::random::random_f64;
+use crate::storage::object::Object;
+use crate::types::StorageValue;
+use bytes::Bytes;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Strings up to this length are embedded in their object, `OBJ_ENCODING_EMBSTR_SIZE_LIMIT`
+const EMBSTR_SIZE_LIMIT: usize = 44;
+/// Maximum size of a list in bytes that is kept as a single listpack, `list-max-listpack-size -2`
+pub(crate) const LIST_MAX_LISTPACK_SIZE: usize = 8192;
+/// `hash-max-listpack-entries`
+const HASH_MAX_LISTPACK_ENTRIES: usize = 128;
+/// `hash-max-listpack-value`
+const HASH_MAX_LISTPACK_VALUE: usize = 64;
+/// `set-max-intset-entries`
+const SET_MAX_INTSET_ENTRIES: usize = 512;
+/// `set-max-listpack-entries`
+const SET_MAX_LISTPACK_ENTRIES: usize = 128;
+/// `set-max-listpack-value`
+const SET_MAX_LISTPACK_VALUE: usize = 64;
+/// `zset-max-listpack-entries`
+const ZSET_MAX_LISTPACK_ENTRIES: usize = 128;
+/// `zset-max-listpack-value`
+const ZSET_MAX_LISTPACK_VALUE: usize = 64;
+
+/// Initial value of the LFU counter of a new key, so that new keys aren't the first ones to be evicted
+const LFU_INIT_VAL: u8 = 5;
+/// `lfu-log-factor`: the higher it is, the more accesses it takes to increment the LFU counter
+const LFU_LOG_FACTOR: f64 = 10.0;
+/// `lfu-decay-time`: the number of minutes after which the LFU counter is decremented
+const LFU_DECAY_TIME: u64 = 1;
+
+/// A stored value together with its metadata
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub value: StorageValue,
+    pub metadata: Metadata,
+}
+
+impl Entry {
+    /// Creates the entry of a new key, which counts as accessed now.
+    pub fn new(value: StorageValue) -> Self {
+        let metadata = Metadata::new(Encoding::of(&value, None));
+        Self { value, metadata }
+    }
+
+    /// Replaces the value of an existing key, which counts as an access of the key.
+    pub fn replace(&mut self, value: StorageValue) {
+        let previous =
+            (value.type_name() == self.value.type_name()).then_some(self.metadata.encoding);
+        self.metadata.encoding = Encoding::of(&value, previous);
+        self.value = value;
+        self.metadata.touch();
+    }
+
+    /// Modifies the value of an existing key in place, which counts as an access of the key.
+    ///
+    /// The encoding is derived anew from the modified value.
+    pub fn modify<T>(&mut self, f: impl FnOnce(&mut StorageValue) -> T) -> T {
+        let type_name = self.value.type_name();
+        let result = f(&mut self.value);
+        let previous = (self.value.type_name() == type_name).then_some(self.metadata.encoding);
+        self.metadata.encoding = Encoding::of(&self.value, previous);
+        self.metadata.touch();
+        result
+    }
+}
+
+/// The metadata of a key
+#[derive(Debug)]
+pub struct Metadata {
+    /// The encoding of the value
+    encoding: Encoding,
+    /// The time of the last access, in milliseconds since the Unix epoch
+    last_access: AtomicU64,
+    /// The time of the last decrement of the LFU counter, in minutes, in the upper 16 bits,
+    /// and the LFU counter in the lower 8 bits, the same as Redis packs them
+    lfu: AtomicU32,
+}
+
+impl Metadata {
+    /// Creates the metadata of a new key, which counts as accessed now.
+    pub fn new(encoding: Encoding) -> Self {
+        Self {
+            encoding,
+            last_access: AtomicU64::new(unix_time_ms()),
+            lfu: AtomicU32::new(pack_lfu(LFU_INIT_VAL)),
+        }
+    }
+
+    /// The encoding of the value, as reported by `OBJECT ENCODING`
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Records an access of the key: updates its last access time, and probabilistically increments its LFU counter.
+    pub fn touch(&self) {
+        self.last_access.store(unix_time_ms(), Ordering::Relaxed);
+        let counter = log_incr(self.frequency());
+        self.lfu.store(pack_lfu(counter), Ordering::Relaxed);
+    }
+
+    /// The number of milliseconds since the last access of the key
+    pub fn idle_time_ms(&self) -> u64 {
+        unix_time_ms().saturating_sub(self.last_access.load(Ordering::Relaxed))
+    }
+
+    /// Sets the time of the last access of the key to `idle_ms` milliseconds ago.
+    pub fn set_idle_time_ms(&self, idle_ms: u64) {
+        let last_access = unix_time_ms().saturating_sub(idle_ms);
+        self.last_access.store(last_access, Ordering::Relaxed);
+    }
+
+    /// The LFU counter, decremented by one for every [`LFU_DECAY_TIME`] minutes since it was last decremented,
+    /// as reported by `OBJECT FREQ`
+    pub fn frequency(&self) -> u8 {
+        let lfu = self.lfu.load(Ordering::Relaxed);
+        let (decrement_time, counter) = ((lfu >> 8) as u16, lfu as u8);
+        let elapsed = minutes().wrapping_sub(decrement_time) as u64;
+        let periods = elapsed / LFU_DECAY_TIME;
+        counter.saturating_sub(periods.min(u8::MAX as u64) as u8)
+    }
+
+    /// Sets the LFU counter, as of now.
+    pub fn set_frequency(&self, counter: u8) {
+        self.lfu.store(pack_lfu(counter), Ordering::Relaxed);
+    }
+}
+
+impl Clone for Metadata {
+    fn clone(&self) -> Self {
+        Self {
+            encoding: self.encoding,
+            last_access: AtomicU64::new(self.last_access.load(Ordering::Relaxed)),
+            lfu: AtomicU32::new(self.lfu.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// The internal representation that Redis would use for a value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// A string that holds a 64-bit signed integer
+    Int,
+    /// A short string, which is embedded in its object
+    Embstr,
+    /// A long string
+    Raw,
+    /// A small list, hash, set or sorted set, which is serialized into a single buffer
+    Listpack,
+    /// A large list: a linked list of listpacks
+    Quicklist,
+    /// A small set of integers: a sorted array of integers
+    Intset,
+    /// A large hash or set
+    Hashtable,
+    /// A large sorted set: a skip list together with a hash table
+    Skiplist,
+    /// A stream: a radix tree of listpacks
+    Stream,
+}
+
+impl Encoding {
+    /// The name of the encoding, as reported by `OBJECT ENCODING`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Int => "int",
+            Self::Embstr => "embstr",
+            Self::Raw => "raw",
+            Self::Listpack => "listpack",
+            Self::Quicklist => "quicklist",
+            Self::Intset => "intset",
+            Self::Hashtable => "hashtable",
+            Self::Skiplist => "skiplist",
+            Self::Stream => "stream",
+        }
+    }
+
+    /// Returns the encoding of `value`, given the `previous` encoding of the key, if it held a value of the same type.
+    ///
+    /// Hashes, sets and sorted sets never go back to a more compact encoding. Lists go back to a listpack
+    /// when they shrink to half of the limit, so that they don't convert back and forth around it.
+    pub fn of(value: &Object, previous: Option<Encoding>) -> Self {
+        match value {
+            Object::String(string) if string.len() <= 20 && is_integer(string) => Self::Int,
+            Object::String(string) if string.len() <= EMBSTR_SIZE_LIMIT => Self::Embstr,
+            Object::String(_) => Self::Raw,
+            Object::List(list) => {
+                let limit = match previous {
+                    Some(Self::Quicklist) => LIST_MAX_LISTPACK_SIZE / 2,
+                    _ => LIST_MAX_LISTPACK_SIZE,
+                };
+                // The header and the end marker take 7 bytes.
+                let size = 7 + list.iter().map(listpack_entry_size).sum::<usize>();
+                if size <= limit {
+                    Self::Listpack
+                } else {
+                    Self::Quicklist
+                }
+            }
+            Object::Hash(hash) => {
+                if previous == Some(Self::Hashtable)
+                    || hash.len() > HASH_MAX_LISTPACK_ENTRIES
+                    || hash.iter().any(|(field, value)| {
+                        field.len() > HASH_MAX_LISTPACK_VALUE
+                            || value.len() > HASH_MAX_LISTPACK_VALUE
+                    })
+                {
+                    Self::Hashtable
+                } else {
+                    Self::Listpack
+                }
+            }
+            Object::Set(set) => {
+                if matches!(previous, None | Some(Self::Intset))
+                    && set.len() <= SET_MAX_INTSET_ENTRIES
+                    && set.iter().all(is_integer)
+                {
+                    Self::Intset
+                } else if previous != Some(Self::Hashtable)
+                    && set.len() <= SET_MAX_LISTPACK_ENTRIES
+                    && set
+                        .iter()
+                        .all(|member| member.len() <= SET_MAX_LISTPACK_VALUE)
+                {
+                    Self::Listpack
+                } else {
+                    Self::Hashtable
+                }
+            }
+            Object::SortedSet(zset) => {
+                if previous == Some(Self::Skiplist)
+                    || zset.len() > ZSET_MAX_LISTPACK_ENTRIES
+                    || zset
+                        .keys()
+                        .any(|member| member.len() > ZSET_MAX_LISTPACK_VALUE)
+                {
+                    Self::Skiplist
+                } else {
+                    Self::Listpack
+                }
+            }
+            Object::Stream(_) => Self::Stream,
+        }
+    }
+}
+
+/// Checks whether a string is the canonical representation of a 64-bit signed integer,
+/// which is how Redis decides whether to store it as an integer.
+fn is_integer(string: &Bytes) -> bool {
+    std::str::from_utf8(string)
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok())
+        .is_some_and(|n| n.to_string().as_bytes() == string.as_ref())
+}
+
+/// Returns the size of a listpack entry that holds `element`, including its encoding and back-length bytes.
+fn listpack_entry_size(element: &Bytes) -> usize {
+    let encoded = if is_integer(element) {
+        let n: i64 = std::str::from_utf8(element)
+            .expect("Integers are UTF-8")
+            .parse()
+            .expect("Checked to be an integer");
+        match n {
+            0..=127 => 1,
+            -4096..=4095 => 2,
+            -32768..=32767 => 3,
+            -8388608..=8388607 => 4,
+            -2147483648..=2147483647 => 5,
+            _ => 9,
+        }
+    } else {
+        match element.len() {
+            len @ 0..=63 => 1 + len,
+            len @ 64..=4095 => 2 + len,
+            len => 5 + len,
+        }
+    };
+    let backlen = match encoded {
+        0..=127 => 1,
+        128..=16382 => 2,
+        16383..=2097150 => 3,
+        2097151..=268435454 => 4,
+        _ => 5,
+    };
+    encoded + backlen
+}
+
+/// Returns the current Unix time in milliseconds.
+fn unix_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as u64)
+}
+
+/// Returns the current Unix time in minutes, reduced to 16 bits, the same as Redis keeps it.
+fn minutes() -> u16 {
+    (unix_time_ms() / 1000 / 60) as u16
+}
+
+/// Packs the LFU counter together with the current time in minutes.
+fn pack_lfu(counter: u8) -> u32 {
+    ((minutes() as u32) << 8) | counter as u32
+}
+
+/// Increments the LFU counter with a probability that gets lower as the counter gets higher.
+fn log_incr(counter: u8) -> u8 {
+    if counter == u8::MAX {
+        return counter;
+    }
+    let r = random_f64();
+    let base = counter.saturating_sub(LFU_INIT_VAL) as f64;
+    let p = 1.0 / (base * LFU_LOG_FACTOR + 1.0);
+    if r < p {
+        counter + 1
+    } else {
+        counter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::cursor::{ScanMap, ScanSet};
+    use std::collections::VecDeque;
+
+    fn set(members: impl IntoIterator<Item = String>) -> Object {
+        Object::Set(members.into_iter().map(Bytes::from).collect::<ScanSet>())
+    }
+
+    #[test]
+    fn encoding_of_strings() {
+        let encoding =
+            |s: &str| Encoding::of(&Object::String(Bytes::copy_from_slice(s.as_bytes())), None);
+        assert_eq!(Encoding::Int, encoding("-9223372036854775808"));
+        assert_eq!(Encoding::Embstr, encoding("9223372036854775808"));
+        assert_eq!(Encoding::Embstr, encoding("007"));
+        assert_eq!(Encoding::Embstr, encoding(&"a".repeat(44)));
+        assert_eq!(Encoding::Raw, encoding(&"a".repeat(45)));
+    }
+
+    #[test]
+    fn encoding_of_sets_is_sticky() {
+        let ints = set((0..512).map(|n| n.to_string()));
+        assert_eq!(Encoding::Intset, Encoding::of(&ints, None));
+        let ints = set((0..513).map(|n| n.to_string()));
+        assert_eq!(Encoding::Hashtable, Encoding::of(&ints, None));
+
+        let small = set(["a".to_string()]);
+        assert_eq!(
+            Encoding::Listpack,
+            Encoding::of(&small, Some(Encoding::Intset))
+        );
+        assert_eq!(
+            Encoding::Listpack,
+            Encoding::of(&set(["1".to_string()]), Some(Encoding::Listpack))
+        );
+        assert_eq!(
+            Encoding::Hashtable,
+            Encoding::of(&small, Some(Encoding::Hashtable))
+        );
+    }
+
+    #[test]
+    fn encoding_of_aggregates() {
+        let hash = Object::Hash(ScanMap::from_iter([(
+            Bytes::from("f"),
+            Bytes::from("a".repeat(65)),
+        )]));
+        assert_eq!(Encoding::Hashtable, Encoding::of(&hash, None));
+
+        let zset = Object::SortedSet(
+            (0..129)
+                .map(|n| (Bytes::from(n.to_string()), 0.0))
+                .collect(),
+        );
+        assert_eq!(Encoding::Skiplist, Encoding::of(&zset, None));
+
+        // 63 bytes of data take 1 byte of encoding and 1 byte of back-length, so 65 bytes per element.
+        let list = |len| Object::List(VecDeque::from(vec![Bytes::from("a".repeat(63)); len]));
+        assert_eq!(Encoding::Listpack, Encoding::of(&list(125), None));
+        assert_eq!(Encoding::Quicklist, Encoding::of(&list(126), None));
+        assert_eq!(
+            Encoding::Quicklist,
+            Encoding::of(&list(63), Some(Encoding::Quicklist))
+        );
+        assert_eq!(
+            Encoding::Listpack,
+            Encoding::of(&list(62), Some(Encoding::Quicklist))
+        );
+    }
+
+    #[test]
+    fn frequency_and_idle_time() {
+        let metadata = Metadata::new(Encoding::Int);
+        assert_eq!(LFU_INIT_VAL, metadata.frequency());
+        metadata.set_frequency(u8::MAX);
+        metadata.touch();
+        assert_eq!(u8::MAX, metadata.frequency());
+
+        metadata.set_idle_time_ms(10_000);
+        assert!((10_000..11_000).contains(&metadata.idle_time_ms()));
+        metadata.touch();
+        assert!(metadata.idle_time_ms() < 1000);
+
+        // The counter is always incremented while it's at most the initial value.
+        metadata.set_frequency(0);
+        metadata.touch();
+        assert_eq!(1, metadata.frequency());
+    }
+}
@@ -1,6 +1,11 @@
 //! Storage For Our Redis Server
 
+pub mod cursor;
 pub mod generic;
+pub mod hyperloglog;
 pub mod inmemory;
+pub mod metadata;
+pub mod object;
+pub mod rdb;
 
 pub use generic::Storage;
@@ -0,0 +1,106 @@
+//! Typed values of the keyspace
+//!
+//! - [Understand Redis data types](https://redis.io/docs/latest/develop/data-types/)
+//!
+//! Every key holds a value of one of the Redis data types. Commands are specific to data types,
+//! and a command used against a key of a different type fails with a `WRONGTYPE` error.
+
+use crate::storage::cursor::{ScanMap, ScanSet};
+use bytes::{Bytes, BytesMut};
+use std::collections::{BTreeMap, VecDeque};
+
+/// ID of a stream entry: milliseconds time and sequence number
+pub type StreamId = (u64, u64);
+
+/// A value stored under a key
+#[derive(Clone, Debug, PartialEq)]
+pub enum Object {
+    /// A binary-safe byte string, which also backs bitmaps and HyperLogLogs
+    String(Bytes),
+    /// A list of byte strings, ordered by insertion
+    List(VecDeque<Bytes>),
+    /// A map of fields to values
+    Hash(ScanMap<Bytes>),
+    /// An unordered collection of unique members
+    Set(ScanSet),
+    /// A collection of unique members, each associated with a score, which orders them
+    SortedSet(ScanMap<f64>),
+    /// An append-only log of entries, each holding field-value pairs
+    Stream(BTreeMap<StreamId, Vec<(Bytes, Bytes)>>),
+}
+
+impl Object {
+    /// The type name, as reported by the [TYPE](https://redis.io/docs/latest/commands/type/) command
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::String(_) => "string",
+            Self::List(_) => "list",
+            Self::Hash(_) => "hash",
+            Self::Set(_) => "set",
+            Self::SortedSet(_) => "zset",
+            Self::Stream(_) => "stream",
+        }
+    }
+
+    /// Returns the byte string if the value is a string, and `None` otherwise.
+    pub fn as_string(&self) -> Option<&Bytes> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Modifies the byte string in place if the value is a string, and returns `None` otherwise.
+    ///
+    /// The string's buffer is reused, together with its spare capacity, unless something else still references it,
+    /// such as a reply that hasn't been sent yet, in which case it's copied first. So appending to a string takes
+    /// amortized time proportional to the length of the appended part, the same as in Redis.
+    pub fn modify_string<T>(&mut self, f: impl FnOnce(&mut BytesMut) -> T) -> Option<T> {
+        let Self::String(value) = self else {
+            return None;
+        };
+        let mut string = BytesMut::from(std::mem::take(value));
+        let result = f(&mut string);
+        *value = string.freeze();
+        Some(result)
+    }
+}
+
+/// An empty string, which is also used as a dummy value in stores that only keep expiration times
+impl Default for Object {
+    fn default() -> Self {
+        Self::String(Bytes::new())
+    }
+}
+
+impl From<Bytes> for Object {
+    fn from(value: Bytes) -> Self {
+        Self::String(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modify_string_reuses_an_unshared_buffer() {
+        let mut buffer = Vec::with_capacity(64);
+        buffer.extend_from_slice(b"Hello");
+        let mut value = Object::String(Bytes::from(buffer));
+        let ptr = value.as_string().unwrap().as_ptr();
+
+        value.modify_string(|string| string.extend_from_slice(b" World"));
+        assert_eq!(Some(&Bytes::from("Hello World")), value.as_string());
+        assert_eq!(ptr, value.as_string().unwrap().as_ptr());
+
+        // A shared buffer is left as it is.
+        let shared = value.as_string().unwrap().clone();
+        value.modify_string(|string| string[0] = b'J');
+        assert_eq!(Bytes::from("Hello World"), shared);
+        assert_eq!(Some(&Bytes::from("Jello World")), value.as_string());
+
+        let mut list = Object::List(Default::default());
+        assert_eq!(None, list.modify_string(|string| string.len()));
+    }
+}
@@ -0,0 +1,972 @@
+//! Serialization of values in the RDB format, as used by `DUMP` and `RESTORE`
+//!
+//! - [DUMP](https://redis.io/docs/latest/commands/dump/)
+//! - [RESTORE](https://redis.io/docs/latest/commands/restore/)
+//!
+//! A payload is a single RDB object followed by a 10-byte footer:
+//! - the object type, as a single byte,
+//! - the encoded value, whose layout depends on the type,
+//! - the RDB version, as a 16-bit little-endian integer,
+//! - the CRC64 (Jones) checksum of everything before it, as a 64-bit little-endian integer.
+//!
+//! The payloads are compatible with Redis's, so that keys can be migrated between Redis and this server.
+//! Values are dumped with the types that Redis 7 uses for the encoding of the key, and all the types
+//! that Redis 7 dumps can be restored, except for streams and module values.
+//!
+//! Lengths are stored in 1, 2, 5 or 9 bytes, depending on the two most significant bits of the first byte:
+//! - `00xxxxxx` - a 6-bit length
+//! - `01xxxxxx xxxxxxxx` - a 14-bit big-endian length
+//! - `10000000` - a 32-bit big-endian length in the next 4 bytes
+//! - `10000001` - a 64-bit big-endian length in the next 8 bytes
+//! - `11xxxxxx` - a special encoding of a string: an 8, 16 or 32-bit little-endian integer,
+//!   or an LZF-compressed string
+//!
+//! Compact encodings, listpacks and intsets, are stored as a single string that holds their
+//! in-memory representation.
+
+use crate::errors::CmdError;
+use crate::storage::cursor::{ScanMap, ScanSet};
+use crate::storage::metadata::{Encoding, LIST_MAX_LISTPACK_SIZE};
+use crate::storage::object::Object;
+use bytes::Bytes;
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+/// The version of the RDB format that Redis 7.4 uses; payloads from newer versions are rejected.
+pub const RDB_VERSION: u16 = 12;
+
+/// A string
+const TYPE_STRING: u8 = 0;
+/// A list, as a sequence of strings; not dumped by Redis 7, but still restored
+const TYPE_LIST: u8 = 1;
+/// A set, as a sequence of strings
+const TYPE_SET: u8 = 2;
+/// A sorted set with scores stored as strings; not dumped by Redis 7, but still restored
+const TYPE_ZSET: u8 = 3;
+/// A hash, as a sequence of fields and values
+const TYPE_HASH: u8 = 4;
+/// A sorted set with scores stored as binary doubles
+const TYPE_ZSET_2: u8 = 5;
+/// A set of integers, as an intset
+const TYPE_SET_INTSET: u8 = 11;
+/// A hash, as a listpack of fields and values
+const TYPE_HASH_LISTPACK: u8 = 16;
+/// A sorted set, as a listpack of members and scores ordered by score
+const TYPE_ZSET_LISTPACK: u8 = 17;
+/// A list, as a sequence of quicklist nodes
+const TYPE_LIST_QUICKLIST_2: u8 = 18;
+/// A set, as a listpack of members
+const TYPE_SET_LISTPACK: u8 = 20;
+
+/// The first byte of a 32-bit length
+const LEN_32BIT: u8 = 0x80;
+/// The first byte of a 64-bit length
+const LEN_64BIT: u8 = 0x81;
+/// The two most significant bits of the first byte of a special encoding
+const ENCVAL: u8 = 0b11;
+/// A string encoded as an 8-bit integer
+const ENC_INT8: u64 = 0;
+/// A string encoded as a 16-bit integer
+const ENC_INT16: u64 = 1;
+/// A string encoded as a 32-bit integer
+const ENC_INT32: u64 = 2;
+/// An LZF-compressed string
+const ENC_LZF: u64 = 3;
+
+/// A quicklist node that holds a single element as a plain string
+const QUICKLIST_NODE_PLAIN: usize = 1;
+/// A quicklist node that holds a listpack of elements
+const QUICKLIST_NODE_PACKED: usize = 2;
+
+/// Length of the footer: the RDB version and the checksum
+const FOOTER_LEN: usize = 10;
+/// Length of the header of a listpack: the total number of bytes and the number of elements
+const LISTPACK_HEADER_LEN: usize = 6;
+/// The end marker of a listpack
+const LISTPACK_EOF: u8 = 0xff;
+/// Length of the header of an intset: the width of the integers and their number
+const INTSET_HEADER_LEN: usize = 8;
+/// The reflected polynomial of the CRC64 variant used by Redis (Jones)
+const CRC64_POLY: u64 = 0x95ac_9329_ac4b_c9b5;
+/// Lookup table of the CRC64 for every byte value
+const CRC64_TABLE: [u64; 256] = crc64_table();
+
+/// Serializes `value`, held with `encoding`, into a `DUMP` payload.
+///
+/// Streams can't be serialized yet.
+pub fn dump(value: &Object, encoding: Encoding) -> Result<Vec<u8>, CmdError> {
+    let mut out = Vec::new();
+    match value {
+        Object::String(string) => {
+            out.push(TYPE_STRING);
+            write_string(&mut out, string);
+        }
+        Object::List(list) => {
+            // A list is dumped as quicklist nodes of at most 8 KB, even when it's a single listpack.
+            let mut nodes = vec![Listpack::default()];
+            for element in list {
+                let node = nodes.last_mut().expect("There is always a node");
+                if node.len() > 0 && node.size() + element.len() > LIST_MAX_LISTPACK_SIZE {
+                    nodes.push(Listpack::default());
+                }
+                nodes
+                    .last_mut()
+                    .expect("There is always a node")
+                    .push(element);
+            }
+            out.push(TYPE_LIST_QUICKLIST_2);
+            write_length(&mut out, nodes.len());
+            for node in nodes {
+                write_length(&mut out, QUICKLIST_NODE_PACKED);
+                write_string(&mut out, &node.finish());
+            }
+        }
+        Object::Set(set) => {
+            let integers = set.iter().map(|member| integer(member)).collect();
+            match (encoding, integers) {
+                (Encoding::Intset, Some(integers)) => {
+                    out.push(TYPE_SET_INTSET);
+                    write_string(&mut out, &intset(integers));
+                }
+                (Encoding::Listpack | Encoding::Intset, _) => {
+                    let mut listpack = Listpack::default();
+                    set.iter().for_each(|member| listpack.push(member));
+                    out.push(TYPE_SET_LISTPACK);
+                    write_string(&mut out, &listpack.finish());
+                }
+                _ => {
+                    out.push(TYPE_SET);
+                    write_length(&mut out, set.len());
+                    set.iter().for_each(|member| write_string(&mut out, member));
+                }
+            }
+        }
+        Object::Hash(hash) => {
+            if encoding == Encoding::Listpack {
+                let mut listpack = Listpack::default();
+                for (field, value) in hash {
+                    listpack.push(field);
+                    listpack.push(value);
+                }
+                out.push(TYPE_HASH_LISTPACK);
+                write_string(&mut out, &listpack.finish());
+            } else {
+                out.push(TYPE_HASH);
+                write_length(&mut out, hash.len());
+                for (field, value) in hash {
+                    write_string(&mut out, field);
+                    write_string(&mut out, value);
+                }
+            }
+        }
+        Object::SortedSet(zset) => {
+            if encoding == Encoding::Listpack {
+                // Redis expects the members of a listpack to be ordered by score, then lexicographically.
+                let mut members = zset.iter().collect::<Vec<_>>();
+                members.sort_by(|a, b| {
+                    a.1.partial_cmp(b.1)
+                        .unwrap_or(Ordering::Equal)
+                        .then_with(|| a.0.cmp(b.0))
+                });
+                let mut listpack = Listpack::default();
+                for (member, score) in members {
+                    listpack.push(member);
+                    listpack.push(format_score(*score).as_bytes());
+                }
+                out.push(TYPE_ZSET_LISTPACK);
+                write_string(&mut out, &listpack.finish());
+            } else {
+                out.push(TYPE_ZSET_2);
+                write_length(&mut out, zset.len());
+                for (member, score) in zset {
+                    write_string(&mut out, member);
+                    out.extend_from_slice(&score.to_le_bytes());
+                }
+            }
+        }
+        Object::Stream(_) => return Err(CmdError::UnsupportedDumpType(value.type_name())),
+    }
+
+    out.extend_from_slice(&RDB_VERSION.to_le_bytes());
+    let checksum = crc64(&out);
+    out.extend_from_slice(&checksum.to_le_bytes());
+    Ok(out)
+}
+
+/// Deserializes a `DUMP` payload into a value.
+///
+/// Fails with [`CmdError::DumpPayloadInvalid`] if the payload comes from a newer RDB version,
+/// or if its checksum doesn't match, and with [`CmdError::BadDataFormat`] if the value can't be decoded.
+pub fn restore(payload: &[u8]) -> Result<Object, CmdError> {
+    let Some(body_len) = payload.len().checked_sub(FOOTER_LEN) else {
+        return Err(CmdError::DumpPayloadInvalid);
+    };
+    let (body, footer) = payload.split_at(body_len);
+    let version = u16::from_le_bytes([footer[0], footer[1]]);
+    let checksum = u64::from_le_bytes(footer[2..].try_into().expect("The checksum is 8 bytes"));
+    if version > RDB_VERSION || crc64(&payload[..body_len + 2]) != checksum {
+        return Err(CmdError::DumpPayloadInvalid);
+    }
+
+    let mut reader = Reader(body);
+    let kind = reader.byte()?;
+    let value = read_object(kind, &mut reader)?;
+    if !reader.0.is_empty() {
+        return Err(CmdError::BadDataFormat);
+    }
+    Ok(value)
+}
+
+/// Reads a value of the object type `kind`.
+///
+/// Empty aggregates and duplicate members are rejected, the same as in Redis.
+fn read_object(kind: u8, reader: &mut Reader) -> Result<Object, CmdError> {
+    Ok(match kind {
+        TYPE_STRING => Object::String(reader.string()?),
+        TYPE_LIST => {
+            let len = reader.length()?;
+            list(reader.strings(len)?)?
+        }
+        TYPE_LIST_QUICKLIST_2 => {
+            let mut elements = Vec::new();
+            for _ in 0..reader.length()? {
+                match reader.length()? {
+                    QUICKLIST_NODE_PLAIN => elements.push(reader.string()?),
+                    QUICKLIST_NODE_PACKED => elements.extend(listpack(&reader.string()?)?),
+                    _ => return Err(CmdError::BadDataFormat),
+                }
+            }
+            list(elements)?
+        }
+        TYPE_SET => {
+            let len = reader.length()?;
+            set(reader.strings(len)?)?
+        }
+        TYPE_SET_INTSET => set(intset_members(&reader.string()?)?)?,
+        TYPE_SET_LISTPACK => set(listpack(&reader.string()?)?)?,
+        TYPE_HASH => {
+            let len = reader.length()?;
+            let len = len.checked_mul(2).ok_or(CmdError::BadDataFormat)?;
+            hash(reader.strings(len)?)?
+        }
+        TYPE_HASH_LISTPACK => hash(listpack(&reader.string()?)?)?,
+        TYPE_ZSET | TYPE_ZSET_2 => {
+            let mut members = Vec::new();
+            for _ in 0..reader.length()? {
+                let member = reader.string()?;
+                let score = if kind == TYPE_ZSET {
+                    reader.string_double()?
+                } else {
+                    f64::from_le_bytes(reader.array()?)
+                };
+                members.push((member, score));
+            }
+            sorted_set(members)?
+        }
+        TYPE_ZSET_LISTPACK => {
+            let entries = listpack(&reader.string()?)?;
+            if !entries.len().is_multiple_of(2) {
+                return Err(CmdError::BadDataFormat);
+            }
+            let members = entries
+                .chunks_exact(2)
+                .map(|pair| {
+                    let score = std::str::from_utf8(&pair[1])
+                        .ok()
+                        .and_then(|score| score.parse().ok())
+                        .ok_or(CmdError::BadDataFormat)?;
+                    Ok((pair[0].clone(), score))
+                })
+                .collect::<Result<_, CmdError>>()?;
+            sorted_set(members)?
+        }
+        // Streams, modules, and the ziplist encodings of Redis versions before 7.
+        _ => return Err(CmdError::BadDataFormat),
+    })
+}
+
+/// Builds a list from its elements.
+fn list(elements: Vec<Bytes>) -> Result<Object, CmdError> {
+    if elements.is_empty() {
+        return Err(CmdError::BadDataFormat);
+    }
+    Ok(Object::List(VecDeque::from(elements)))
+}
+
+/// Builds a set from its members.
+fn set(members: Vec<Bytes>) -> Result<Object, CmdError> {
+    let mut set = ScanSet::with_capacity(members.len());
+    for member in members {
+        if !set.insert(member) {
+            return Err(CmdError::BadDataFormat);
+        }
+    }
+    if set.is_empty() {
+        return Err(CmdError::BadDataFormat);
+    }
+    Ok(Object::Set(set))
+}
+
+/// Builds a hash from its fields, each followed by its value.
+fn hash(entries: Vec<Bytes>) -> Result<Object, CmdError> {
+    if entries.is_empty() || !entries.len().is_multiple_of(2) {
+        return Err(CmdError::BadDataFormat);
+    }
+    let mut hash = ScanMap::with_capacity(entries.len() / 2);
+    let mut entries = entries.into_iter();
+    while let (Some(field), Some(value)) = (entries.next(), entries.next()) {
+        if hash.insert(field, value).is_some() {
+            return Err(CmdError::BadDataFormat);
+        }
+    }
+    Ok(Object::Hash(hash))
+}
+
+/// Builds a sorted set from its members and their scores.
+fn sorted_set(members: Vec<(Bytes, f64)>) -> Result<Object, CmdError> {
+    let mut zset = ScanMap::with_capacity(members.len());
+    for (member, score) in members {
+        if score.is_nan() || zset.insert(member, score).is_some() {
+            return Err(CmdError::BadDataFormat);
+        }
+    }
+    if zset.is_empty() {
+        return Err(CmdError::BadDataFormat);
+    }
+    Ok(Object::SortedSet(zset))
+}
+
+/// A cursor over the bytes of a payload that are left to read
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    /// Reads the next `len` bytes.
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CmdError> {
+        if len > self.0.len() {
+            return Err(CmdError::BadDataFormat);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    /// Reads the next `N` bytes into an array.
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], CmdError> {
+        Ok(self.take(N)?.try_into().expect("Exactly N bytes are taken"))
+    }
+
+    /// Reads the next byte.
+    fn byte(&mut self) -> Result<u8, CmdError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    /// Reads a length, or the type of a special encoding, which is flagged by `true`.
+    fn length_or_encoding(&mut self) -> Result<(u64, bool), CmdError> {
+        let first = self.byte()?;
+        let low = (first & 0x3f) as u64;
+        match first >> 6 {
+            0 => Ok((low, false)),
+            1 => Ok((low << 8 | self.byte()? as u64, false)),
+            ENCVAL => Ok((low, true)),
+            _ => match first {
+                LEN_32BIT => Ok((u32::from_be_bytes(self.array()?) as u64, false)),
+                LEN_64BIT => Ok((u64::from_be_bytes(self.array()?), false)),
+                _ => Err(CmdError::BadDataFormat),
+            },
+        }
+    }
+
+    /// Reads a length.
+    fn length(&mut self) -> Result<usize, CmdError> {
+        match self.length_or_encoding()? {
+            (len, false) => usize::try_from(len).map_err(|_| CmdError::BadDataFormat),
+            (_, true) => Err(CmdError::BadDataFormat),
+        }
+    }
+
+    /// Reads a string, which may be encoded as an integer or compressed.
+    fn string(&mut self) -> Result<Bytes, CmdError> {
+        let (len, encoded) = self.length_or_encoding()?;
+        if !encoded {
+            let len = usize::try_from(len).map_err(|_| CmdError::BadDataFormat)?;
+            return Ok(Bytes::copy_from_slice(self.take(len)?));
+        }
+        let n = match len {
+            ENC_INT8 => i8::from_le_bytes(self.array()?) as i64,
+            ENC_INT16 => i16::from_le_bytes(self.array()?) as i64,
+            ENC_INT32 => i32::from_le_bytes(self.array()?) as i64,
+            ENC_LZF => {
+                let compressed_len = self.length()?;
+                let len = self.length()?;
+                let compressed = self.take(compressed_len)?;
+                return lzf_decompress(compressed, len).map(Bytes::from);
+            }
+            _ => return Err(CmdError::BadDataFormat),
+        };
+        Ok(Bytes::from(n.to_string()))
+    }
+
+    /// Reads `len` strings.
+    fn strings(&mut self, len: usize) -> Result<Vec<Bytes>, CmdError> {
+        (0..len).map(|_| self.string()).collect()
+    }
+
+    /// Reads a double stored as a string, prefixed by its length in a single byte.
+    ///
+    /// The lengths 253, 254 and 255 stand for NaN, positive infinity and negative infinity.
+    fn string_double(&mut self) -> Result<f64, CmdError> {
+        match self.byte()? {
+            253 => Ok(f64::NAN),
+            254 => Ok(f64::INFINITY),
+            255 => Ok(f64::NEG_INFINITY),
+            len => std::str::from_utf8(self.take(len as usize)?)
+                .ok()
+                .and_then(|double| double.parse().ok())
+                .ok_or(CmdError::BadDataFormat),
+        }
+    }
+}
+
+/// Writes a length in the shortest of its encodings.
+fn write_length(out: &mut Vec<u8>, len: usize) {
+    if len < 1 << 6 {
+        out.push(len as u8);
+    } else if len < 1 << 14 {
+        out.extend_from_slice(&[(len >> 8) as u8 | 0x40, len as u8]);
+    } else if let Ok(len) = u32::try_from(len) {
+        out.push(LEN_32BIT);
+        out.extend_from_slice(&len.to_be_bytes());
+    } else {
+        out.push(LEN_64BIT);
+        out.extend_from_slice(&(len as u64).to_be_bytes());
+    }
+}
+
+/// Writes a string, encoded as an integer if it's the canonical representation of a 32-bit integer.
+///
+/// Strings aren't compressed, which Redis accepts regardless of its `rdbcompression` setting.
+fn write_string(out: &mut Vec<u8>, string: &[u8]) {
+    // Longer strings can't hold a 32-bit integer, so Redis doesn't try them.
+    let n = if string.len() <= 11 {
+        integer(string)
+    } else {
+        None
+    };
+    match n {
+        Some(n @ -128..=127) => out.extend_from_slice(&[ENCVAL << 6 | ENC_INT8 as u8, n as u8]),
+        Some(n @ -32768..=32767) => {
+            out.push(ENCVAL << 6 | ENC_INT16 as u8);
+            out.extend_from_slice(&(n as i16).to_le_bytes());
+        }
+        Some(n @ -2147483648..=2147483647) => {
+            out.push(ENCVAL << 6 | ENC_INT32 as u8);
+            out.extend_from_slice(&(n as i32).to_le_bytes());
+        }
+        _ => {
+            write_length(out, string.len());
+            out.extend_from_slice(string);
+        }
+    }
+}
+
+/// Parses a string that is the canonical representation of a 64-bit signed integer.
+fn integer(string: &[u8]) -> Option<i64> {
+    std::str::from_utf8(string)
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok())
+        .filter(|n| n.to_string().as_bytes() == string)
+}
+
+/// Formats a score the way Redis stores it in a listpack, which has to fit in 128 bytes when parsed back.
+fn format_score(score: f64) -> String {
+    let formatted = score.to_string();
+    if formatted.len() > 24 {
+        format!("{score:e}")
+    } else {
+        formatted
+    }
+}
+
+/// A listpack that is being built
+///
+/// A listpack is a 6-byte header, with the total number of bytes as a 32-bit little-endian integer,
+/// and the number of elements as a 16-bit little-endian integer, followed by the elements, and the end marker.
+/// Each element has an encoding byte, which may also hold its integer value or its length, the data,
+/// and its back-length, the number of bytes of the encoding and the data, used to traverse it backwards.
+#[derive(Default)]
+struct Listpack {
+    entries: Vec<u8>,
+    len: usize,
+}
+
+impl Listpack {
+    /// The number of elements
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The total number of bytes, including the header and the end marker
+    fn size(&self) -> usize {
+        LISTPACK_HEADER_LEN + self.entries.len() + 1
+    }
+
+    /// Appends an element, encoded as an integer if it's the canonical representation of one.
+    fn push(&mut self, element: &[u8]) {
+        let out = &mut self.entries;
+        let start = out.len();
+        match integer(element) {
+            Some(n @ 0..=127) => out.push(n as u8),
+            Some(n @ -4096..=4095) => {
+                let n = (n & 0x1fff) as u16;
+                out.extend_from_slice(&[0xc0 | (n >> 8) as u8, n as u8]);
+            }
+            Some(n @ -32768..=32767) => {
+                out.push(0xf1);
+                out.extend_from_slice(&(n as i16).to_le_bytes());
+            }
+            Some(n @ -8388608..=8388607) => {
+                out.push(0xf2);
+                out.extend_from_slice(&(n as i32).to_le_bytes()[..3]);
+            }
+            Some(n @ -2147483648..=2147483647) => {
+                out.push(0xf3);
+                out.extend_from_slice(&(n as i32).to_le_bytes());
+            }
+            Some(n) => {
+                out.push(0xf4);
+                out.extend_from_slice(&n.to_le_bytes());
+            }
+            None => {
+                match element.len() {
+                    len @ 0..=63 => out.push(0x80 | len as u8),
+                    len @ 64..=4095 => out.extend_from_slice(&[0xe0 | (len >> 8) as u8, len as u8]),
+                    len => {
+                        out.push(0xf0);
+                        out.extend_from_slice(&(len as u32).to_le_bytes());
+                    }
+                }
+                out.extend_from_slice(element);
+            }
+        }
+
+        // The back-length is stored with the most significant 7 bits first, and every byte but the first one
+        // has its most significant bit set.
+        let encoded = out.len() - start;
+        let size = backlen_size(encoded);
+        for i in (0..size).rev() {
+            let digit = (encoded >> (7 * i)) as u8 & 0x7f;
+            out.push(if i == size - 1 { digit } else { digit | 0x80 });
+        }
+        self.len += 1;
+    }
+
+    /// Returns the bytes of the listpack.
+    fn finish(self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.size());
+        out.extend_from_slice(&(self.size() as u32).to_le_bytes());
+        // The number of elements saturates, and then the listpack has to be traversed to count them.
+        out.extend_from_slice(&(self.len.min(u16::MAX as usize) as u16).to_le_bytes());
+        out.extend_from_slice(&self.entries);
+        out.push(LISTPACK_EOF);
+        out
+    }
+}
+
+/// Returns the number of bytes of the back-length of a listpack entry of `encoded` bytes.
+fn backlen_size(encoded: usize) -> usize {
+    match encoded {
+        0..=127 => 1,
+        128..=16382 => 2,
+        16383..=2097150 => 3,
+        2097151..=268435454 => 4,
+        _ => 5,
+    }
+}
+
+/// Returns the elements of a listpack, with integers formatted as strings.
+fn listpack(bytes: &[u8]) -> Result<Vec<Bytes>, CmdError> {
+    let invalid = || CmdError::BadDataFormat;
+    let header = bytes.get(..LISTPACK_HEADER_LEN).ok_or_else(invalid)?;
+    let total = u32::from_le_bytes(header[..4].try_into().expect("The header is 6 bytes"));
+    let count = u16::from_le_bytes(header[4..].try_into().expect("The header is 6 bytes"));
+    if bytes.len() <= LISTPACK_HEADER_LEN
+        || total as usize != bytes.len()
+        || bytes.last() != Some(&LISTPACK_EOF)
+    {
+        return Err(invalid());
+    }
+
+    // The entries are between the header and the end marker.
+    let end = bytes.len() - 1;
+    let mut elements = Vec::new();
+    let mut pos = LISTPACK_HEADER_LEN;
+    while let Some(entry) = bytes.get(pos..end).filter(|entry| !entry.is_empty()) {
+        let first = entry[0];
+        let string = |offset: usize, len: usize| {
+            entry
+                .get(offset..offset + len)
+                .map(|string| (Bytes::copy_from_slice(string), offset + len))
+        };
+        let int = |len: usize| {
+            let mut le = [0; 8];
+            le[..len].copy_from_slice(entry.get(1..1 + len)?);
+            // Shifting the most significant byte into place extends the sign.
+            let shift = 64 - 8 * len as u32;
+            let n = i64::from_le_bytes(le) << shift >> shift;
+            Some((Bytes::from(n.to_string()), 1 + len))
+        };
+        let next = |i: usize| entry.get(i).map(|&byte| byte as usize);
+        let decoded = match first {
+            0x00..=0x7f => Some((Bytes::from(first.to_string()), 1)),
+            0x80..=0xbf => string(1, (first & 0x3f) as usize),
+            0xc0..=0xdf => next(1).map(|low| {
+                let n = ((first & 0x1f) as i64) << 8 | low as i64;
+                let n = if n >= 1 << 12 { n - (1 << 13) } else { n };
+                (Bytes::from(n.to_string()), 2)
+            }),
+            0xe0..=0xef => next(1).and_then(|low| string(2, ((first & 0x0f) as usize) << 8 | low)),
+            0xf0 => entry
+                .get(1..5)
+                .map(|len| u32::from_le_bytes(len.try_into().expect("4 bytes")) as usize)
+                .and_then(|len| string(5, len)),
+            0xf1 => int(2),
+            0xf2 => int(3),
+            0xf3 => int(4),
+            0xf4 => int(8),
+            _ => None,
+        };
+        let (element, encoded) = decoded.ok_or_else(invalid)?;
+        pos += encoded + backlen_size(encoded);
+        elements.push(element);
+    }
+
+    if pos != end || (count != u16::MAX && count as usize != elements.len()) {
+        return Err(invalid());
+    }
+    Ok(elements)
+}
+
+/// Returns a sorted intset of `integers`.
+///
+/// An intset is an 8-byte header, with the width of the integers in bytes and their number,
+/// both as 32-bit little-endian integers, followed by the sorted integers in little-endian order.
+/// The width is the smallest of 2, 4 and 8 that fits all the integers.
+fn intset(mut integers: Vec<i64>) -> Vec<u8> {
+    integers.sort_unstable();
+    let width = integers.iter().fold(2, |width, &n| {
+        if i16::try_from(n).is_ok() {
+            width
+        } else if i32::try_from(n).is_ok() {
+            width.max(4)
+        } else {
+            8
+        }
+    });
+
+    let mut out = Vec::with_capacity(INTSET_HEADER_LEN + width * integers.len());
+    out.extend_from_slice(&(width as u32).to_le_bytes());
+    out.extend_from_slice(&(integers.len() as u32).to_le_bytes());
+    for n in integers {
+        out.extend_from_slice(&n.to_le_bytes()[..width]);
+    }
+    out
+}
+
+/// Returns the members of an intset, formatted as strings.
+fn intset_members(bytes: &[u8]) -> Result<Vec<Bytes>, CmdError> {
+    let invalid = || CmdError::BadDataFormat;
+    let header = bytes.get(..INTSET_HEADER_LEN).ok_or_else(invalid)?;
+    let width = u32::from_le_bytes(header[..4].try_into().expect("The header is 8 bytes")) as usize;
+    let len = u32::from_le_bytes(header[4..].try_into().expect("The header is 8 bytes")) as usize;
+    let integers = &bytes[INTSET_HEADER_LEN..];
+    if !matches!(width, 2 | 4 | 8) || Some(integers.len()) != width.checked_mul(len) {
+        return Err(invalid());
+    }
+
+    Ok(integers
+        .chunks_exact(width)
+        .map(|le| {
+            let mut bytes = [0; 8];
+            bytes[..width].copy_from_slice(le);
+            let shift = 64 - 8 * width as u32;
+            Bytes::from((i64::from_le_bytes(bytes) << shift >> shift).to_string())
+        })
+        .collect())
+}
+
+/// Decompresses LZF data into `len` bytes.
+///
+/// The data is a sequence of chunks, each starting with a control byte:
+/// - `000lllll` - a run of 1 to 32 literal bytes that follow
+/// - `lllooooo oooooooo` - a back-reference to 3 to 8 bytes at the 13-bit offset, plus one, from the end
+/// - `111ooooo llllllll oooooooo` - the same, with 9 to 264 bytes
+fn lzf_decompress(input: &[u8], len: usize) -> Result<Vec<u8>, CmdError> {
+    let invalid = || CmdError::BadDataFormat;
+    let mut out = Vec::with_capacity(len.min(input.len().saturating_mul(16)));
+    let mut input = input.iter().map(|&byte| byte as usize);
+    while let Some(control) = input.next() {
+        if control < 1 << 5 {
+            for _ in 0..=control {
+                out.push(input.next().ok_or_else(invalid)? as u8);
+            }
+        } else {
+            let mut run = control >> 5;
+            if run == 7 {
+                run += input.next().ok_or_else(invalid)?;
+            }
+            let offset = ((control & 0x1f) << 8 | input.next().ok_or_else(invalid)?) + 1;
+            let start = out.len().checked_sub(offset).ok_or_else(invalid)?;
+            // The reference may overlap the bytes it produces, so they're copied one at a time.
+            for i in start..start + run + 2 {
+                out.push(out[i]);
+            }
+        }
+        if out.len() > len {
+            return Err(invalid());
+        }
+    }
+
+    if out.len() != len {
+        return Err(invalid());
+    }
+    Ok(out)
+}
+
+/// Computes the CRC64 (Jones) checksum of `bytes`, which is reflected and starts from 0.
+fn crc64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |crc, &byte| {
+        CRC64_TABLE[((crc ^ byte as u64) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Builds the lookup table of [`crc64`].
+const fn crc64_table() -> [u64; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u64;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ CRC64_POLY
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Appends the footer to the body of a payload.
+    pub(crate) fn payload(body: &[u8], version: u16) -> Vec<u8> {
+        let mut payload = body.to_vec();
+        payload.extend_from_slice(&version.to_le_bytes());
+        let checksum = crc64(&payload);
+        payload.extend_from_slice(&checksum.to_le_bytes());
+        payload
+    }
+
+    fn bytes(strings: &[&str]) -> Vec<Bytes> {
+        strings
+            .iter()
+            .map(|s| Bytes::copy_from_slice(s.as_bytes()))
+            .collect()
+    }
+
+    #[test]
+    fn crc64_check_value() {
+        assert_eq!(crc64(b"123456789"), 0xe9c6d914c4b8d9ca);
+    }
+
+    #[test]
+    fn restore_redis_payloads() {
+        // `SET mykey 10` and `DUMP mykey` in Redis 7.0
+        let dumped = b"\x00\xc0\n\n\x00n\x9fWE\x0e\xaec\xbb";
+        assert_eq!(restore(dumped).unwrap(), Object::String(Bytes::from("10")));
+        assert_eq!(
+            dump(&Object::String(Bytes::from("10")), Encoding::Int).unwrap()[..3],
+            dumped[..3]
+        );
+
+        // A hash of `a` => `1` in a listpack
+        let listpack = [12, 0, 0, 0, 2, 0, 0x81, b'a', 2, 1, 1, LISTPACK_EOF];
+        let mut body = vec![TYPE_HASH_LISTPACK, listpack.len() as u8];
+        body.extend_from_slice(&listpack);
+        let Object::Hash(hash) = restore(&payload(&body, 11)).unwrap() else {
+            panic!("Expected a hash");
+        };
+        assert_eq!(
+            hash,
+            ScanMap::from_iter([(Bytes::from("a"), Bytes::from("1"))])
+        );
+
+        // 25 times `a`, compressed as a literal and a back-reference
+        let body = [TYPE_STRING, 0xc3, 5, 25, 0x00, b'a', 0xe0, 15, 0x00];
+        assert_eq!(
+            restore(&payload(&body, 10)).unwrap(),
+            Object::String(Bytes::from("a".repeat(25)))
+        );
+
+        // A set of integers in a 4-byte intset
+        let intset = [4, 0, 0, 0, 2, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0, 0, 1, 0];
+        let mut body = vec![TYPE_SET_INTSET, intset.len() as u8];
+        body.extend_from_slice(&intset);
+        assert_eq!(
+            restore(&payload(&body, 10)).unwrap(),
+            Object::Set(ScanSet::from_iter(bytes(&["-1", "65536"])))
+        );
+    }
+
+    #[test]
+    fn dump_and_restore_every_encoding() {
+        let big = |n: usize| (0..n).map(|i| format!("element:{i}")).collect::<Vec<_>>();
+        let strings = |strings: &[String]| {
+            strings
+                .iter()
+                .map(|s| Bytes::copy_from_slice(s.as_bytes()))
+                .collect::<Vec<_>>()
+        };
+        let values = [
+            Object::String(Bytes::from("-123")),
+            Object::String(Bytes::from("12345678901")),
+            Object::String(Bytes::from("x".repeat(100))),
+            Object::List(VecDeque::from(bytes(&[
+                "a",
+                "1",
+                "-5000",
+                "70000",
+                "9999999999",
+                "",
+            ]))),
+            Object::List(VecDeque::from(strings(&big(2000)))),
+            Object::Set(ScanSet::from_iter(bytes(&["1", "-40000", "5000000000"]))),
+            Object::Set(ScanSet::from_iter(bytes(&["1", "a"]))),
+            Object::Set(ScanSet::from_iter(strings(&big(200)))),
+            Object::Hash(ScanMap::from_iter(
+                bytes(&["f", "v", "n", "42"])
+                    .chunks(2)
+                    .map(|pair| (pair[0].clone(), pair[1].clone())),
+            )),
+            Object::Hash(ScanMap::from_iter(
+                strings(&big(300))
+                    .chunks(2)
+                    .map(|pair| (pair[0].clone(), pair[1].clone())),
+            )),
+            Object::SortedSet(ScanMap::from_iter([
+                (Bytes::from("a"), 1.5),
+                (Bytes::from("b"), -3.0),
+                (Bytes::from("c"), f64::INFINITY),
+                (Bytes::from("d"), 1e300),
+            ])),
+            Object::SortedSet(ScanMap::from_iter(
+                strings(&big(200))
+                    .into_iter()
+                    .zip((0..).map(|i| i as f64 / 3.0)),
+            )),
+        ];
+        for value in values {
+            let payload = dump(&value, Encoding::of(&value, None)).unwrap();
+            assert_eq!(restore(&payload).unwrap(), value);
+        }
+
+        let stream = Object::Stream(Default::default());
+        assert!(dump(&stream, Encoding::Stream).is_err());
+    }
+
+    #[test]
+    fn restore_truncated_payloads() {
+        let values = [
+            Object::String(Bytes::from("x".repeat(100))),
+            Object::List(VecDeque::from(bytes(&[
+                "a",
+                "-5000",
+                "70000",
+                "9999999999",
+            ]))),
+            Object::Set(ScanSet::from_iter(bytes(&["1", "-40000", "5000000000"]))),
+            Object::Hash(ScanMap::from_iter([(Bytes::from("f"), Bytes::from("v"))])),
+            Object::SortedSet(ScanMap::from_iter([(Bytes::from("a"), 1.5)])),
+        ];
+        for value in values {
+            let dumped = dump(&value, Encoding::of(&value, None)).unwrap();
+            let body = &dumped[..dumped.len() - FOOTER_LEN];
+            for len in 0..body.len() {
+                assert!(restore(&payload(&body[..len], RDB_VERSION)).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn restore_invalid_payloads() {
+        let dumped = dump(&Object::String(Bytes::from("value")), Encoding::Embstr).unwrap();
+        assert!(restore(&dumped).is_ok());
+
+        let mut corrupted = dumped.clone();
+        corrupted[2] ^= 1;
+        assert!(matches!(
+            restore(&corrupted),
+            Err(CmdError::DumpPayloadInvalid)
+        ));
+
+        let newer = payload(&dumped[..dumped.len() - FOOTER_LEN], RDB_VERSION + 1);
+        assert!(matches!(restore(&newer), Err(CmdError::DumpPayloadInvalid)));
+        assert!(matches!(
+            restore(b"short"),
+            Err(CmdError::DumpPayloadInvalid)
+        ));
+
+        for body in [
+            &[TYPE_STRING, 10, b'a'][..],
+            &[TYPE_SET, 0],
+            &[TYPE_SET, 2, 1, b'a', 1, b'a'],
+            &[TYPE_STRING, 1, b'a', 0],
+            &[7, 0],
+            // A listpack that is only a header, whose last byte looks like the end marker
+            &[TYPE_HASH_LISTPACK, 6, 6, 0, 0, 0, 0, LISTPACK_EOF],
+            // A listpack whose only entry runs into the end marker
+            &[
+                TYPE_SET_LISTPACK,
+                9,
+                9,
+                0,
+                0,
+                0,
+                1,
+                0,
+                0x85,
+                b'a',
+                LISTPACK_EOF,
+            ],
+            // A listpack with the end marker in the middle
+            &[
+                TYPE_SET_LISTPACK,
+                10,
+                10,
+                0,
+                0,
+                0,
+                1,
+                0,
+                LISTPACK_EOF,
+                0x01,
+                0x01,
+                LISTPACK_EOF,
+            ],
+            &[TYPE_SET_INTSET, 8, 4, 0, 0, 0, 1, 0, 0, 0],
+            &[TYPE_LIST_QUICKLIST_2, 1, 2, 3, 0xff, 0xff, 0xff],
+            &[TYPE_STRING, 0xc3, 5, 25, 0x00, b'a', 0xe0, 15, 0x05],
+        ] {
+            assert!(matches!(
+                restore(&payload(body, RDB_VERSION)),
+                Err(CmdError::BadDataFormat)
+            ));
+        }
+    }
+}
@@ -12,26 +12,32 @@
 //!   - From [EXPIRE](https://redis.io/docs/latest/commands/expire/):
 //!     "Normally, Redis keys are created without an associated time to live."
 
-use std::collections::{BTreeMap, HashMap};
+use crate::storage::cursor::ScanMap;
+use crate::storage::metadata::Entry;
+use crate::storage::object::Object;
+use bytes::Bytes;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::sync::RwLock;
 
-/// Primary key
-pub type StorageKey = String;
-/// Stored value
-pub type StorageValue = String;
+/// Primary key - a binary-safe byte string
+pub type StorageKey = Bytes;
+/// Stored value - a typed object, such as a string or a list
+pub type StorageValue = Object;
 /// Raw (inner) type of expiration time in milliseconds of an entry in the storage. Relevant only if the time is set.
 pub type ExpirationTimeType = u128;
 /// Expiration time of an entry in the storage. Wraps as an [`Option`] around [`ExpirationTimeType`].
 pub type ExpirationTime = Option<ExpirationTimeType>;
-/// The type of a single row (of a single stored entry): storage value
-pub type StorageEntry = StorageValue;
-/// A concrete in-memory storage implementation of the main key-value store - a hash map
-pub type InMemoryStorageHashMap = HashMap<StorageKey, StorageEntry>;
+/// The type of a single row (of a single stored entry): storage value and its metadata
+pub type StorageEntry = Entry;
+/// A concrete in-memory storage implementation of the main key-value store - a hash map,
+/// which also keeps its keys ordered by [cursor](crate::storage::cursor) position
+pub type InMemoryStorageHashMap = ScanMap<StorageEntry>;
 /// A generic implementation of the auxiliary data structure that's used to store keys' expiration times
 pub type InMemoryExpiryTime<DS> = DS;
-/// A concrete implementation of the auxiliary data structure that's used to store keys' expiration times - a hash map
-pub type InMemoryExpiryTimeHashMap = HashMap<StorageKey, ExpirationTime>;
+/// A concrete implementation of the auxiliary data structure that's used to store keys' expiration times - a hash map,
+/// which also keeps its keys ordered by [cursor](crate::storage::cursor) position
+pub type InMemoryExpiryTimeHashMap = ScanMap<ExpirationTime>;
 /// A concrete implementation of the auxiliary data structure that's used to store keys' expiration times - a b-tree map
 pub type InMemoryExpiryTimeBTreeMap = BTreeMap<StorageKey, ExpirationTime>;
 /// Generic in-memory storage - could be a [`HashMap`] or a [`BTreeMap`] or anything else that resides in memory
@@ -40,3 +46,5 @@ pub type InMemoryStorage<KV, KE> = (KV, KE);
 pub type StorageType<KV, KE> = InMemoryStorage<KV, KE>;
 /// Wrapper around [`StorageType`] which makes it concurrent-safe
 pub type ConcurrentStorageType<KV, KE> = Arc<RwLock<StorageType<KV, KE>>>;
+/// All logical databases, indexed by their number, each of which is locked separately
+pub type ConcurrentDatabases<KV, KE> = Arc<[ConcurrentStorageType<KV, KE>]>;
@@ -40,8 +40,8 @@ run_test()
 
 # PING
 run_test "*1\r\n\$4\r\nPING\r\n"    $'+PONG\r\n' # 1
-run_test "*2\r\n\$4\r\nPING\r\n\$4\r\nPING\r\n"    $'+PONG\r\n+PONG\r\n' # 2
-run_test "*3\r\n\$4\r\nPinG\r\n\$4\r\nping\r\n\$4\r\npINg\r\n"    $'+PONG\r\n+PONG\r\n+PONG\r\n' # 3
+run_test "*1\r\n\$4\r\nPING\r\n*1\r\n\$4\r\nPING\r\n"    $'+PONG\r\n+PONG\r\n' # 2
+run_test "*1\r\n\$4\r\nPinG\r\n*1\r\n\$4\r\nping\r\n*1\r\n\$4\r\npINg\r\n"    $'+PONG\r\n+PONG\r\n+PONG\r\n' # 3
 run_test "*2\r\n\$4\r\nPING\r\n\$5\r\nHello\r\n"    $'$5\r\nHello\r\n' # 4
 run_test "*2\r\n\$4\r\nPing\r\n\$8\r\nTest a B\r\n"    $'$8\r\nTest a B\r\n' # 5
 run_test "*2\r\n\$4\r\nPING\r\n\$13\r\nHello, world!\r\n"    $'$13\r\nHello, world!\r\n' # 6
@@ -49,7 +49,7 @@ run_test "*2\r\n\$4\r\nPING\r\n\$13\r\nHello, world!\r\n"    $'$13\r\nHello, wor
 # ECHO
 run_test "*2\r\n\$4\r\nECHO\r\n\$3\r\nHey\r\n"    $'$3\r\nHey\r\n' # 7
 run_test "*2\r\n\$4\r\nECHO\r\n\$13\r\nHello, world!\r\n"    $'$13\r\nHello, world!\r\n' # 8
-run_test "*4\r\n\$4\r\nEchO\r\n\$3\r\nHey\r\n\$4\r\nEchO\r\n\$3\r\nHey\r\n"    $'$3\r\nHey\r\n$3\r\nHey\r\n' # 9
+run_test "*2\r\n\$4\r\nEchO\r\n\$3\r\nHey\r\n*2\r\n\$4\r\nEchO\r\n\$3\r\nHey\r\n"    $'$3\r\nHey\r\n$3\r\nHey\r\n' # 9
 
 # SET & GET
 run_test "*3\r\n\$3\r\nSET\r\n\$5\r\nKey01\r\n\$7\r\nValue01\r\n"    $'+OK\r\n' # 10